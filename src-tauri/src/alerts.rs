@@ -0,0 +1,628 @@
+//! Threshold alerts on the metrics channels publish (`Channel::metrics`): CPU above 90 % for
+//! 30 s, a disk with under 5 GB free, battery under 15 %, a sensor above 85 °C. Rules live in
+//! `Config::alerts`; the engine holds an internal subscription to every channel a rule watches,
+//! which keeps that channel's producer running — and its rules evaluated on each tick — whether
+//! or not a widget shows it. Internal subscriptions aren't counted in `get_stream_stats`.
+//!
+//! An alert is raised once its condition has held for `for_secs`, and cleared only once the value
+//! is back past the threshold by `hysteresis`, so a value hovering at the threshold doesn't flap.
+//! One whose metric drops out of the payload (a disk unmounted, a sensor gone) is cleared too,
+//! with the last value it had.
+//! `cooldown_secs` is the least time between two raises of the same alert. Both transitions are
+//! emitted (`alert::raised`, `alert::cleared`); on Linux, raises can also show a desktop
+//! notification. Other platforms ignore `notify` and only emit the events.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+use crate::channel::{unix_ms, Channels};
+use crate::events::{self, StreamName};
+
+const TARGET: &str = "alerts";
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum AlertCondition {
+    Above,
+    Below,
+}
+
+impl AlertCondition {
+    fn breached(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Self::Above => value > threshold,
+            Self::Below => value < threshold,
+        }
+    }
+
+    fn recovered(self, value: f32, threshold: f32, hysteresis: f32) -> bool {
+        match self {
+            Self::Above => value <= threshold - hysteresis,
+            Self::Below => value >= threshold + hysteresis,
+        }
+    }
+}
+
+/// One entry of `Config::alerts`. CPU above 90 % for 30 s:
+/// `{ "id": "cpu-busy", "name": "CPU busy", "channel": "cpu", "metric": "global_usage",
+/// "condition": "above", "threshold": 90, "for_secs": 30 }`.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct AlertRule {
+    /// Unique among the rules; carried by the `alert::*` events.
+    pub id: String,
+    /// Shown as the notification's title.
+    pub name: String,
+    pub channel: StreamName,
+    /// A metric name as `get_stream_history` lists them (`global_usage`, `/home/available_space`,
+    /// `hwmon2/temp1/temperature`). `*` matches any run of characters — `*/temperature` watches
+    /// every temperature sensor, each as an alert of its own.
+    pub metric: String,
+    pub condition: AlertCondition,
+    /// In the metric's own unit: percent, bytes, °C.
+    pub threshold: f32,
+    /// How long the condition must hold before the alert is raised.
+    #[serde(default)]
+    pub for_secs: u64,
+    /// How far back past `threshold` the value must go for the alert to clear.
+    #[serde(default)]
+    pub hysteresis: f32,
+    /// Least time between two raises of the same alert.
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    /// Also show a desktop notification when raised. Linux only; elsewhere the alert is still
+    /// emitted, just not shown.
+    #[serde(default)]
+    pub notify: bool,
+}
+
+/// Drops the entries of a config file's `alerts` that don't parse as an [`AlertRule`] (a typo'd
+/// condition, a channel that no longer exists), with a warning, so one bad rule doesn't fail the
+/// whole config — which setup would then replace with the defaults.
+pub fn drop_invalid_rules(config: &mut serde_json::Value) {
+    let Some(alerts) = config.get_mut("alerts") else {
+        return;
+    };
+    let serde_json::Value::Array(rules) = alerts else {
+        tracing::warn!(target: TARGET, "`alerts` is not a list; ignoring it");
+        *alerts = serde_json::Value::Array(Vec::new());
+        return;
+    };
+    rules.retain(|rule| match AlertRule::deserialize(rule) {
+        Ok(_) => true,
+        Err(err) => {
+            tracing::warn!(target: TARGET, rule = %rule["id"], error = %err, "skipping invalid alert rule");
+            false
+        }
+    });
+}
+
+/// Payload of `alert::raised` and `alert::cleared`.
+#[derive(Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct AlertEvent {
+    pub rule_id: String,
+    pub name: String,
+    /// The metric that crossed, e.g. `hwmon2/temp1/temperature` for a `*/temperature` rule.
+    pub metric: String,
+    pub condition: AlertCondition,
+    pub threshold: f32,
+    /// At the moment of the transition.
+    pub value: f32,
+    /// Unix ms.
+    pub at: u64,
+}
+
+/// `*` globbing for [`AlertRule::metric`].
+fn matches(pattern: &str, metric: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = metric.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` at all.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// The engine, managed once in `setup`.
+#[derive(Default)]
+pub struct Alerts(Mutex<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    rules: Vec<AlertRule>,
+    /// Channels this engine holds an internal subscription to.
+    watched: HashSet<StreamName>,
+    /// Keyed by rule id and concrete metric.
+    tracked: HashMap<(String, String), Tracked>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    breached_since: Option<u64>,
+    raised: Option<AlertEvent>,
+    last_raised_at: Option<u64>,
+}
+
+/// What one tick changed, emitted by `Alerts` once its lock is released.
+#[derive(Default)]
+struct Transitions {
+    /// With the rule's `notify`.
+    raised: Vec<(AlertEvent, bool)>,
+    cleared: Vec<AlertEvent>,
+}
+
+impl Inner {
+    fn evaluate(&mut self, name: StreamName, at: u64, metrics: &[(String, f32)]) -> Transitions {
+        let mut transitions = Transitions::default();
+        let Inner { rules, tracked, .. } = self;
+        for rule in rules.iter().filter(|r| r.channel == name) {
+            for (metric, value) in metrics.iter().filter(|(m, _)| matches(&rule.metric, m)) {
+                let value = *value;
+                let state = tracked
+                    .entry((rule.id.clone(), metric.clone()))
+                    .or_default();
+                match state.raised.take() {
+                    Some(active) => {
+                        if rule
+                            .condition
+                            .recovered(value, rule.threshold, rule.hysteresis)
+                        {
+                            state.breached_since = None;
+                            transitions.cleared.push(AlertEvent {
+                                value,
+                                at,
+                                ..active
+                            });
+                        } else {
+                            state.raised = Some(active);
+                        }
+                    }
+                    None if rule.condition.breached(value, rule.threshold) => {
+                        let since = *state.breached_since.get_or_insert(at);
+                        let held = at.saturating_sub(since) >= rule.for_secs * 1000;
+                        let cooled = state.last_raised_at.is_none_or(|last| {
+                            at.saturating_sub(last) >= rule.cooldown_secs * 1000
+                        });
+                        if held && cooled {
+                            let alert = AlertEvent {
+                                rule_id: rule.id.clone(),
+                                name: rule.name.clone(),
+                                metric: metric.clone(),
+                                condition: rule.condition,
+                                threshold: rule.threshold,
+                                value,
+                                at,
+                            };
+                            state.raised = Some(alert.clone());
+                            state.last_raised_at = Some(at);
+                            transitions.raised.push((alert, rule.notify));
+                        }
+                    }
+                    None => state.breached_since = None,
+                }
+            }
+        }
+
+        let watching: HashSet<&str> = rules
+            .iter()
+            .filter(|r| r.channel == name)
+            .map(|r| r.id.as_str())
+            .collect();
+        tracked.retain(|(id, metric), state| {
+            let keep = !watching.contains(id.as_str()) || metrics.iter().any(|(m, _)| m == metric);
+            if !keep {
+                if let Some(raised) = state.raised.take() {
+                    transitions.cleared.push(AlertEvent { at, ..raised });
+                }
+            }
+            keep
+        });
+        transitions
+    }
+}
+
+impl Alerts {
+    /// Replaces the rules, subscribing to newly watched channels and releasing ones no rule
+    /// watches any more. Alerts of unchanged rules keep their state; those of removed or edited
+    /// rules are cleared.
+    pub fn configure(&self, app: &AppHandle, rules: Vec<AlertRule>) {
+        let channels = app.state::<Channels>();
+        let mut cleared = Vec::new();
+        {
+            let Ok(mut inner) = self.0.lock() else {
+                return;
+            };
+            let wanted: HashSet<StreamName> = rules.iter().map(|r| r.channel).collect();
+            for name in wanted.difference(&inner.watched) {
+                channels.subscribe_internal(*name, None);
+            }
+            for name in inner.watched.difference(&wanted) {
+                channels.unsubscribe_internal(*name, None);
+            }
+
+            let unchanged: HashSet<String> = rules
+                .iter()
+                .filter(|rule| inner.rules.contains(rule))
+                .map(|rule| rule.id.clone())
+                .collect();
+            let now = unix_ms();
+            inner.tracked.retain(|(id, _), tracked| {
+                let keep = unchanged.contains(id);
+                if !keep {
+                    if let Some(raised) = tracked.raised.take() {
+                        cleared.push(AlertEvent { at: now, ..raised });
+                    }
+                }
+                keep
+            });
+            tracing::info!(target: TARGET, rules = rules.len(), channels = wanted.len(), "alert rules configured");
+            inner.rules = rules;
+            inner.watched = wanted;
+        }
+        for alert in cleared {
+            events::emit_alert_cleared(app, &alert);
+        }
+    }
+
+    /// Checks one published tick of `name` against the rules. Called from `Channels`' publish
+    /// path for every stream with metrics.
+    pub fn evaluate(&self, app: &AppHandle, name: StreamName, at: u64, metrics: &[(String, f32)]) {
+        let Transitions { raised, cleared } = match self.0.lock() {
+            Ok(mut inner) => inner.evaluate(name, at, metrics),
+            Err(_) => return,
+        };
+        for alert in cleared {
+            tracing::info!(target: TARGET, rule = %alert.rule_id, metric = %alert.metric, value = alert.value, "alert cleared");
+            events::emit_alert_cleared(app, &alert);
+        }
+        for (alert, notify) in raised {
+            tracing::info!(target: TARGET, rule = %alert.rule_id, metric = %alert.metric, value = alert.value, "alert raised");
+            events::emit_alert_raised(app, &alert);
+            if notify {
+                tauri::async_runtime::spawn(notify::show(alert));
+            }
+        }
+    }
+
+    fn active(&self) -> Vec<AlertEvent> {
+        let Ok(inner) = self.0.lock() else {
+            return Vec::new();
+        };
+        let mut active: Vec<AlertEvent> = inner
+            .tracked
+            .values()
+            .filter_map(|t| t.raised.clone())
+            .collect();
+        active.sort_by_key(|a| a.at);
+        active
+    }
+}
+
+/// Currently raised alerts, oldest first, so a reloaded window can show them without waiting for
+/// the next transition.
+#[tauri::command]
+pub async fn get_active_alerts(app: AppHandle) -> Result<Vec<AlertEvent>, String> {
+    Ok(app.state::<Alerts>().active())
+}
+
+/// Replaces `Config::alerts` and applies the new rules at once.
+#[tauri::command]
+pub async fn set_alert_rules(rules: Vec<AlertRule>, app: AppHandle) -> Result<(), String> {
+    tracing::debug!(target: TARGET, rules = rules.len(), "invoke: set_alert_rules");
+    if let Some(rule) = rules
+        .iter()
+        .find(|r| rules.iter().filter(|o| o.id == r.id).count() > 1)
+    {
+        return Err(format!("duplicate alert rule id `{}`", rule.id));
+    }
+    let state = app.state::<crate::AppState>();
+    let mut state = state.lock().await;
+    state.config.alerts = rules.clone();
+    crate::config::write_config(&state.config).map_err(|e| {
+        tracing::error!(target: TARGET, error = %e, "failed to write config");
+        e.to_string()
+    })?;
+    events::emit_config_changed(&app, &state.config);
+    drop(state);
+    app.state::<Alerts>().configure(&app, rules);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod notify {
+    use std::collections::HashMap;
+    use zbus::zvariant::Value;
+
+    use super::{AlertCondition, AlertEvent, TARGET};
+
+    /// Through the freedesktop notification service on the session bus.
+    pub async fn show(alert: AlertEvent) {
+        let relation = match alert.condition {
+            AlertCondition::Above => "above",
+            AlertCondition::Below => "below",
+        };
+        let body = format!(
+            "{} is {} ({relation} {})",
+            alert.metric, alert.value, alert.threshold
+        );
+        let result = async {
+            let conn = zbus::Connection::session().await?;
+            let hints: HashMap<&str, Value> = HashMap::from([("urgency", Value::U8(1))]);
+            conn.call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.Notifications"),
+                "Notify",
+                &(
+                    "desk-disp",
+                    0u32,
+                    "dialog-warning",
+                    alert.name.as_str(),
+                    body.as_str(),
+                    Vec::<&str>::new(),
+                    hints,
+                    -1i32,
+                ),
+            )
+            .await?;
+            zbus::Result::Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(target: TARGET, rule = %alert.rule_id, error = %err, "failed to show notification");
+        }
+    }
+}
+
+/// No notification backend outside Linux yet (Windows would need a toast through WinRT); the
+/// alert is still emitted, only not shown.
+#[cfg(not(target_os = "linux"))]
+mod notify {
+    use super::{AlertEvent, TARGET};
+
+    pub async fn show(alert: AlertEvent) {
+        tracing::debug!(target: TARGET, rule = %alert.rule_id, "desktop notifications are only implemented on Linux");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_config(json: &str) -> crate::config::Config {
+        let mut value = serde_json::from_str(json).unwrap();
+        drop_invalid_rules(&mut value);
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn invalid_rules_are_dropped_not_fatal() {
+        let config = parse_config(
+            r#"{ "monitor": "DP-1", "alerts": [
+                { "id": "hot", "name": "Hot", "channel": "sensors", "metric": "*/temperature",
+                  "condition": "above", "threshold": 85 },
+                { "id": "typo", "name": "Typo", "channel": "cpu", "metric": "global_usage",
+                  "condition": "gt", "threshold": 90 }
+            ] }"#,
+        );
+        assert_eq!(config.monitor.as_deref(), Some("DP-1"));
+        let ids: Vec<&str> = config.alerts.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["hot"]);
+
+        let config = parse_config(r#"{ "monitor": "DP-1", "alerts": {} }"#);
+        assert_eq!(config.monitor.as_deref(), Some("DP-1"));
+        assert!(config.alerts.is_empty());
+    }
+
+    fn rule(metric: &str, condition: AlertCondition, threshold: f32) -> AlertRule {
+        AlertRule {
+            id: "rule".into(),
+            name: "Rule".into(),
+            channel: StreamName::Cpu,
+            metric: metric.into(),
+            condition,
+            threshold,
+            for_secs: 0,
+            hysteresis: 0.0,
+            cooldown_secs: 0,
+            notify: false,
+        }
+    }
+
+    fn engine(rule: AlertRule) -> Inner {
+        Inner {
+            rules: vec![rule],
+            ..Default::default()
+        }
+    }
+
+    type Reading = (String, f32);
+
+    /// `(metric, value)` of the raised and of the cleared alerts of one tick at `at` seconds.
+    fn tick(inner: &mut Inner, at: u64, metrics: &[(&str, f32)]) -> (Vec<Reading>, Vec<Reading>) {
+        let metrics: Vec<(String, f32)> =
+            metrics.iter().map(|(m, v)| (m.to_string(), *v)).collect();
+        let Transitions { raised, cleared } = inner.evaluate(StreamName::Cpu, at * 1000, &metrics);
+        (
+            raised
+                .into_iter()
+                .map(|(a, _)| (a.metric, a.value))
+                .collect(),
+            cleared.into_iter().map(|a| (a.metric, a.value)).collect(),
+        )
+    }
+
+    fn usage(value: f32) -> Reading {
+        ("global_usage".to_string(), value)
+    }
+
+    #[test]
+    fn raises_only_once_the_condition_has_held_for_secs() {
+        let mut inner = engine(AlertRule {
+            for_secs: 30,
+            ..rule("global_usage", AlertCondition::Above, 90.0)
+        });
+        assert_eq!(
+            tick(&mut inner, 0, &[("global_usage", 95.0)]),
+            (vec![], vec![])
+        );
+        // A dip starts the wait over.
+        assert_eq!(
+            tick(&mut inner, 20, &[("global_usage", 50.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 30, &[("global_usage", 95.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 59, &[("global_usage", 96.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 60, &[("global_usage", 97.0)]),
+            (vec![usage(97.0)], vec![])
+        );
+        // Raised once, not on every tick it stays breached.
+        assert_eq!(
+            tick(&mut inner, 61, &[("global_usage", 97.0)]),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn clears_only_past_the_hysteresis_band() {
+        let mut inner = engine(AlertRule {
+            hysteresis: 5.0,
+            ..rule("global_usage", AlertCondition::Above, 90.0)
+        });
+        assert_eq!(
+            tick(&mut inner, 0, &[("global_usage", 91.0)]).0,
+            [usage(91.0)]
+        );
+        assert_eq!(
+            tick(&mut inner, 1, &[("global_usage", 89.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 2, &[("global_usage", 85.5)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 3, &[("global_usage", 85.0)]),
+            (vec![], vec![usage(85.0)])
+        );
+
+        let mut inner = engine(AlertRule {
+            hysteresis: 2.0,
+            ..rule("global_usage", AlertCondition::Below, 15.0)
+        });
+        assert_eq!(
+            tick(&mut inner, 0, &[("global_usage", 10.0)]).0,
+            [usage(10.0)]
+        );
+        assert_eq!(
+            tick(&mut inner, 1, &[("global_usage", 16.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 2, &[("global_usage", 17.0)]).1,
+            [usage(17.0)]
+        );
+    }
+
+    #[test]
+    fn cooldown_holds_back_a_raise() {
+        let mut inner = engine(AlertRule {
+            cooldown_secs: 60,
+            ..rule("global_usage", AlertCondition::Above, 90.0)
+        });
+        assert_eq!(
+            tick(&mut inner, 0, &[("global_usage", 95.0)]).0,
+            [usage(95.0)]
+        );
+        assert_eq!(
+            tick(&mut inner, 10, &[("global_usage", 50.0)]).1,
+            [usage(50.0)]
+        );
+        assert_eq!(
+            tick(&mut inner, 20, &[("global_usage", 95.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 59, &[("global_usage", 95.0)]),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            tick(&mut inner, 60, &[("global_usage", 96.0)]).0,
+            [usage(96.0)]
+        );
+    }
+
+    #[test]
+    fn globs_match_any_run_of_characters() {
+        assert!(matches("global_usage", "global_usage"));
+        assert!(!matches("global_usage", "global_usage_max"));
+        assert!(matches("*/temperature", "hwmon2/temp1/temperature"));
+        assert!(!matches("*/temperature", "hwmon2/temp1/critical"));
+        assert!(matches(
+            "hwmon*/temp*/temperature",
+            "hwmon2/temp1/temperature"
+        ));
+        assert!(!matches("hwmon*/fan*/speed", "hwmon2/temp1/temperature"));
+        assert!(matches("*", ""));
+
+        // Each matching metric is an alert of its own.
+        let mut inner = engine(rule("*/temperature", AlertCondition::Above, 85.0));
+        let (raised, _) = tick(
+            &mut inner,
+            0,
+            &[
+                ("hwmon2/temp1/temperature", 90.0),
+                ("hwmon2/temp1/critical", 100.0),
+                ("hwmon3/temp2/temperature", 70.0),
+                ("hwmon3/temp3/temperature", 88.0),
+            ],
+        );
+        let expected = [
+            ("hwmon2/temp1/temperature".to_string(), 90.0),
+            ("hwmon3/temp3/temperature".to_string(), 88.0),
+        ];
+        assert_eq!(raised, expected);
+    }
+
+    #[test]
+    fn clears_with_the_last_value_when_the_metric_disappears() {
+        let mut inner = engine(rule("*/available_space", AlertCondition::Below, 5e9));
+        let (raised, _) = tick(
+            &mut inner,
+            0,
+            &[
+                ("/mnt/usb/available_space", 1e9),
+                ("/home/available_space", 8e10),
+            ],
+        );
+        assert_eq!(raised, [("/mnt/usb/available_space".to_string(), 1e9)]);
+        let (_, cleared) = tick(&mut inner, 1, &[("/home/available_space", 8e10)]);
+        assert_eq!(cleared, [("/mnt/usb/available_space".to_string(), 1e9)]);
+        assert!(inner
+            .tracked
+            .keys()
+            .all(|(_, metric)| metric == "/home/available_space"));
+    }
+}
@@ -0,0 +1,789 @@
+//! Subscription channels (`stream::<name>` events). Each stream is a struct implementing
+//! [`Channel`]: it owns its producer — a tokio task or a dedicated thread — and a [`ChannelCore`]
+//! holding everything the IPC side needs (subscriber count, last emitted value, start/stop
+//! counters). Registering one is a single `app.manage_channel(SomeChannel::new(..))` in
+//! `lib.rs::run`; `subscribe_channel`/`unsubscribe_channel`/`get_stream_stats` find it through
+//! the [`Channels`] registry by its [`StreamName`].
+//!
+//! Channels with graphable values also keep a short history of them per metric, so a graph can
+//! start full after a window reload or a widget remount (`get_stream_history`).
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use ts_rs::TS;
+
+use crate::alerts::Alerts;
+use crate::events::StreamName;
+use crate::recording::Recorder;
+
+const TARGET: &str = "channel";
+
+/// Floor for subscriber-requested intervals, so one widget can't make a producer spin.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+pub trait Channel: Send + Sync + 'static {
+    /// What the producer emits as `stream::<name>`.
+    type Payload: Serialize + Clone + Send + 'static;
+    const NAME: StreamName;
+
+    fn core(&self) -> &ChannelCore;
+
+    /// Spawns the producer. Called once when the channel is managed, and again by `reset`.
+    /// Producers run for the life of the app and gate their own work on subscribers through
+    /// [`ChannelCore::gate`].
+    fn start(&self, app: &AppHandle);
+
+    /// Stops the producer; a no-op if it isn't running.
+    fn stop(&self);
+
+    /// Adds a subscriber and returns the new count. `interval` is how often the subscriber
+    /// wants updates; `None` takes the channel's default.
+    fn subscribe(&self, interval: Option<Duration>) -> usize {
+        self.core().subscribe(interval)
+    }
+
+    /// Removes a subscriber; `interval` should match what it subscribed with.
+    fn unsubscribe(&self, interval: Option<Duration>) {
+        self.core().unsubscribe(interval)
+    }
+
+    /// Drops whatever per-subscriber state a departing subscriber registered under `id` (a
+    /// visualizer config, say), so it doesn't outlive the widget. The default has none.
+    fn release(&self, _app: &AppHandle, _id: &str) {}
+
+    /// The last payload published, so a new subscriber can render before the next tick.
+    fn last_value(&self) -> Option<serde_json::Value> {
+        self.core().last_value()
+    }
+
+    fn stats(&self) -> StreamStats {
+        self.core().stats()
+    }
+
+    /// Dev mode only: everything the channel knows about itself, for the devtools console.
+    fn dump(&self) -> serde_json::Value {
+        serde_json::json!({
+            "stats": self.stats(),
+            "requested_intervals_ms": self.core().requested_intervals_ms(),
+            "last_value": self.last_value(),
+        })
+    }
+
+    /// Dev mode only: restarts the producer from scratch with an empty cache and zeroed
+    /// counters. Subscribers are kept — their widgets are still mounted.
+    fn reset(&self, app: &AppHandle) {
+        self.stop();
+        self.core().reset();
+        self.start(app);
+    }
+
+    /// The numeric values in `payload` worth keeping a history of and alerting on, as
+    /// `(metric, value)` pairs.
+    ///
+    /// Metric names mirror the payload's fields, prefixed with the entity they belong to where
+    /// there are several (`eth0/received_per_sec`, `cpu3/usage`). The default records nothing.
+    fn metrics(_payload: &Self::Payload) -> Vec<(String, f32)>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    /// Caches `payload` as the channel's last value, records its [`metrics`](Channel::metrics)
+    /// and emits it as `stream::<name>`.
+    fn publish(app: &AppHandle, payload: Self::Payload)
+    where
+        Self: Sized,
+    {
+        let metrics = Self::metrics(&payload);
+        publish_value(app, Self::NAME, payload, metrics);
+    }
+}
+
+/// Object-safe view of a [`Channel`], for the registry.
+trait AnyChannel: Send + Sync {
+    fn core(&self) -> &ChannelCore;
+    fn subscribe(&self, interval: Option<Duration>) -> usize;
+    fn unsubscribe(&self, interval: Option<Duration>);
+    fn release(&self, app: &AppHandle, id: &str);
+    fn last_value(&self) -> Option<serde_json::Value>;
+    fn stats(&self) -> StreamStats;
+    fn dump(&self) -> serde_json::Value;
+    fn reset(&self, app: &AppHandle);
+}
+
+impl<C: Channel> AnyChannel for C {
+    fn core(&self) -> &ChannelCore {
+        Channel::core(self)
+    }
+    fn subscribe(&self, interval: Option<Duration>) -> usize {
+        Channel::subscribe(self, interval)
+    }
+    fn unsubscribe(&self, interval: Option<Duration>) {
+        Channel::unsubscribe(self, interval)
+    }
+    fn release(&self, app: &AppHandle, id: &str) {
+        Channel::release(self, app, id)
+    }
+    fn last_value(&self) -> Option<serde_json::Value> {
+        Channel::last_value(self)
+    }
+    fn stats(&self) -> StreamStats {
+        Channel::stats(self)
+    }
+    fn dump(&self) -> serde_json::Value {
+        Channel::dump(self)
+    }
+    fn reset(&self, app: &AppHandle) {
+        Channel::reset(self, app)
+    }
+}
+
+/* Registry  */
+
+/// Every managed channel, by name. Managed once in `lib.rs::run` before any channel.
+pub struct Channels {
+    channels: RwLock<HashMap<StreamName, Arc<dyn AnyChannel>>>,
+    linger: Duration,
+    history_len: usize,
+}
+
+impl Channels {
+    /// `linger` is how long producers keep running after their last subscriber leaves — see
+    /// [`SubscriberGate`]. `history_len` is how many samples each metric's history keeps; 0
+    /// keeps none.
+    pub fn new(linger: Duration, history_len: usize) -> Self {
+        Self {
+            channels: RwLock::new(HashMap::new()),
+            linger,
+            history_len,
+        }
+    }
+
+    fn get(&self, name: StreamName) -> Option<Arc<dyn AnyChannel>> {
+        self.channels.read().ok()?.get(&name).cloned()
+    }
+
+    fn all(&self) -> Vec<Arc<dyn AnyChannel>> {
+        let mut all: Vec<_> = self
+            .channels
+            .read()
+            .map(|map| map.values().cloned().collect())
+            .unwrap_or_default();
+        all.sort_by_key(|c| c.core().name().as_str());
+        all
+    }
+
+    /// Adds a subscriber from webview `webview` to `name` and returns the new count; 0 for an
+    /// unregistered channel.
+    pub fn subscribe(&self, name: StreamName, interval: Option<Duration>, webview: &str) -> usize {
+        let Some(c) = self.get(name) else {
+            return 0;
+        };
+        c.core().count_webview(webview, true);
+        c.subscribe(interval)
+    }
+
+    pub fn unsubscribe(&self, name: StreamName, interval: Option<Duration>, webview: &str) {
+        if let Some(c) = self.get(name) {
+            c.core().count_webview(webview, false);
+            c.unsubscribe(interval);
+        }
+    }
+
+    /// Labels of the webviews subscribed to `name`, with how many subscriptions each holds.
+    pub fn webviews(&self, name: StreamName) -> HashMap<String, usize> {
+        self.get(name)
+            .and_then(|c| c.core().0.webviews.lock().ok().map(|w| w.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Forgets which channels webview `label` subscribed to. Called when it reloads: its widgets
+    /// vanish without unsubscribing, and the reloaded page subscribes afresh.
+    pub fn forget_webview(&self, label: &str) {
+        for c in self.all() {
+            if let Ok(mut webviews) = c.core().0.webviews.lock() {
+                webviews.remove(label);
+            }
+        }
+    }
+
+    /// Subscribes on the backend's own behalf (alert rules, metric recording) rather than a
+    /// widget's. It keeps the producer running like any other subscriber, but isn't counted in
+    /// `get_stream_stats`, whose counts are of widgets.
+    pub fn subscribe_internal(&self, name: StreamName, interval: Option<Duration>) {
+        if let Some(c) = self.get(name) {
+            c.subscribe(interval);
+            c.core().count_internal(true);
+        }
+    }
+
+    pub fn unsubscribe_internal(&self, name: StreamName, interval: Option<Duration>) {
+        if let Some(c) = self.get(name) {
+            c.unsubscribe(interval);
+            c.core().count_internal(false);
+        }
+    }
+
+    pub fn release(&self, app: &AppHandle, name: StreamName, id: &str) {
+        if let Some(c) = self.get(name) {
+            c.release(app, id);
+        }
+    }
+
+    pub fn last_value(&self, name: StreamName) -> Option<serde_json::Value> {
+        self.get(name)?.last_value()
+    }
+
+    /// `name`'s recorded metrics, each with only the samples newer than `since` (Unix ms).
+    pub fn history(
+        &self,
+        name: StreamName,
+        since: Option<u64>,
+    ) -> Result<Vec<MetricSeries>, String> {
+        let channel = self
+            .get(name)
+            .ok_or_else(|| format!("channel `{name}` is not registered"))?;
+        Ok(channel.core().history(since))
+    }
+
+    /// One row per channel, sorted by name.
+    pub fn stats(&self) -> Vec<StreamStats> {
+        self.all().iter().map(|c| c.stats()).collect()
+    }
+
+    pub fn dump(&self) -> serde_json::Value {
+        self.all()
+            .iter()
+            .map(|c| (c.core().name().as_str().to_string(), c.dump()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Restarts `name`'s producer (see [`Channel::reset`]). Blocks until a thread producer has
+    /// wound down, so call it off the async runtime.
+    pub fn reset(&self, app: &AppHandle, name: StreamName) -> Result<(), String> {
+        let channel = self
+            .get(name)
+            .ok_or_else(|| format!("channel `{name}` is not registered"))?;
+        tracing::info!(target: TARGET, stream = %name, "resetting channel");
+        channel.reset(app);
+        Ok(())
+    }
+}
+
+/// Registers a channel with the app and starts its producer.
+pub trait ManageChannel {
+    fn manage_channel<C: Channel>(&self, channel: C);
+}
+
+impl ManageChannel for tauri::App {
+    fn manage_channel<C: Channel>(&self, channel: C) {
+        let channel = Arc::new(channel);
+        if let Ok(mut map) = self.state::<Channels>().channels.write() {
+            map.insert(C::NAME, channel.clone());
+        }
+        channel.start(self.handle());
+    }
+}
+
+/// Serializes `payload` into `name`'s last-value cache, appends `metrics` to its history (and the
+/// long-term recording, if on), checks them against the alert rules and emits it as
+/// `stream::<name>`. Generic over the payload type so every stream shares one implementation;
+/// producers call it through [`Channel::publish`], which pins the payload to the channel's
+/// declared type.
+fn publish_value<T: Serialize + Clone>(
+    app: &AppHandle,
+    name: StreamName,
+    payload: T,
+    metrics: Vec<(String, f32)>,
+) {
+    let channels = app.state::<Channels>();
+    if let Some(channel) = channels.get(name) {
+        if let Ok(value) = serde_json::to_value(&payload) {
+            channel.core().set_last_value(value);
+        }
+        let at = unix_ms();
+        if let Some(recorder) = app.try_state::<Recorder>() {
+            recorder.record(name, at, &metrics);
+        }
+        app.state::<Alerts>().evaluate(app, name, at, &metrics);
+        channel
+            .core()
+            .record_metrics(at, metrics, channels.history_len);
+    }
+    let _ = app.emit(&name.event_name(), payload);
+}
+
+/// Wall-clock now in Unix ms, the timebase of metric history and recording.
+pub fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/* Shared per-channel state  */
+
+/// The IPC-facing half of a channel, shared (cheaply cloned) between the channel struct and its
+/// producer.
+#[derive(Clone)]
+pub struct ChannelCore(Arc<CoreInner>);
+
+struct CoreInner {
+    name: StreamName,
+    subscribers: AtomicUsize,
+    /// Each live subscriber's requested interval (`None` for the channel default); the count
+    /// above mirrors its length so the per-tick gate check stays lock-free.
+    intervals: Mutex<Vec<Option<Duration>>>,
+    /// How many of those subscribers are the backend's own ([`Channels::subscribe_internal`]).
+    internal: AtomicUsize,
+    /// Widget subscribers per webview label, for streams delivered to each webview separately
+    /// (the visualizer's frames).
+    webviews: Mutex<HashMap<String, usize>>,
+    intervals_changed: tokio::sync::Notify,
+    last_value: Mutex<Option<serde_json::Value>>,
+    history: Mutex<MetricHistory>,
+    lifecycle: Mutex<Lifecycle>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Lifecycle {
+    running: bool,
+    created: u64,
+    destroyed: u64,
+}
+
+impl ChannelCore {
+    pub fn new(name: StreamName) -> Self {
+        Self(Arc::new(CoreInner {
+            name,
+            subscribers: AtomicUsize::new(0),
+            intervals: Mutex::new(Vec::new()),
+            internal: AtomicUsize::new(0),
+            webviews: Mutex::new(HashMap::new()),
+            intervals_changed: tokio::sync::Notify::new(),
+            last_value: Mutex::new(None),
+            history: Mutex::new(MetricHistory::default()),
+            lifecycle: Mutex::new(Lifecycle::default()),
+        }))
+    }
+
+    pub fn name(&self) -> StreamName {
+        self.0.name
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.0.subscribers.load(Ordering::Relaxed)
+    }
+
+    fn subscribe(&self, interval: Option<Duration>) -> usize {
+        let Ok(mut intervals) = self.0.intervals.lock() else {
+            return 0;
+        };
+        intervals.push(interval.map(|i| i.max(MIN_INTERVAL)));
+        self.0.subscribers.store(intervals.len(), Ordering::Relaxed);
+        self.0.intervals_changed.notify_one();
+        intervals.len()
+    }
+
+    fn unsubscribe(&self, interval: Option<Duration>) {
+        let Ok(mut intervals) = self.0.intervals.lock() else {
+            return;
+        };
+        let interval = interval.map(|i| i.max(MIN_INTERVAL));
+        // A mismatched interval still has to drop *some* subscriber, or the count never
+        // returns to zero.
+        match intervals.iter().position(|i| *i == interval) {
+            Some(index) => {
+                intervals.swap_remove(index);
+            }
+            None => {
+                intervals.pop();
+            }
+        }
+        self.0.subscribers.store(intervals.len(), Ordering::Relaxed);
+        self.0.intervals_changed.notify_one();
+    }
+
+    fn count_internal(&self, added: bool) {
+        let internal = &self.0.internal;
+        if added {
+            internal.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let _ =
+                internal.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        }
+    }
+
+    fn count_webview(&self, label: &str, added: bool) {
+        let Ok(mut webviews) = self.0.webviews.lock() else {
+            return;
+        };
+        if added {
+            *webviews.entry(label.to_string()).or_default() += 1;
+        } else if let Some(count) = webviews.get_mut(label) {
+            *count -= 1;
+            if *count == 0 {
+                webviews.remove(label);
+            }
+        }
+    }
+
+    /// How often the producer should update: the fastest rate any live subscriber asked for,
+    /// `default` for subscribers that didn't ask, and `default` while there are none.
+    pub fn interval(&self, default: Duration) -> Duration {
+        self.0
+            .intervals
+            .lock()
+            .ok()
+            .and_then(|intervals| intervals.iter().map(|i| i.unwrap_or(default)).min())
+            .unwrap_or(default)
+    }
+
+    /// Resolves after the set of requested intervals changes.
+    pub async fn interval_changed(&self) {
+        self.0.intervals_changed.notified().await
+    }
+
+    fn requested_intervals_ms(&self) -> Vec<Option<u64>> {
+        self.0
+            .intervals
+            .lock()
+            .map(|intervals| {
+                intervals
+                    .iter()
+                    .map(|i| i.map(|i| i.as_millis() as u64))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn last_value(&self) -> Option<serde_json::Value> {
+        self.0.last_value.lock().ok()?.clone()
+    }
+
+    fn set_last_value(&self, value: serde_json::Value) {
+        if let Ok(mut last) = self.0.last_value.lock() {
+            *last = Some(value);
+        }
+    }
+
+    fn record_metrics(&self, at: u64, metrics: Vec<(String, f32)>, capacity: usize) {
+        if metrics.is_empty() || capacity == 0 {
+            return;
+        }
+        if let Ok(mut history) = self.0.history.lock() {
+            history.record(at, metrics, capacity);
+        }
+    }
+
+    fn history(&self, since: Option<u64>) -> Vec<MetricSeries> {
+        self.0
+            .history
+            .lock()
+            .map(|history| history.since(since))
+            .unwrap_or_default()
+    }
+
+    fn stats(&self) -> StreamStats {
+        let lifecycle = self.0.lifecycle.lock().map(|l| *l).unwrap_or_default();
+        StreamStats {
+            channel: self.0.name,
+            subscribers: self
+                .subscribers()
+                .saturating_sub(self.0.internal.load(Ordering::Relaxed)),
+            running: lifecycle.running,
+            created: lifecycle.created,
+            destroyed: lifecycle.destroyed,
+        }
+    }
+
+    fn record(&self, started: bool) {
+        if let Ok(mut lifecycle) = self.0.lifecycle.lock() {
+            lifecycle.running = started;
+            if started {
+                lifecycle.created += 1;
+            } else {
+                lifecycle.destroyed += 1;
+            }
+        }
+    }
+
+    fn reset(&self) {
+        if let Ok(mut last) = self.0.last_value.lock() {
+            *last = None;
+        }
+        if let Ok(mut history) = self.0.history.lock() {
+            *history = MetricHistory::default();
+        }
+        if let Ok(mut lifecycle) = self.0.lifecycle.lock() {
+            *lifecycle = Lifecycle::default();
+        }
+    }
+
+    /// A subscriber gate for this channel's producer, with the app-wide linger.
+    pub fn gate(&self, app: &AppHandle) -> SubscriberGate {
+        SubscriberGate {
+            core: self.clone(),
+            linger: app.state::<Channels>().linger,
+            running: false,
+            idle_since: None,
+        }
+    }
+}
+
+/// One stream's row in `get_stream_stats`.
+#[derive(Serialize, TS, Clone, Debug)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct StreamStats {
+    pub channel: StreamName,
+    /// Widgets subscribed. The backend's own subscriptions (alert rules, metric recording) keep
+    /// the producer running too, but aren't counted.
+    pub subscribers: usize,
+    /// Whether the producer is currently running (subscribed, or lingering).
+    pub running: bool,
+    /// Times the producer has started since launch — for the visualizer, each one opens a
+    /// new audio capture.
+    pub created: u64,
+    /// Times it has stopped. Both climbing together on every edit-mode toggle means the linger
+    /// is too short.
+    pub destroyed: u64,
+}
+
+/* Metric history  */
+
+/// Ring buffers of a channel's recent metric values, one per metric.
+#[derive(Default)]
+struct MetricHistory {
+    /// When each tick still in the window was recorded, oldest first.
+    ticks: VecDeque<u64>,
+    series: BTreeMap<String, VecDeque<(u64, f32)>>,
+}
+
+impl MetricHistory {
+    fn record(&mut self, at: u64, metrics: Vec<(String, f32)>, capacity: usize) {
+        self.ticks.push_back(at);
+        while self.ticks.len() > capacity {
+            self.ticks.pop_front();
+        }
+        for (metric, value) in metrics {
+            let series = self.series.entry(metric).or_default();
+            series.push_back((at, value));
+            while series.len() > capacity {
+                series.pop_front();
+            }
+        }
+        // A metric that hasn't appeared for a whole window (an unplugged interface) goes.
+        let oldest = self.ticks.front().copied().unwrap_or(at);
+        self.series
+            .retain(|_, series| series.back().is_some_and(|&(t, _)| t >= oldest));
+    }
+
+    fn since(&self, since: Option<u64>) -> Vec<MetricSeries> {
+        self.series
+            .iter()
+            .map(|(metric, series)| {
+                let (timestamps, values) = series
+                    .iter()
+                    .filter(|(t, _)| since.is_none_or(|since| *t > since))
+                    .copied()
+                    .unzip();
+                MetricSeries {
+                    metric: metric.clone(),
+                    timestamps,
+                    values,
+                }
+            })
+            .filter(|series| !series.timestamps.is_empty())
+            .collect()
+    }
+}
+
+/// One metric's recorded history, as returned by `get_stream_history`. Every metric published in
+/// the same tick shares its timestamp, so series line up by timestamp.
+#[derive(Serialize, TS, Clone, Debug)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct MetricSeries {
+    pub metric: String,
+    /// Unix milliseconds, oldest first.
+    pub timestamps: Vec<u64>,
+    pub values: Vec<f32>,
+}
+
+/* Producers  */
+
+/// The task or thread a channel's producer runs on, owned by the channel struct.
+#[derive(Default)]
+pub struct Producer(Mutex<Option<ProducerHandle>>);
+
+enum ProducerHandle {
+    Task(tauri::async_runtime::JoinHandle<()>),
+    Thread {
+        stop: Arc<AtomicBool>,
+        thread: std::thread::JoinHandle<()>,
+    },
+}
+
+impl Producer {
+    /// Runs `task` on the async runtime, replacing (and stopping) any previous producer.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.stop();
+        if let Ok(mut handle) = self.0.lock() {
+            *handle = Some(ProducerHandle::Task(tauri::async_runtime::spawn(task)));
+        }
+    }
+
+    /// Adopts a dedicated thread that polls `stop` and returns once it is set.
+    pub fn adopt_thread(&self, stop: Arc<AtomicBool>, thread: std::thread::JoinHandle<()>) {
+        self.stop();
+        if let Ok(mut handle) = self.0.lock() {
+            *handle = Some(ProducerHandle::Thread { stop, thread });
+        }
+    }
+
+    /// Aborts the task, or signals the thread and waits for it to wind down.
+    pub fn stop(&self) {
+        let Some(handle) = self.0.lock().ok().and_then(|mut h| h.take()) else {
+            return;
+        };
+        match handle {
+            ProducerHandle::Task(task) => task.abort(),
+            ProducerHandle::Thread { stop, thread } => {
+                stop.store(true, Ordering::Relaxed);
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+/// The common producer shape: while the gate is open, publish `collect()` at the fastest
+/// interval any subscriber requested (`default` for those that didn't). `collect` owns whatever
+/// handles it needs (e.g. a `sysinfo::System`), so they live exactly as long as the producer.
+pub async fn run_polled<C, F>(app: AppHandle, core: ChannelCore, default: Duration, mut collect: F)
+where
+    C: Channel,
+    F: FnMut() -> C::Payload + Send,
+{
+    run_polled_on_change::<C, _>(app, core, default, move || Some(collect())).await
+}
+
+/// [`run_polled`] for slow-changing payloads: `collect` still runs every tick but returns `None`
+/// when nothing worth sending changed, and only `Some` ticks are published. New subscribers are
+/// served from the last-value cache in the meantime.
+pub async fn run_polled_on_change<C, F>(
+    app: AppHandle,
+    core: ChannelCore,
+    default: Duration,
+    mut collect: F,
+) where
+    C: Channel,
+    F: FnMut() -> Option<C::Payload> + Send,
+{
+    tracing::info!(target: TARGET, stream = %C::NAME, "producer started");
+    let mut gate = core.gate(&app);
+    let mut current = core.interval(default);
+    let mut ticker = poll_ticker(current);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if gate.should_run() {
+                    if let Some(payload) = collect() {
+                        C::publish(&app, payload);
+                    }
+                }
+            }
+            _ = core.interval_changed() => {
+                let wanted = core.interval(default);
+                if wanted != current {
+                    tracing::debug!(target: TARGET, stream = %C::NAME, from_ms = current.as_millis() as u64, to_ms = wanted.as_millis() as u64, "refresh interval changed");
+                    current = wanted;
+                    // A fresh interval ticks immediately, so a subscriber asking for faster
+                    // updates doesn't wait out the old, slower period first.
+                    ticker = poll_ticker(current);
+                }
+            }
+        }
+    }
+}
+
+fn poll_ticker(period: Duration) -> tokio::time::Interval {
+    let mut ticker = tokio::time::interval(period);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticker
+}
+
+/* Subscriber gate  */
+
+/// Tracks whether a stream currently has ≥1 subscriber and logs the pause/resume transition
+/// exactly once when that state flips. Factors out the "no subscribers — pausing X updates" /
+/// "subscriber(s) active — resuming X updates" shape that used to be copy-pasted per loop —
+/// one instance per producer, held as ordinary mutable local state by the owning loop.
+///
+/// The gate keeps reporting "run" for a linger period after the last subscriber leaves, so an
+/// unsubscribe immediately followed by a subscribe — a widget remounting when edit mode
+/// toggles — reuses whatever the loop has running instead of tearing it down and rebuilding
+/// it. Every start and stop is counted in the channel's [`StreamStats`].
+pub struct SubscriberGate {
+    core: ChannelCore,
+    linger: Duration,
+    running: bool,
+    /// When the subscriber count last dropped to zero while running.
+    idle_since: Option<Instant>,
+}
+
+impl SubscriberGate {
+    pub fn subscribers(&self) -> usize {
+        self.core.subscribers()
+    }
+
+    /// Loads the current subscriber count, logs a transition if the gate state flipped since
+    /// the last call, and returns whether the caller should do work this tick. Stays `true`
+    /// during the linger after the last subscriber leaves.
+    pub fn should_run(&mut self) -> bool {
+        self.should_run_if(true)
+    }
+
+    /// `should_run`, with a veto from the owning loop (e.g. the visualizer while nothing is
+    /// visible). A veto stops the stream immediately, without lingering.
+    pub fn should_run_if(&mut self, allowed: bool) -> bool {
+        let name = self.core.name();
+        let count = self.subscribers();
+        if count > 0 && allowed {
+            if let Some(since) = self.idle_since.take() {
+                tracing::debug!(target: TARGET, stream = %name, idle_ms = since.elapsed().as_millis() as u64, "subscriber returned while lingering — reusing stream");
+            }
+            if !self.running {
+                tracing::info!(target: TARGET, stream = %name, sub_count = count, "subscriber(s) active — resuming updates");
+                self.running = true;
+                self.core.record(true);
+            }
+            return true;
+        }
+        if !self.running {
+            return false;
+        }
+        if allowed {
+            let since = *self.idle_since.get_or_insert_with(|| {
+                tracing::debug!(target: TARGET, stream = %name, linger_ms = self.linger.as_millis() as u64, "no subscribers — lingering before pausing");
+                Instant::now()
+            });
+            if since.elapsed() < self.linger {
+                return true;
+            }
+        }
+        tracing::info!(target: TARGET, stream = %name, sub_count = count, "pausing updates");
+        self.running = false;
+        self.idle_since = None;
+        self.core.record(false);
+        false
+    }
+}
@@ -31,4 +31,32 @@ pub struct Args {
     /// Default log level
     #[arg(long, value_name = "LEVEL", default_value = "info")]
     pub log_level: LogLevel,
-}
\ No newline at end of file
+
+    /// Audio source for the visualizer: `loopback` (default output device), `wav:<path>`,
+    /// `sine:<hz>`, `sweep` or `noise`. The non-loopback sources need no audio hardware.
+    #[arg(long, value_name = "SOURCE", default_value = "loopback")]
+    pub visualizer_source: String,
+
+    /// How long a stream keeps its producer running after its last subscriber leaves, so a
+    /// widget remount (unsubscribe immediately followed by subscribe) reuses it instead of
+    /// tearing it down and rebuilding it. `0` stops immediately.
+    #[arg(long, value_name = "MS", default_value_t = 3000)]
+    pub stream_linger_ms: u64,
+
+    /// How many samples each stream keeps per metric for `get_stream_history`. One sample per
+    /// publish, so the span follows the stream's interval: at the default, two and a half
+    /// minutes of the 500 ms streams (CPU, memory, disks, networks), five of the 1 s ones, and
+    /// 30 s of a stream polled at the 100 ms floor. `0` keeps no history.
+    #[arg(long, value_name = "SAMPLES", default_value_t = 300)]
+    pub stream_history: usize,
+
+    /// Record CPU, memory, network and disk I/O metrics to disk for `query_metrics`: 1 s
+    /// resolution for an hour, 1 min for a day and 15 min for a month. Keeps those streams
+    /// running while the app is.
+    #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+    pub record_metrics: bool,
+
+    /// Size cap for the recording; the oldest, finest data goes first.
+    #[arg(long, value_name = "MB", default_value_t = 256)]
+    pub record_max_mb: u64,
+}
@@ -406,7 +406,7 @@ fn hex_to_linear(c: u8) -> f64 {
     }
 }
 
-fn srgb_to_oklab(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+pub(crate) fn srgb_to_oklab(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
     let rl = hex_to_linear(r);
     let gl = hex_to_linear(g);
     let bl = hex_to_linear(b);
@@ -420,18 +420,18 @@ fn srgb_to_oklab(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
     )
 }
 
-fn oklab_to_oklch(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
+pub(crate) fn oklab_to_oklch(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
     let c = (a * a + b * b).sqrt();
     let h = b.atan2(a).to_degrees().rem_euclid(360.0);
     (l, c, h)
 }
 
-fn oklch_to_oklab(l: f64, c: f64, h: f64) -> (f64, f64, f64) {
+pub(crate) fn oklch_to_oklab(l: f64, c: f64, h: f64) -> (f64, f64, f64) {
     let h_rad = h.to_radians();
     (l, c * h_rad.cos(), c * h_rad.sin())
 }
 
-fn oklab_to_linear_srgb(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
+pub(crate) fn oklab_to_linear_srgb(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
     let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
     let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
     let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
@@ -454,7 +454,7 @@ fn linear_to_srgb_channel(c: f64) -> u8 {
     (v.clamp(0.0, 1.0) * 255.0).round() as u8
 }
 
-fn oklch_to_hex(l: f64, c: f64, h: f64) -> String {
+pub(crate) fn oklch_to_hex(l: f64, c: f64, h: f64) -> String {
     let (lab_l, lab_a, lab_b) = oklch_to_oklab(l, c, h);
     let (rl, gl, bl) = oklab_to_linear_srgb(lab_l, lab_a, lab_b);
     return format!(
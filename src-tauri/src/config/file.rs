@@ -16,7 +16,11 @@ pub fn get_config() -> io::Result<Config> {
         e
     })?;
     let reader = BufReader::new(file);
-    let config: Config = from_reader(reader).map_err(|e| {
+    let parsed = from_reader(reader).and_then(|mut value| {
+        crate::alerts::drop_invalid_rules(&mut value);
+        serde_json::from_value::<Config>(value)
+    });
+    let config = parsed.map_err(|e| {
         tracing::error!(target: TARGET, path = %path.display(), error = %e, "config parse failed");
         io::Error::new(io::ErrorKind::InvalidData, e)
     })?;
@@ -45,6 +45,8 @@ pub struct Config {
     pub active_theme: Option<String>,
     pub active_layout: Option<String>,
     pub preferences: Preferences,
+    /// Threshold alert rules, evaluated by `crate::alerts` whether or not a widget is showing.
+    pub alerts: Vec<crate::alerts::AlertRule>,
 }
 
 /* Theme types  */
@@ -145,6 +147,7 @@ impl Default for Config {
             active_theme: Some("e58e167b-8c7d-4b88-9c20-46b25147ab25".to_string()),
             active_layout: Some("3dd07989-2eda-4a0b-83f8-ef66323e85a4".to_string()),
             preferences: Preferences::default(),
+            alerts: Vec::new(),
         }
     }
 }
@@ -245,6 +248,13 @@ pub fn get_themes_root() -> Option<PathBuf> {
     })
 }
 
+pub fn get_media_rules_path() -> Option<PathBuf> {
+    app_config_dir().map(|mut p| {
+        p.push("media_rules.json");
+        p
+    })
+}
+
 pub fn get_layouts_root() -> Option<PathBuf> {
     app_config_dir().map(|mut p| {
         p.push("layouts");
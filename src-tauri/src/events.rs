@@ -1,26 +1,32 @@
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 use ts_rs::TS;
 
+use crate::alerts::AlertEvent;
 use crate::config::{Config, LayoutFile, Preferences, WidgetConfig, WidgetId};
 
 pub const STREAM_MEDIA: &str = "stream::media";
 pub const STREAM_VISUALIZER: &str = "stream::visualizer";
+pub const VISUALIZER_SPECTRUM: &str = "visualizer::spectrum";
+pub const VISUALIZER_STEREO: &str = "visualizer::stereo";
+pub const VISUALIZER_WAVEFORM: &str = "visualizer::waveform";
+pub const VISUALIZER_LEVELS: &str = "visualizer::levels";
+pub const VISUALIZER_BEAT: &str = "visualizer::beat";
 pub const CONFIG_CHANGED: &str = "config::changed";
 pub const THEME_CHANGED: &str = "theme::changed";
 pub const LAYOUT_CHANGED: &str = "layout::changed";
 pub const WIDGET_UPDATED: &str = "widget::updated";
 pub const PREFERENCES_CHANGED: &str = "preferences::changed";
 pub const PREFERENCES_PREVIEW: &str = "preferences::preview";
+pub const ALERT_RAISED: &str = "alert::raised";
+pub const ALERT_CLEARED: &str = "alert::cleared";
 
 /// Single source of truth for stream/subscription channel names, on both sides of the IPC
 /// boundary — ts-rs-exported and imported directly by the frontend (`src/ipc/events.ts`)
-/// instead of hand-mirrored, the same pattern `file.rs`'s `Scope` enum already uses. `cpu`/
-/// `memory`/`disks`/`networks` share one task (`system::run_resource_loop`); `media`/
-/// `visualizer` keep their own dedicated task/thread — see CLAUDE.md's "Subscription channels"
-/// section.
+/// instead of hand-mirrored, the same pattern `file.rs`'s `Scope` enum already uses. Each name
+/// is served by one [`crate::channel::Channel`] implementation.
 #[derive(Serialize, Deserialize, TS, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[serde(rename_all = "snake_case")]
 #[ts(export, export_to = "../../src/ffi_types.ts")]
@@ -28,9 +34,16 @@ pub enum StreamName {
     Cpu,
     Memory,
     Disks,
+    DiskIo,
     Networks,
     Media,
+    MediaTick,
     Visualizer,
+    Sensors,
+    Power,
+    Processes,
+    Gpu,
+    SystemInfo,
 }
 
 impl StreamName {
@@ -40,9 +53,16 @@ impl StreamName {
             StreamName::Cpu => "cpu",
             StreamName::Memory => "memory",
             StreamName::Disks => "disks",
+            StreamName::DiskIo => "disk_io",
             StreamName::Networks => "networks",
             StreamName::Media => "media",
+            StreamName::MediaTick => "media_tick",
             StreamName::Visualizer => "visualizer",
+            StreamName::Sensors => "sensors",
+            StreamName::Power => "power",
+            StreamName::Processes => "processes",
+            StreamName::Gpu => "gpu",
+            StreamName::SystemInfo => "system_info",
         }
     }
 
@@ -58,54 +78,108 @@ impl std::fmt::Display for StreamName {
     }
 }
 
-/// Tracks whether a stream currently has ≥1 subscriber and logs the pause/resume transition
-/// exactly once when that state flips. Factors out the "no subscribers — pausing X updates" /
-/// "subscriber(s) active — resuming X updates" shape that used to be copy-pasted per loop
-/// (`run_system_loop`, `run_hardware_loop`, and independently `spawn_visualizer_loop`'s
-/// `last_sub_nonzero`) — one instance per gated metric, held as ordinary mutable local state by
-/// the owning loop.
-pub struct SubscriberGate {
-    subscribers: Arc<AtomicUsize>,
-    name: StreamName,
-    had_subs: bool,
-}
-
-impl SubscriberGate {
-    pub fn new(subscribers: Arc<AtomicUsize>, name: StreamName) -> Self {
-        Self {
-            subscribers,
-            name,
-            had_subs: false,
+/// Longest a frame may stay unacknowledged before the gate sends the newest one anyway, so a
+/// lost ack (webview reload, a listener that never acks) can't stall delivery for good.
+const FRAME_ACK_TIMEOUT: Duration = Duration::from_secs(1);
+/// Cap for the doubling retry interval while acks keep not arriving.
+const FRAME_ACK_TIMEOUT_MAX: Duration = Duration::from_secs(30);
+
+/// Latest-frame-wins delivery for high-rate streams (the visualizer's ~30 fps events). Frames go
+/// to one webview at a time (`emit_to` its label), and each webview's event — per `id` for the
+/// tagged `visualizer::*` payloads — has at most one frame in flight: while that webview hasn't
+/// acked it, newer frames only overwrite a single pending slot, and its ack sends whatever is
+/// pending. A webview that stops consuming (screen off, throttled in the background) therefore
+/// costs one buffered frame instead of an unbounded backlog that replays on wake, and neither
+/// holds back nor speeds up the others. Unacked frames are re-sent after a timeout that doubles
+/// up to 30 s.
+#[derive(Default)]
+pub struct FrameGate(std::sync::Mutex<HashMap<String, FrameSlot>>);
+
+struct FrameSlot {
+    sent_at: Option<Instant>,
+    timeout: Duration,
+    pending: Option<(&'static str, serde_json::Value)>,
+}
+
+impl FrameGate {
+    fn key(webview: &str, event: &str, id: Option<&str>) -> String {
+        match id {
+            Some(id) => format!("{webview}|{event}#{id}"),
+            None => format!("{webview}|{event}"),
         }
     }
 
-    /// Loads the current subscriber count, logs a transition if the gate state flipped since
-    /// the last call, and returns whether the caller should do work this tick.
-    pub fn should_run(&mut self) -> bool {
-        let count = self.subscribers.load(Ordering::Relaxed);
-        if count == 0 {
-            if self.had_subs {
-                tracing::info!(target: "resource", stream = %self.name, "no subscribers — pausing updates");
-                self.had_subs = false;
+    /// Emits `payload` as `event` to webview `webview` if the previous frame it was sent was acked
+    /// (or timed out), otherwise parks it as the pending frame, replacing any older one.
+    pub fn offer<T: Serialize + Clone>(
+        &self,
+        app: &AppHandle,
+        webview: &str,
+        event: &'static str,
+        id: Option<&str>,
+        payload: T,
+    ) {
+        let Ok(mut slots) = self.0.lock() else {
+            return;
+        };
+        let slot = slots
+            .entry(Self::key(webview, event, id))
+            .or_insert(FrameSlot {
+                sent_at: None,
+                timeout: FRAME_ACK_TIMEOUT,
+                pending: None,
+            });
+        match slot.sent_at {
+            Some(sent) if sent.elapsed() < slot.timeout => {
+                slot.pending = serde_json::to_value(&payload).ok().map(|v| (event, v));
+                return;
             }
-            return false;
+            Some(_) => {
+                tracing::debug!(target: "events", webview, event, id, timeout_ms = slot.timeout.as_millis() as u64, "frame not acked in time — re-sending latest");
+                slot.timeout = (slot.timeout * 2).min(FRAME_ACK_TIMEOUT_MAX);
+            }
+            None => {}
         }
-        if !self.had_subs {
-            tracing::info!(target: "resource", stream = %self.name, sub_count = count, "subscriber(s) active — resuming updates");
-            self.had_subs = true;
+        slot.pending = None;
+        slot.sent_at = Some(Instant::now());
+        let _ = app.emit_to(webview, event, payload);
+    }
+
+    /// Marks `webview`'s in-flight frame for `event`/`id` consumed and sends it the pending one,
+    /// if any.
+    pub fn ack(&self, app: &AppHandle, webview: &str, event: &str, id: Option<&str>) {
+        let Ok(mut slots) = self.0.lock() else {
+            return;
+        };
+        let Some(slot) = slots.get_mut(&Self::key(webview, event, id)) else {
+            return;
+        };
+        slot.timeout = FRAME_ACK_TIMEOUT;
+        match slot.pending.take() {
+            Some((event, value)) => {
+                slot.sent_at = Some(Instant::now());
+                let _ = app.emit_to(webview, event, value);
+            }
+            None => slot.sent_at = None,
         }
-        true
     }
-}
 
-/// Serializes `payload`, updates the shared per-stream cache, and emits it as `stream::<name>`.
-/// Generic over the payload type so cpu/memory/disks/networks (4 distinct payload types) share
-/// one implementation instead of 4 copies of `windows_media.rs`'s single-type `emit_media` shape.
-pub fn emit_stream<T: Serialize + Clone>(app: &AppHandle, name: StreamName, payload: T) {
-    if let Ok(value) = serde_json::to_value(&payload) {
-        app.state::<crate::ChannelCache>().set(name, value);
+    /// Forgets webview `label`'s slots when it reloads, so its first frames aren't held back
+    /// waiting on acks the old page will never send.
+    pub fn forget_webview(&self, label: &str) {
+        if let Ok(mut slots) = self.0.lock() {
+            let prefix = format!("{label}|");
+            slots.retain(|key, _| !key.starts_with(&prefix));
+        }
+    }
+
+    /// Forgets every slot, e.g. when the producer pauses, so stale pending frames aren't
+    /// delivered on resume.
+    pub fn clear(&self) {
+        if let Ok(mut slots) = self.0.lock() {
+            slots.clear();
+        }
     }
-    let _ = app.emit(&name.event_name(), payload);
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -157,3 +231,12 @@ pub fn emit_preferences_preview(app: &AppHandle, prefs: &Preferences) {
     app.emit_to("main", PREFERENCES_PREVIEW, prefs).ok();
 }
 
+pub fn emit_alert_raised(app: &AppHandle, alert: &AlertEvent) {
+    tracing::trace!(target: "events", event = ALERT_RAISED, rule = %alert.rule_id, "emit");
+    app.emit(ALERT_RAISED, alert).ok();
+}
+
+pub fn emit_alert_cleared(app: &AppHandle, alert: &AlertEvent) {
+    tracing::trace!(target: "events", event = ALERT_CLEARED, rule = %alert.rule_id, "emit");
+    app.emit(ALERT_CLEARED, alert).ok();
+}
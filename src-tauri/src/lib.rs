@@ -1,13 +1,15 @@
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tauri::{
     async_runtime::{Mutex, RwLock},
     Manager, Monitor,
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
+use channel::ManageChannel;
+
+mod alerts;
+mod channel;
 pub mod cli;
 mod config;
 mod error;
@@ -15,6 +17,7 @@ pub mod events;
 mod file;
 mod logging;
 mod media;
+mod recording;
 mod system;
 
 struct AppStateInner {
@@ -55,63 +58,6 @@ pub fn apply_webview_env<'a, R: tauri::Runtime, M: Manager<R>>(
     }
 }
 
-struct ChannelSubscribers {
-    channels: HashMap<events::StreamName, Arc<AtomicUsize>>,
-}
-
-impl ChannelSubscribers {
-    fn new() -> Self {
-        Self {
-            channels: HashMap::new(),
-        }
-    }
-
-    /// Registers a channel and returns the Arc that should be passed to its loop.
-    fn register(&mut self, channel: events::StreamName) -> Arc<AtomicUsize> {
-        let counter = Arc::new(AtomicUsize::new(0));
-        self.channels.insert(channel, Arc::clone(&counter));
-        counter
-    }
-
-    fn get_counter(&self, channel: events::StreamName) -> Option<&Arc<AtomicUsize>> {
-        self.channels.get(&channel)
-    }
-
-    pub fn increment(&self, channel: events::StreamName) -> usize {
-        self.get_counter(channel)
-            .map(|c| c.fetch_add(1, Ordering::Relaxed) + 1)
-            .unwrap_or(0)
-    }
-
-    pub fn decrement(&self, channel: events::StreamName) {
-        if let Some(c) = self.get_counter(channel) {
-            let _ = c.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
-                Some(v.saturating_sub(1))
-            });
-        }
-    }
-}
-
-/// Caches the last emitted value per stream channel so new subscribers can
-/// receive it immediately without waiting for the next poll tick.
-pub struct ChannelCache(std::sync::Mutex<HashMap<events::StreamName, serde_json::Value>>);
-
-impl ChannelCache {
-    pub fn new() -> Self {
-        Self(std::sync::Mutex::new(HashMap::new()))
-    }
-
-    pub fn set(&self, channel: events::StreamName, value: serde_json::Value) {
-        if let Ok(mut map) = self.0.lock() {
-            map.insert(channel, value);
-        }
-    }
-
-    pub fn get(&self, channel: events::StreamName) -> Option<serde_json::Value> {
-        self.0.lock().ok()?.get(&channel).cloned()
-    }
-}
-
 // Takes an `AppHandle` rather than a window on purpose — see build_monitor_cache in config/mod.rs.
 fn get_monitor(app: &tauri::AppHandle, config: &config::Config) -> Result<Monitor, String> {
     let monitors = app.available_monitors().map_err(|e| e.to_string())?;
@@ -137,29 +83,112 @@ fn get_monitor(app: &tauri::AppHandle, config: &config::Config) -> Result<Monito
     Err("No monitors found".into())
 }
 
+/// `interval_ms` asks for updates at least that often (floored at 100 ms); the stream polls at
+/// the fastest rate any live subscriber asked for. Omit it for the stream's default. Only the
+/// polled resource streams honour it — media and the visualizer push on their own schedule.
 #[tauri::command]
 async fn subscribe_channel(
     channel: events::StreamName,
+    interval_ms: Option<u64>,
     app: tauri::AppHandle,
+    webview: tauri::Webview,
 ) -> Result<serde_json::Value, String> {
-    let subs = app.state::<ChannelSubscribers>();
-    let count = subs.increment(channel);
-    let last_value = app.state::<ChannelCache>().get(channel);
+    let channels = app.state::<channel::Channels>();
+    let count = channels.subscribe(
+        channel,
+        interval_ms.map(Duration::from_millis),
+        webview.label(),
+    );
+    let last_value = channels.last_value(channel);
     Ok(serde_json::json!({
         "is_first_subscriber": count == 1,
         "last_value": last_value
     }))
 }
 
+/// `interval_ms` must match what the subscriber passed to `subscribe_channel`. `id` is the id
+/// the subscriber registered per-subscriber state under, if any (`set_visualizer_config`); that
+/// state is dropped with the subscription.
 #[tauri::command]
 async fn unsubscribe_channel(
     channel: events::StreamName,
+    interval_ms: Option<u64>,
+    id: Option<String>,
     app: tauri::AppHandle,
+    webview: tauri::Webview,
 ) -> Result<(), String> {
-    app.state::<ChannelSubscribers>().decrement(channel);
+    let channels = app.state::<channel::Channels>();
+    channels.unsubscribe(
+        channel,
+        interval_ms.map(Duration::from_millis),
+        webview.label(),
+    );
+    if let Some(id) = id {
+        channels.release(&app, channel, &id);
+    }
+    Ok(())
+}
+
+/// Acknowledges a frame delivered through the `FrameGate` (the visualizer's events), releasing
+/// the newest one pending for the calling webview. `id` is the tag of `visualizer::*` payloads;
+/// omit it for `stream::visualizer`.
+#[tauri::command]
+async fn ack_frame(
+    event: String,
+    id: Option<String>,
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+) -> Result<(), String> {
+    app.state::<events::FrameGate>()
+        .ack(&app, webview.label(), &event, id.as_deref());
     Ok(())
 }
 
+/// Subscriber counts and producer start/stop counts for every stream channel — for spotting
+/// streams that get torn down and rebuilt on every widget remount.
+#[tauri::command]
+async fn get_stream_stats(app: tauri::AppHandle) -> Result<Vec<channel::StreamStats>, String> {
+    Ok(app.state::<channel::Channels>().stats())
+}
+
+/// The channel's recorded metric history, so graphs survive window reloads and widget remounts.
+/// `since` (Unix ms) returns only newer samples, for catching up after a gap.
+#[tauri::command]
+async fn get_stream_history(
+    channel: events::StreamName,
+    since: Option<u64>,
+    app: tauri::AppHandle,
+) -> Result<Vec<channel::MetricSeries>, String> {
+    app.state::<channel::Channels>().history(channel, since)
+}
+
+/// Dev mode only: every channel's stats and cached last value, keyed by channel name.
+#[tauri::command]
+async fn dump_channels(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+    require_dev_mode()?;
+    Ok(app.state::<channel::Channels>().dump())
+}
+
+/// Dev mode only: restarts a channel's producer with an empty cache and zeroed counters.
+#[tauri::command]
+async fn reset_channel(channel: events::StreamName, app: tauri::AppHandle) -> Result<(), String> {
+    require_dev_mode()?;
+    // Stopping a thread producer (the visualizer) joins it; keep that off the async workers.
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<channel::Channels>().reset(&app, channel)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn require_dev_mode() -> Result<(), String> {
+    if *DEV_MODE.get().unwrap_or(&false) {
+        Ok(())
+    } else {
+        Err("only available in dev mode".into())
+    }
+}
+
 #[tauri::command]
 async fn get_config_path() -> Result<String, String> {
     config::get_config_path()
@@ -219,12 +248,39 @@ pub fn run(args: cli::Args) {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        // A reloading webview's widgets vanish without unsubscribing; forget the analysis and
+        // process queries they registered so they don't run for nobody, and the frames in
+        // flight to the old page.
+        .on_page_load(|webview, payload| {
+            if payload.event() == tauri::webview::PageLoadEvent::Started {
+                if let Some(channels) = webview.try_state::<channel::Channels>() {
+                    channels.forget_webview(webview.label());
+                }
+                if let Some(gate) = webview.try_state::<events::FrameGate>() {
+                    gate.forget_webview(webview.label());
+                }
+                if let Some(configs) = webview.try_state::<media::VisualizerConfigs>() {
+                    configs.clear_webview(webview.label());
+                }
+                if let Some(queries) = webview.try_state::<system::ProcessQueries>() {
+                    queries.clear_webview(webview.label());
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             exit_program,
             is_dev_mode,
             // stream subscription
             subscribe_channel,
             unsubscribe_channel,
+            ack_frame,
+            get_stream_stats,
+            get_stream_history,
+            recording::query_metrics,
+            alerts::get_active_alerts,
+            alerts::set_alert_rules,
+            dump_channels,
+            reset_channel,
             // config commands
             get_config_path,
             get_config,
@@ -236,6 +292,17 @@ pub fn run(args: cli::Args) {
             media::next_track,
             media::prev_track,
             media::toggle_playback,
+            media::seek_media,
+            media::set_volume,
+            media::set_shuffle,
+            media::set_repeat,
+            media::list_media_sessions,
+            media::select_media_session,
+            // visualizer commands
+            media::set_visualizer_config,
+            media::clear_visualizer_config,
+            system::set_process_query,
+            system::clear_process_query,
             // monitor commands
             config::next_monitor,
             config::get_monitor_count,
@@ -284,6 +351,7 @@ pub fn run(args: cli::Args) {
             });
             config::ensure_default_themes();
             config::ensure_default_layouts();
+            media::ensure_default_rules();
 
             // Everything state-related (`app.manage()`) must happen before any webview is
             // built below: building a window starts loading frontend JS immediately, and
@@ -296,6 +364,15 @@ pub fn run(args: cli::Args) {
             // built) window so this ordering is possible at all — see build_monitor_cache.
             let dev = args.dev;
             let _ = DEV_MODE.set(dev);
+            let stream_linger = Duration::from_millis(args.stream_linger_ms);
+            let stream_history = args.stream_history;
+            let record_metrics = args.record_metrics.then_some(args.record_max_mb * 1024 * 1024);
+            let alert_rules = config.alerts.clone();
+            let visualizer_source = media::SourceSpec::parse(&args.visualizer_source)
+                .unwrap_or_else(|e| {
+                    warn!("{e}; falling back to loopback");
+                    media::SourceSpec::Loopback
+                });
             let target_monitor = get_monitor(app.handle(), &config).expect("Failed to get target monitor");
             let monitor_cache =
                 config::build_monitor_cache(app.handle(), target_monitor.name().map(|s| s.as_str()));
@@ -309,17 +386,34 @@ pub fn run(args: cli::Args) {
                 file_manager,
             }));
 
-            /* Channel subscription counters and cache  */
-
-            let mut channel_subs = ChannelSubscribers::new();
-            let cpu_subs = channel_subs.register(events::StreamName::Cpu);
-            let memory_subs = channel_subs.register(events::StreamName::Memory);
-            let disks_subs = channel_subs.register(events::StreamName::Disks);
-            let networks_subs = channel_subs.register(events::StreamName::Networks);
-            let media_subs = channel_subs.register(events::StreamName::Media);
-            let visualizer_subs = channel_subs.register(events::StreamName::Visualizer);
-            app.manage(channel_subs);
-            app.manage(ChannelCache::new());
+            /* Stream channels  */
+
+            app.manage(channel::Channels::new(stream_linger, stream_history));
+            app.manage(alerts::Alerts::default());
+            app.manage(events::FrameGate::default());
+            app.manage(media::VisualizerConfigs::default());
+            app.manage(system::ProcessQueries::default());
+            app.manage_channel(system::CpuChannel::new(Duration::from_millis(500)));
+            app.manage_channel(system::MemoryChannel::new(Duration::from_millis(500)));
+            app.manage_channel(system::DisksChannel::new(Duration::from_millis(500)));
+            app.manage_channel(system::DiskIoChannel::new(Duration::from_secs(1)));
+            app.manage_channel(system::NetworksChannel::new(Duration::from_millis(500)));
+            app.manage_channel(system::SensorsChannel::new(Duration::from_secs(2)));
+            app.manage_channel(system::PowerChannel::new(Duration::from_secs(5)));
+            app.manage_channel(system::ProcessesChannel::new(Duration::from_secs(2)));
+            app.manage_channel(system::GpuChannel::new(Duration::from_secs(1)));
+            app.manage_channel(system::SystemInfoChannel::new(Duration::from_secs(5)));
+            app.manage_channel(media::MediaChannel::new(Duration::from_secs(2)));
+            app.manage_channel(media::MediaTickChannel::new(Duration::from_secs(1)));
+            app.manage_channel(media::VisualizerChannel::new(
+                Duration::from_millis(33),
+                visualizer_source,
+            ));
+            if let Some(max_bytes) = record_metrics {
+                recording::start(app, max_bytes);
+            }
+            app.state::<alerts::Alerts>()
+                .configure(app.handle(), alert_rules);
 
             /* Windows — only now, with all state already managed  */
 
@@ -378,28 +472,6 @@ pub fn run(args: cli::Args) {
 
             win.show().expect("Failed to show window");
 
-            /* Background event loops  */
-
-            let handle = app.handle().clone();
-            tauri::async_runtime::spawn(system::run_resource_loop(
-                handle.clone(),
-                Arc::clone(&cpu_subs),
-                Arc::clone(&memory_subs),
-                Arc::clone(&disks_subs),
-                Arc::clone(&networks_subs),
-                Duration::from_millis(500),
-            ));
-            tauri::async_runtime::spawn(media::run_media_loop(
-                handle.clone(),
-                Arc::clone(&media_subs),
-                Duration::from_secs(2),
-            ));
-            media::spawn_visualizer_loop(
-                handle,
-                Arc::clone(&visualizer_subs),
-                Duration::from_millis(33),
-            );
-
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,698 @@
+//! Turns `FFTStream`'s raw magnitude spectrum into the log-spaced, weighted, smoothed bars the
+//! visualizer widgets draw. One `SpectrumAnalyzer` per analysis configuration: the default one
+//! feeds `stream::visualizer`, and every widget that registers its own `VisualizerConfig` gets a
+//! separate analyzer emitting `visualizer::spectrum` tagged with its id — all sharing one capture
+//! and one FFT per frame. A config can also ask for separate left/right spectra
+//! (`visualizer::stereo`) and for the time-domain outputs in `dynamics` (`visualizer::waveform`,
+//! `visualizer::levels`, `visualizer::beat`).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+
+use super::dynamics::{
+    self, BeatDetector, CorrelationMeter, LevelMeter, StereoImage, VisualizerLevels,
+    VisualizerWaveform,
+};
+use super::{FFTStream, FrequencyReading, FFT_TARGET};
+use crate::channel::Channels;
+use crate::events::{
+    FrameGate, StreamName, STREAM_VISUALIZER, VISUALIZER_BEAT, VISUALIZER_LEVELS,
+    VISUALIZER_SPECTRUM, VISUALIZER_STEREO, VISUALIZER_WAVEFORM,
+};
+
+const MAX_BINS: u32 = 512;
+const MAX_FRAME_RATE: f32 = 120.0;
+const MAX_WAVEFORM_POINTS: u32 = 2048;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum Weighting {
+    None,
+    /// Approximates perceived loudness at moderate levels — tames the bass-heavy look of a raw
+    /// spectrum.
+    A,
+    /// Much flatter than A; only rolls off the extremes.
+    C,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+#[serde(default)]
+pub struct VisualizerConfig {
+    /// Number of log-spaced bars, 1–512.
+    pub bins: u32,
+    pub min_freq: f32,
+    /// Clamped to the capture's Nyquist frequency.
+    pub max_freq: f32,
+    pub weighting: Weighting,
+    /// Time constant for rising bars, in milliseconds.
+    pub attack_ms: f32,
+    /// Time constant for falling bars, in milliseconds.
+    pub decay_ms: f32,
+    /// Spectra per second, 1–120. Waveform and levels are reported at the same rate.
+    pub frame_rate: f32,
+    /// Emit `visualizer::spectrum`. Turn off for widgets that only want the outputs below.
+    pub spectrum: bool,
+    /// Emit `visualizer::stereo`: left and right spectra (sharing one normalisation, so the
+    /// louder side draws taller) plus phase correlation. Mono sources report identical sides.
+    pub stereo: bool,
+    /// Points per `visualizer::waveform` payload, up to 2048; 0 disables the waveform.
+    pub waveform_points: u32,
+    /// Emit per-channel peak/RMS levels on `visualizer::levels`.
+    pub levels: bool,
+    /// Run beat detection and emit `visualizer::beat` on each onset. Beats are not tagged with
+    /// an id — every widget asking for them receives the same events.
+    pub beats: bool,
+}
+
+impl Default for VisualizerConfig {
+    fn default() -> Self {
+        Self {
+            bins: 64,
+            min_freq: 20.0,
+            max_freq: 20_000.0,
+            weighting: Weighting::A,
+            attack_ms: 10.0,
+            decay_ms: 300.0,
+            frame_rate: 30.0,
+            spectrum: true,
+            stereo: false,
+            waveform_points: 0,
+            levels: false,
+            beats: false,
+        }
+    }
+}
+
+impl VisualizerConfig {
+    /// Clamps every field into a range the analyzer can work with, so a bad config from the
+    /// frontend degrades instead of producing NaNs or a thousand-bar spectrum.
+    fn sanitized(mut self) -> Self {
+        self.bins = self.bins.clamp(1, MAX_BINS);
+        if !self.min_freq.is_finite() || self.min_freq < 1.0 {
+            self.min_freq = 1.0;
+        }
+        if !self.max_freq.is_finite() || self.max_freq <= self.min_freq {
+            self.max_freq = self.min_freq * 2.0;
+        }
+        self.attack_ms = self.attack_ms.max(0.0);
+        self.decay_ms = self.decay_ms.max(0.0);
+        if !self.frame_rate.is_finite() {
+            self.frame_rate = VisualizerConfig::default().frame_rate;
+        }
+        self.frame_rate = self.frame_rate.clamp(1.0, MAX_FRAME_RATE);
+        self.waveform_points = self.waveform_points.min(MAX_WAVEFORM_POINTS);
+        self
+    }
+
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.frame_rate)
+    }
+}
+
+/// Payload of `visualizer::spectrum`: one configured analyzer's output.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct VisualizerSpectrum {
+    pub id: String,
+    pub readings: Vec<FrequencyReading>,
+}
+
+/// Payload of `visualizer::stereo`.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct VisualizerStereo {
+    pub id: String,
+    pub left: Vec<FrequencyReading>,
+    pub right: Vec<FrequencyReading>,
+    pub image: StereoImage,
+}
+
+pub struct SpectrumAnalyzer {
+    frequency_bins: Vec<(f32, f32)>,
+    bin_indices: Vec<Vec<usize>>,
+    weights: Vec<f32>,
+    smoothed_magnitudes: Vec<f32>,
+    attack_coeff: f32,
+    decay_coeff: f32,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(config: &VisualizerConfig, sample_rate: f32, fft_size: usize) -> Self {
+        let (frequency_bins, bin_indices) = Self::create_log_frequency_bins(
+            sample_rate,
+            fft_size,
+            config.bins as usize,
+            config.min_freq,
+            config.max_freq,
+        );
+        let weights = frequency_bins
+            .iter()
+            .map(|(lo, hi)| weighting_response(config.weighting, (lo + hi) / 2.0))
+            .collect();
+        Self {
+            smoothed_magnitudes: vec![0.0; frequency_bins.len()],
+            frequency_bins,
+            bin_indices,
+            weights,
+            attack_coeff: time_constant_to_coeff(config.attack_ms / 1000.0, config.frame_rate),
+            decay_coeff: time_constant_to_coeff(config.decay_ms / 1000.0, config.frame_rate),
+        }
+    }
+
+    fn create_log_frequency_bins(
+        sample_rate: f32,
+        fft_size: usize,
+        num_bins: usize,
+        min_freq: f32,
+        max_freq: f32,
+    ) -> (Vec<(f32, f32)>, Vec<Vec<usize>>) {
+        let nyquist = sample_rate / 2.0;
+        let log_max = max_freq.min(nyquist).ln();
+        let log_min = min_freq.ln().min(log_max);
+        let log_step = (log_max - log_min) / num_bins as f32;
+
+        (0..num_bins)
+            .map(|i| {
+                let freq_lo = (log_min + i as f32 * log_step).exp();
+                let freq_hi = (log_min + (i + 1) as f32 * log_step).exp();
+                let bin_lo = ((freq_lo / nyquist) * (fft_size / 2) as f32).floor() as usize;
+                let bin_hi = ((freq_hi / nyquist) * (fft_size / 2) as f32).ceil() as usize;
+                let indices = (bin_lo..=bin_hi.min(fft_size / 2)).collect();
+                ((freq_lo, freq_hi), indices)
+            })
+            .unzip()
+    }
+
+    /// Folds a fresh magnitude spectrum (`None` on buffer underrun) into the smoothed bars and
+    /// returns them.
+    pub fn process(&mut self, spectrum: Option<&[f32]>) -> Vec<FrequencyReading> {
+        if let Some(spectrum) = spectrum {
+            let mut current = self.scale(spectrum);
+            normalise(&mut [&mut current]);
+            self.smooth(&current);
+        }
+        self.readings()
+    }
+
+    /// Weighted, log-scaled level per bar, before normalisation.
+    fn scale(&self, spectrum: &[f32]) -> Vec<f32> {
+        self.bin_indices
+            .iter()
+            .enumerate()
+            .map(|(bin_idx, indices)| {
+                if indices.is_empty() {
+                    return 0.0;
+                }
+                let mag: f32 =
+                    indices.iter().map(|&i| spectrum[i]).sum::<f32>() / indices.len() as f32;
+
+                let weighted = mag * self.weights[bin_idx];
+                let scaled = ((weighted + 1e-10).log10() + 6.0) / 6.0;
+                scaled.max(0.0)
+            })
+            .collect()
+    }
+
+    fn smooth(&mut self, current: &[f32]) {
+        for (i, &cur) in current.iter().enumerate() {
+            let s = &mut self.smoothed_magnitudes[i];
+            let coeff = if cur > *s {
+                self.attack_coeff
+            } else {
+                self.decay_coeff
+            };
+            *s = *s * coeff + cur * (1.0 - coeff);
+        }
+    }
+
+    fn readings(&self) -> Vec<FrequencyReading> {
+        self.frequency_bins
+            .iter()
+            .zip(&self.smoothed_magnitudes)
+            .map(|((lo, hi), &mag)| FrequencyReading {
+                freq_lo: *lo,
+                freq_hi: *hi,
+                magnitude: mag,
+            })
+            .collect()
+    }
+}
+
+/// Scales every bar in `sets` by the loudest bar across all of them, so the loudest reads 1.0.
+fn normalise(sets: &mut [&mut Vec<f32>]) {
+    let max_mag = sets
+        .iter()
+        .flat_map(|set| set.iter())
+        .fold(0.0_f32, |max, &m| max.max(m));
+    if max_mag > 0.0 {
+        for m in sets.iter_mut().flat_map(|set| set.iter_mut()) {
+            *m = (*m / max_mag).min(1.0);
+        }
+    }
+}
+
+/// A left/right pair of analyzers sharing one normalisation, so the louder channel still draws
+/// taller bars — what a mirrored stereo visualizer needs.
+pub struct StereoAnalyzer {
+    left: SpectrumAnalyzer,
+    right: SpectrumAnalyzer,
+}
+
+impl StereoAnalyzer {
+    pub fn new(config: &VisualizerConfig, sample_rate: f32, fft_size: usize) -> Self {
+        Self {
+            left: SpectrumAnalyzer::new(config, sample_rate, fft_size),
+            right: SpectrumAnalyzer::new(config, sample_rate, fft_size),
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        spectra: Option<(&[f32], &[f32])>,
+    ) -> (Vec<FrequencyReading>, Vec<FrequencyReading>) {
+        if let Some((left, right)) = spectra {
+            let mut l = self.left.scale(left);
+            let mut r = self.right.scale(right);
+            normalise(&mut [&mut l, &mut r]);
+            self.left.smooth(&l);
+            self.right.smooth(&r);
+        }
+        (self.left.readings(), self.right.readings())
+    }
+}
+
+fn weighting_response(weighting: Weighting, freq: f32) -> f32 {
+    match weighting {
+        Weighting::None => 1.0,
+        Weighting::A => a_weighting_response(freq),
+        Weighting::C => c_weighting_response(freq),
+    }
+}
+
+fn a_weighting_response(freq: f32) -> f32 {
+    let f2 = freq * freq;
+    let f4 = f2 * f2;
+    let num = 12194.0_f32.powi(2) * f4;
+    let den = (f2 + 20.6_f32.powi(2))
+        * ((f2 + 107.7_f32.powi(2)) * (f2 + 737.9_f32.powi(2))).sqrt()
+        * (f2 + 12194.0_f32.powi(2));
+    let db = 20.0 * (num / den).log10() + 2.0;
+    10.0_f32.powf(db / 20.0)
+}
+
+fn c_weighting_response(freq: f32) -> f32 {
+    let f2 = freq * freq;
+    let num = 12194.0_f32.powi(2) * f2;
+    let den = (f2 + 20.6_f32.powi(2)) * (f2 + 12194.0_f32.powi(2));
+    let db = 20.0 * (num / den).log10() + 0.06;
+    10.0_f32.powf(db / 20.0)
+}
+
+/// One-pole smoothing coefficient for a time constant `tc` (seconds) at `rate` updates/second.
+fn time_constant_to_coeff(tc: f32, rate: f32) -> f32 {
+    if tc <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (tc * rate)).exp()
+}
+
+/* Per-subscriber configs  */
+
+/// Analysis configs registered by widgets, keyed by the id the widget chose (normally its widget
+/// id), each with the label of the webview that registered it. `generation` bumps on every change
+/// so the visualizer thread only rebuilds analyzers when something actually changed.
+///
+/// A config lives as long as its widget's subscription: unsubscribing from `visualizer` with the
+/// id drops it, and so does a reload of the webview it came from, whose widgets vanish without
+/// unsubscribing.
+#[derive(Default)]
+pub struct VisualizerConfigs(std::sync::Mutex<(u64, HashMap<String, Registered>)>);
+
+struct Registered {
+    /// Label of the webview that registered the config.
+    webview: String,
+    config: VisualizerConfig,
+}
+
+/// A registered config as the visualizer thread sees it.
+struct Configured {
+    id: String,
+    webview: String,
+    config: VisualizerConfig,
+}
+
+impl VisualizerConfigs {
+    fn set(&self, id: String, webview: String, config: VisualizerConfig) {
+        if let Ok(mut guard) = self.0.lock() {
+            guard.0 += 1;
+            guard.1.insert(id, Registered { webview, config });
+        }
+    }
+
+    pub(crate) fn remove(&self, id: &str) {
+        if let Ok(mut guard) = self.0.lock() {
+            if guard.1.remove(id).is_some() {
+                guard.0 += 1;
+            }
+        }
+    }
+
+    /// Drops every config registered from webview `label`.
+    pub fn clear_webview(&self, label: &str) {
+        if let Ok(mut guard) = self.0.lock() {
+            let before = guard.1.len();
+            guard.1.retain(|_, registered| registered.webview != label);
+            let dropped = before - guard.1.len();
+            if dropped > 0 {
+                tracing::debug!(target: FFT_TARGET, webview = label, dropped, "dropped visualizer configs of reloaded webview");
+                guard.0 += 1;
+            }
+        }
+    }
+
+    /// Returns the current configs if they changed since generation `seen`.
+    fn changed_since(&self, seen: u64) -> Option<(u64, Vec<Configured>)> {
+        let guard = self.0.lock().ok()?;
+        (guard.0 != seen).then(|| {
+            let configs = guard
+                .1
+                .iter()
+                .map(|(id, registered)| Configured {
+                    id: id.clone(),
+                    webview: registered.webview.clone(),
+                    config: registered.config.clone(),
+                })
+                .collect();
+            (guard.0, configs)
+        })
+    }
+}
+
+/// Registers (or replaces) the analysis config for `id`. The widget still subscribes to the
+/// `visualizer` channel as usual — that is what keeps capture running — and then listens for
+/// `visualizer::spectrum` payloads carrying its id. It should pass the same id when it
+/// unsubscribes, which drops the config.
+#[tauri::command]
+pub async fn set_visualizer_config(
+    id: String,
+    config: VisualizerConfig,
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+) -> Result<(), String> {
+    let config = config.sanitized();
+    tracing::debug!(target: FFT_TARGET, id = %id, webview = webview.label(), ?config, "invoke: set_visualizer_config");
+    app.state::<VisualizerConfigs>()
+        .set(id, webview.label().to_string(), config);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_visualizer_config(id: String, app: tauri::AppHandle) -> Result<(), String> {
+    tracing::debug!(target: FFT_TARGET, id = %id, "invoke: clear_visualizer_config");
+    app.state::<VisualizerConfigs>().remove(&id);
+    Ok(())
+}
+
+/* Per-frame output scheduling  */
+
+struct Output {
+    /// `None` for the default `stream::visualizer` output.
+    id: Option<String>,
+    /// Label of the webview that registered the config; `None` for the default output, which
+    /// goes to every webview with a subscriber that didn't register one.
+    webview: Option<String>,
+    config: VisualizerConfig,
+    analyzer: SpectrumAnalyzer,
+    stereo: Option<(StereoAnalyzer, CorrelationMeter)>,
+    levels: Option<LevelMeter>,
+    interval: Duration,
+    due: Instant,
+}
+
+impl Output {
+    fn new(
+        id: Option<String>,
+        webview: Option<String>,
+        config: VisualizerConfig,
+        stream: &FFTStream,
+    ) -> Self {
+        Self {
+            analyzer: SpectrumAnalyzer::new(&config, stream.sample_rate, stream.fft_size),
+            stereo: config.stereo.then(|| {
+                (
+                    StereoAnalyzer::new(&config, stream.sample_rate, stream.fft_size),
+                    CorrelationMeter::default(),
+                )
+            }),
+            levels: config.levels.then(LevelMeter::default),
+            interval: config.frame_interval(),
+            due: Instant::now(),
+            id,
+            webview,
+            config,
+        }
+    }
+}
+
+impl Output {
+    /// Frame-rate payloads go through the `FrameGate`, so a webview that stops acking only ever
+    /// has the newest frame waiting for it. The default output goes to `default_webviews`.
+    fn emit(
+        &mut self,
+        app: &tauri::AppHandle,
+        stream: &FFTStream,
+        fresh: bool,
+        default_webviews: &[String],
+    ) {
+        let gate = app.state::<FrameGate>();
+        let (Some(id), Some(webview)) = (&self.id, &self.webview) else {
+            let readings = self.analyzer.process(fresh.then(|| stream.spectrum()));
+            for webview in default_webviews {
+                gate.offer(app, webview, STREAM_VISUALIZER, None, readings.clone());
+            }
+            return;
+        };
+        let tag = Some(id.as_str());
+        if self.config.spectrum {
+            let readings = self.analyzer.process(fresh.then(|| stream.spectrum()));
+            let payload = VisualizerSpectrum {
+                id: id.clone(),
+                readings,
+            };
+            gate.offer(app, webview, VISUALIZER_SPECTRUM, tag, payload);
+        }
+        if let Some((analyzer, meter)) = &mut self.stereo {
+            let (left, right) = analyzer.process(stream.stereo_spectra().filter(|_| fresh));
+            let payload = VisualizerStereo {
+                id: id.clone(),
+                left,
+                right,
+                image: meter.report(),
+            };
+            gate.offer(app, webview, VISUALIZER_STEREO, tag, payload);
+        }
+        if self.config.waveform_points > 0 {
+            let samples = dynamics::waveform(
+                stream.latest(stream.fft_size),
+                self.config.waveform_points as usize,
+            );
+            let payload = VisualizerWaveform {
+                id: id.clone(),
+                samples,
+            };
+            gate.offer(app, webview, VISUALIZER_WAVEFORM, tag, payload);
+        }
+        if let Some(meter) = &mut self.levels {
+            let payload = VisualizerLevels {
+                id: id.clone(),
+                channels: meter.report(),
+            };
+            gate.offer(app, webview, VISUALIZER_LEVELS, tag, payload);
+        }
+    }
+}
+
+/// Owns every analyzer the visualizer thread runs and decides which are due each frame. Both
+/// platform loops hand it their `FFTStream` once per wake-up and sleep for however long it says.
+pub struct VisualizerOutputs {
+    default_config: VisualizerConfig,
+    outputs: Vec<Output>,
+    /// Present while any config asks for beats.
+    beats: Option<BeatDetector>,
+    /// Whether any config asks for stereo, re-applied to every stream the loop hands in.
+    stereo: bool,
+    generation: u64,
+    /// `(sample_rate, fft_size)` the analyzers were built for; a re-created stream with a
+    /// different rate (e.g. after a device switch) needs fresh bin indices.
+    built_for: Option<(f32, usize)>,
+}
+
+impl VisualizerOutputs {
+    pub fn new(default_interval: Duration) -> Self {
+        Self {
+            default_config: VisualizerConfig {
+                frame_rate: 1.0 / default_interval.as_secs_f32().max(f32::EPSILON),
+                ..VisualizerConfig::default()
+            }
+            .sanitized(),
+            outputs: Vec::new(),
+            beats: None,
+            stereo: false,
+            generation: u64::MAX,
+            built_for: None,
+        }
+    }
+
+    fn rebuild(&mut self, app: &tauri::AppHandle, stream: &FFTStream) {
+        let format = (stream.sample_rate, stream.fft_size);
+        let changed = app
+            .state::<VisualizerConfigs>()
+            .changed_since(self.generation);
+        if changed.is_none() && self.built_for == Some(format) {
+            return;
+        }
+        let configs = match changed {
+            Some((generation, configs)) => {
+                self.generation = generation;
+                configs
+            }
+            None => self
+                .outputs
+                .iter()
+                .filter_map(|o| {
+                    Some(Configured {
+                        id: o.id.clone()?,
+                        webview: o.webview.clone()?,
+                        config: o.config.clone(),
+                    })
+                })
+                .collect(),
+        };
+        tracing::debug!(target: FFT_TARGET, configured = configs.len(), sample_rate = stream.sample_rate, "rebuilding spectrum analyzers");
+        self.outputs =
+            std::iter::once(Output::new(None, None, self.default_config.clone(), stream))
+                .chain(
+                    configs
+                        .into_iter()
+                        .map(|c| Output::new(Some(c.id), Some(c.webview), c.config, stream)),
+                )
+                .collect();
+        self.beats = self
+            .outputs
+            .iter()
+            .any(|o| o.config.beats)
+            .then(|| BeatDetector::new(stream.sample_rate, stream.fft_size));
+        self.stereo = self.outputs.iter().any(|o| o.config.stereo);
+        self.built_for = Some(format);
+    }
+
+    /// Runs one visualizer frame: analyses the newest audio and emits every output that is due.
+    /// The default `stream::visualizer` output only runs for webviews with more `visualizer`
+    /// subscribers than configs registered. Returns how long to sleep until the next output is
+    /// due.
+    pub fn tick(&mut self, app: &tauri::AppHandle, stream: &mut FFTStream) -> Duration {
+        self.rebuild(app, stream);
+        stream.set_stereo(self.stereo);
+        let fresh = stream.update();
+        let default_webviews: Vec<String> = app
+            .state::<Channels>()
+            .webviews(StreamName::Visualizer)
+            .into_iter()
+            .filter(|(label, subscribers)| {
+                let configured = self
+                    .outputs
+                    .iter()
+                    .filter(|o| o.webview.as_ref() == Some(label))
+                    .count();
+                *subscribers > configured
+            })
+            .map(|(label, _)| label)
+            .collect();
+        let default_needed = !default_webviews.is_empty();
+
+        let now = Instant::now();
+        if let (true, Some(detector)) = (fresh, &mut self.beats) {
+            if let Some(beat) = detector.process(stream.spectrum(), now) {
+                tracing::trace!(target: FFT_TARGET, ?beat, "beat");
+                let _ = app.emit(VISUALIZER_BEAT, beat);
+            }
+        }
+
+        let mut next_due = now + self.default_config.frame_interval();
+        for output in &mut self.outputs {
+            if output.id.is_none() && !default_needed {
+                continue;
+            }
+            // Levels integrate every captured frame, not just the ones around emit time.
+            if let Some(meter) = &mut output.levels {
+                meter.accumulate(stream.incoming(), stream.channels);
+            }
+            if let Some((_, meter)) = &mut output.stereo {
+                meter.accumulate(stream.incoming(), stream.channels);
+            }
+            if output.due <= now {
+                output.emit(app, stream, fresh, &default_webviews);
+                // Schedule from the previous due time so the rate doesn't drift with jitter, but
+                // don't try to catch up after a stall.
+                output.due = (output.due + output.interval).max(now);
+            }
+            next_due = next_due.min(output.due);
+        }
+        next_due.saturating_duration_since(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    #[test]
+    fn weightings_follow_the_iec_61672_tables() {
+        // The tables are at exact frequencies (10^(n/10) Hz) under nominal labels like 31.5 Hz.
+        let table = [
+            // Hz, A dB, C dB
+            (31.62, -39.4, -3.0),
+            (100.0, -19.1, -0.3),
+            (1_000.0, 0.0, 0.0),
+            (3_981.0, 1.0, -0.8),
+            (10_000.0, -2.5, -4.4),
+        ];
+        for (freq, a, c) in table {
+            let got = db(weighting_response(Weighting::A, freq));
+            assert!((got - a).abs() < 0.1, "A at {freq} Hz: {got} dB");
+            let got = db(weighting_response(Weighting::C, freq));
+            assert!((got - c).abs() < 0.1, "C at {freq} Hz: {got} dB");
+            assert_eq!(weighting_response(Weighting::None, freq), 1.0);
+        }
+    }
+
+    #[test]
+    fn a_weighting_tilts_a_flat_spectrum() {
+        let config = VisualizerConfig {
+            attack_ms: 0.0,
+            ..VisualizerConfig::default()
+        };
+        let mut analyzer = SpectrumAnalyzer::new(&config, 48_000.0, 4096);
+        let readings = analyzer.process(Some(&[1.0; 2049]));
+        let at = |freq: f32| {
+            readings
+                .iter()
+                .find(|r| r.freq_lo <= freq && freq < r.freq_hi)
+                .unwrap()
+                .magnitude
+        };
+        assert!(at(30.0) < at(100.0));
+        assert!(at(100.0) < at(1_000.0));
+        assert!(at(2_500.0) > 0.99);
+        assert!(at(18_000.0) < at(1_000.0));
+    }
+}
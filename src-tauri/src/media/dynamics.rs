@@ -0,0 +1,478 @@
+//! Time-domain companions to the spectrum, computed from the same `FFTStream` capture: a
+//! downsampled oscilloscope waveform, per-channel peak/RMS levels in dBFS (absolute — unlike the
+//! spectrum, which is normalised to each frame's loudest bin), and spectral-flux onset detection
+//! with a running tempo estimate.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ts_rs::TS;
+
+/// Level reported for digital silence; keeps `-inf` out of the JSON payloads.
+pub const DBFS_FLOOR: f32 = -120.0;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct ChannelLevel {
+    /// Largest absolute sample since the previous report.
+    pub peak_dbfs: f32,
+    /// RMS since the previous report; a full-scale sine reads about -3 dBFS.
+    pub rms_dbfs: f32,
+}
+
+/// Payload of `visualizer::levels`, one entry per captured channel (in capture order).
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct VisualizerLevels {
+    pub id: String,
+    pub channels: Vec<ChannelLevel>,
+}
+
+/// Payload of `visualizer::waveform`: mono samples in `-1.0..=1.0`, oldest first.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct VisualizerWaveform {
+    pub id: String,
+    pub samples: Vec<f32>,
+}
+
+/// Payload of `visualizer::beat`, emitted once per detected onset.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct VisualizerBeat {
+    /// How far the onset cleared the adaptive threshold, `0.0..=1.0`.
+    pub strength: f32,
+    /// Tempo estimate from recent onsets; `None` until there are enough of them.
+    pub bpm: Option<f32>,
+    /// How consistently recent onsets land on the `bpm` grid, `0.0..=1.0`.
+    pub confidence: f32,
+}
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(DBFS_FLOOR)
+    } else {
+        DBFS_FLOOR
+    }
+}
+
+/* Levels  */
+
+/// Accumulates peak and mean-square per channel between reports, so every captured frame counts
+/// exactly once no matter how the report rate relates to the capture's chunk size.
+#[derive(Default)]
+pub struct LevelMeter {
+    peak: Vec<f32>,
+    sum_sq: Vec<f64>,
+    frames: usize,
+    last: Vec<ChannelLevel>,
+}
+
+impl LevelMeter {
+    pub fn accumulate(&mut self, interleaved: &[f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        if self.peak.len() != channels {
+            self.peak = vec![0.0; channels];
+            self.sum_sq = vec![0.0; channels];
+            self.frames = 0;
+            self.last.clear();
+        }
+        for frame in interleaved.chunks_exact(channels) {
+            for (c, &s) in frame.iter().enumerate() {
+                self.peak[c] = self.peak[c].max(s.abs());
+                self.sum_sq[c] += (s as f64) * (s as f64);
+            }
+            self.frames += 1;
+        }
+    }
+
+    /// Levels since the previous call. If no audio arrived in between (the report rate outran
+    /// the capture's chunks) the previous levels are repeated rather than dropping to silence.
+    pub fn report(&mut self) -> Vec<ChannelLevel> {
+        if self.frames > 0 {
+            self.last = self
+                .peak
+                .iter()
+                .zip(&self.sum_sq)
+                .map(|(&peak, &sum_sq)| ChannelLevel {
+                    peak_dbfs: to_dbfs(peak),
+                    rms_dbfs: to_dbfs((sum_sq / self.frames as f64).sqrt() as f32),
+                })
+                .collect();
+            self.peak.iter_mut().for_each(|p| *p = 0.0);
+            self.sum_sq.iter_mut().for_each(|s| *s = 0.0);
+            self.frames = 0;
+        }
+        self.last.clone()
+    }
+}
+
+/// Phase correlation and mid/side balance between the first two channels, accumulated between
+/// reports like [`LevelMeter`].
+#[derive(Default)]
+pub struct CorrelationMeter {
+    lr: f64,
+    ll: f64,
+    rr: f64,
+    frames: usize,
+    last: StereoImage,
+}
+
+/// What a correlation meter widget draws.
+#[derive(serde::Serialize, Clone, Copy, Debug, Default, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct StereoImage {
+    /// Pearson correlation of left and right, `-1.0..=1.0`: +1 mono, 0 unrelated (wide or
+    /// decorrelated), -1 out of phase. 0 during silence.
+    pub correlation: f32,
+    /// Side energy as a share of mid + side energy, `0.0..=1.0`: 0 mono, 0.5 unrelated channels,
+    /// 1 fully out of phase.
+    pub width: f32,
+}
+
+impl CorrelationMeter {
+    pub fn accumulate(&mut self, interleaved: &[f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+        for frame in interleaved.chunks_exact(channels) {
+            let l = frame[0] as f64;
+            let r = *frame.get(1).unwrap_or(&frame[0]) as f64;
+            self.lr += l * r;
+            self.ll += l * l;
+            self.rr += r * r;
+            self.frames += 1;
+        }
+    }
+
+    /// Correlation since the previous call; repeats the last value if no audio arrived.
+    pub fn report(&mut self) -> StereoImage {
+        if self.frames > 0 {
+            let energy = self.ll + self.rr;
+            self.last = if energy > 0.0 {
+                let norm = (self.ll * self.rr).sqrt();
+                StereoImage {
+                    correlation: if norm > 0.0 {
+                        (self.lr / norm) as f32
+                    } else {
+                        0.0
+                    },
+                    // side² = (l - r)² / 4, mid² + side² = (l² + r²) / 2.
+                    width: ((energy - 2.0 * self.lr) / (2.0 * energy)) as f32,
+                }
+            } else {
+                StereoImage::default()
+            };
+            *self = Self {
+                last: self.last,
+                ..Self::default()
+            };
+        }
+        self.last
+    }
+}
+
+/* Waveform  */
+
+/// Downsamples `mono` to `points` values for an oscilloscope. Shows the newer half of the
+/// buffer, starting at a rising zero crossing when there is one so a steady tone stands still
+/// instead of scrolling. Each point keeps its bucket's largest-magnitude sample, so transients
+/// survive the decimation.
+pub fn waveform(mono: &[f32], points: usize) -> Vec<f32> {
+    let span = mono.len() / 2;
+    if span == 0 || points == 0 {
+        return vec![0.0; points];
+    }
+    let start = (1..=span)
+        .find(|&i| mono[i - 1] < 0.0 && mono[i] >= 0.0)
+        .unwrap_or(span);
+    let window = &mono[start..start + span];
+
+    (0..points)
+        .map(|i| {
+            let lo = i * span / points;
+            let hi = ((i + 1) * span / points).max(lo + 1).min(span);
+            window[lo..hi]
+                .iter()
+                .copied()
+                .fold(0.0_f32, |acc, s| if s.abs() > acc.abs() { s } else { acc })
+        })
+        .collect()
+}
+
+/* Beat detection  */
+
+/// Onsets come from bass and low-mid energy (kick, snare, bass notes); hats and cymbals above
+/// this mostly add noise to the flux.
+const FLUX_MAX_FREQ: f32 = 2_000.0;
+/// How much flux history the adaptive threshold averages over.
+const FLUX_WINDOW: Duration = Duration::from_millis(1500);
+/// Standard deviations above the mean flux an onset has to reach.
+const THRESHOLD_K: f32 = 1.5;
+/// Flux below this is treated as silence, however quiet the surrounding history is.
+const MIN_FLUX: f32 = 0.5;
+/// Refractory period — caps detection at 200 BPM and swallows flams.
+const MIN_BEAT_INTERVAL: Duration = Duration::from_millis(300);
+/// Onsets older than this drop out of the tempo estimate.
+const TEMPO_WINDOW: Duration = Duration::from_secs(8);
+/// Tempos are folded into this single octave: half- and double-time are indistinguishable from
+/// onsets alone, so a 70 BPM ballad reads as 140.
+const MIN_BPM: f32 = 90.0;
+const MAX_BPM: f32 = 180.0;
+const TEMPO_STEP_BPM: f32 = 0.25;
+/// Onsets needed before a tempo is reported.
+const MIN_TEMPO_ONSETS: usize = 4;
+
+pub struct BeatDetector {
+    max_bin: usize,
+    previous: Vec<f32>,
+    flux: VecDeque<(Instant, f32)>,
+    onsets: VecDeque<Instant>,
+    /// Set once flux drops back under the threshold; an onset needs a fresh rising edge.
+    armed: bool,
+}
+
+impl BeatDetector {
+    pub fn new(sample_rate: f32, fft_size: usize) -> Self {
+        let max_bin = ((FLUX_MAX_FREQ / (sample_rate / 2.0)) * (fft_size / 2) as f32) as usize;
+        Self {
+            max_bin: max_bin.clamp(1, fft_size / 2),
+            previous: Vec::new(),
+            flux: VecDeque::new(),
+            onsets: VecDeque::new(),
+            armed: true,
+        }
+    }
+
+    /// Feeds one fresh magnitude spectrum. Returns a beat when it holds an onset.
+    pub fn process(&mut self, spectrum: &[f32], now: Instant) -> Option<VisualizerBeat> {
+        let current: Vec<f32> = spectrum[..self.max_bin.min(spectrum.len())]
+            .iter()
+            .map(|&m| (m * 100.0).ln_1p())
+            .collect();
+        let flux: f32 = if self.previous.len() == current.len() {
+            current
+                .iter()
+                .zip(&self.previous)
+                .map(|(c, p)| (c - p).max(0.0))
+                .sum()
+        } else {
+            0.0
+        };
+        self.previous = current;
+
+        while self
+            .flux
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > FLUX_WINDOW)
+        {
+            self.flux.pop_front();
+        }
+        let threshold = if self.flux.len() >= 2 {
+            let n = self.flux.len() as f32;
+            let mean = self.flux.iter().map(|(_, f)| f).sum::<f32>() / n;
+            let var = self
+                .flux
+                .iter()
+                .map(|(_, f)| (f - mean).powi(2))
+                .sum::<f32>()
+                / n;
+            (mean + THRESHOLD_K * var.sqrt()).max(MIN_FLUX)
+        } else {
+            f32::INFINITY
+        };
+        self.flux.push_back((now, flux));
+
+        if flux < threshold {
+            self.armed = true;
+            return None;
+        }
+        let refractory = self
+            .onsets
+            .back()
+            .is_some_and(|t| now.duration_since(*t) < MIN_BEAT_INTERVAL);
+        if !self.armed || refractory {
+            return None;
+        }
+        self.armed = false;
+
+        self.onsets.push_back(now);
+        while self
+            .onsets
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > TEMPO_WINDOW)
+        {
+            self.onsets.pop_front();
+        }
+        let (bpm, confidence) = self.estimate_tempo().unzip();
+        Some(VisualizerBeat {
+            strength: ((flux - threshold) / threshold).clamp(0.0, 1.0),
+            bpm,
+            confidence: confidence.unwrap_or(0.0),
+        })
+    }
+
+    /// Phase-coherence fit: for each candidate tempo, how tightly the recent onsets line up on a
+    /// grid with that period (1.0 when every onset falls exactly on a grid line). Tolerates the
+    /// frame-rate jitter on individual onset times far better than averaging raw intervals.
+    fn estimate_tempo(&self) -> Option<(f32, f32)> {
+        if self.onsets.len() < MIN_TEMPO_ONSETS {
+            return None;
+        }
+        let newest = *self.onsets.back()?;
+        let ages: Vec<f32> = self
+            .onsets
+            .iter()
+            .map(|t| newest.duration_since(*t).as_secs_f32())
+            .collect();
+        let n = ages.len() as f32;
+
+        let steps = ((MAX_BPM - MIN_BPM) / TEMPO_STEP_BPM) as usize;
+        (0..steps)
+            .map(|i| {
+                let bpm = MIN_BPM + i as f32 * TEMPO_STEP_BPM;
+                let omega = std::f32::consts::TAU * bpm / 60.0;
+                let (re, im) = ages.iter().fold((0.0, 0.0), |(re, im), age| {
+                    (re + (omega * age).cos(), im + (omega * age).sin())
+                });
+                (bpm, (re * re + im * im).sqrt() / n)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::source::{Pacing, Signal, SyntheticSource};
+    use crate::media::{AudioSource, FFTStream};
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn sine(freq: f32, amplitude: f32, samples: usize) -> Vec<f32> {
+        let signal = Signal::Sine { freq, amplitude };
+        let mut source = SyntheticSource::new(signal, SAMPLE_RATE, Pacing::Fixed(samples));
+        let mut out = Vec::new();
+        source.read(&mut out);
+        out
+    }
+
+    #[test]
+    fn full_scale_sine_reads_minus_3_rms_and_0_peak() {
+        let mono = sine(1_000.0, 1.0, SAMPLE_RATE as usize / 10);
+        // Left carries the sine, right is silent.
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, 0.0]).collect();
+        let mut meter = LevelMeter::default();
+        meter.accumulate(&stereo, 2);
+        let levels = meter.report();
+        assert_eq!(levels.len(), 2);
+        assert!(levels[0].peak_dbfs.abs() < 0.01, "{:?}", levels[0]);
+        assert!((levels[0].rms_dbfs + 3.01).abs() < 0.01, "{:?}", levels[0]);
+        assert_eq!(levels[1].peak_dbfs, DBFS_FLOOR);
+        assert_eq!(levels[1].rms_dbfs, DBFS_FLOOR);
+
+        // Nothing new since: the last levels are repeated rather than dropping to silence.
+        assert_eq!(meter.report()[0].rms_dbfs, levels[0].rms_dbfs);
+        meter.accumulate(&[0.0; 200], 2);
+        assert_eq!(meter.report()[0].rms_dbfs, DBFS_FLOOR);
+    }
+
+    #[test]
+    fn correlation_tells_mono_from_out_of_phase() {
+        let mono = sine(440.0, 0.5, 4_800);
+        let mut meter = CorrelationMeter::default();
+        meter.accumulate(&mono, 1);
+        let image = meter.report();
+        assert!((image.correlation - 1.0).abs() < 1e-4);
+        assert!(image.width.abs() < 1e-4);
+
+        let inverted: Vec<f32> = mono.iter().flat_map(|&s| [s, -s]).collect();
+        meter.accumulate(&inverted, 2);
+        let image = meter.report();
+        assert!((image.correlation + 1.0).abs() < 1e-4);
+        assert!((image.width - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn waveform_starts_at_a_rising_zero_crossing() {
+        // Start mid-cycle, on a falling slope.
+        let mono = sine(440.0, 0.8, 4_096 + 40)[40..].to_vec();
+        assert!(mono[0] > 0.0 && mono[1] < mono[0]);
+        let points = waveform(&mono, 2_048);
+        assert_eq!(points.len(), 2_048);
+        // At 440 Hz a sample advances the phase by ~0.058 rad, so the first one past the
+        // crossing is within 0.8 * sin(0.058) of zero.
+        assert!(points[0] >= 0.0 && points[0] < 0.05, "{}", points[0]);
+        assert!(points[1] > points[0]);
+
+        assert_eq!(waveform(&[0.0; 4_096], 16), vec![0.0; 16]);
+        assert_eq!(waveform(&[], 4), vec![0.0; 4]);
+        assert!(waveform(&mono, 0).is_empty());
+    }
+
+    /// A one-sample click every `interval` samples.
+    struct Clicks {
+        interval: usize,
+        position: usize,
+        per_read: usize,
+    }
+
+    impl AudioSource for Clicks {
+        fn name(&self) -> &str {
+            "clicks"
+        }
+
+        fn sample_rate(&self) -> f32 {
+            SAMPLE_RATE
+        }
+
+        fn channels(&self) -> usize {
+            1
+        }
+
+        fn read(&mut self, out: &mut Vec<f32>) {
+            let start = self.position;
+            self.position += self.per_read;
+            let click = |i: usize| i.is_multiple_of(self.interval);
+            out.extend((start..self.position).map(|i| if click(i) { 1.0 } else { 0.0 }));
+        }
+    }
+
+    #[test]
+    fn a_120_bpm_click_train_reads_120_bpm() {
+        const FFT_SIZE: usize = 2048;
+        // 60 updates a second, clicks every half second.
+        let per_read = SAMPLE_RATE as usize / 60;
+        let clicks = Clicks {
+            interval: SAMPLE_RATE as usize / 2,
+            position: SAMPLE_RATE as usize / 4,
+            per_read,
+        };
+        let mut stream = FFTStream::with_source(Box::new(clicks), FFT_SIZE);
+        let mut detector = BeatDetector::new(SAMPLE_RATE, FFT_SIZE);
+        let start = Instant::now();
+
+        let mut beats = Vec::new();
+        for frame in 0..60 * 10 {
+            let now = start + Duration::from_secs_f32(frame as f32 / 60.0);
+            if stream.update() {
+                if let Some(beat) = detector.process(stream.spectrum(), now) {
+                    beats.push((now, beat));
+                }
+            }
+        }
+
+        // Clicks at 0.25 s, 0.75 s, …, 9.75 s: every one is an onset, half a second apart.
+        assert_eq!(beats.len(), 20);
+        for pair in beats.windows(2) {
+            let interval = pair[1].0 - pair[0].0;
+            assert!(interval.abs_diff(Duration::from_millis(500)) < Duration::from_millis(20));
+        }
+        let (_, last) = beats.last().unwrap();
+        let bpm = last.bpm.expect("a tempo after ten seconds of clicks");
+        assert!((bpm - 120.0).abs() <= 1.0, "{bpm} BPM");
+        assert!(last.confidence > 0.9, "confidence {}", last.confidence);
+    }
+}
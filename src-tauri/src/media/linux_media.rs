@@ -1,20 +1,1499 @@
+use base64::Engine;
+use futures_util::future::BoxFuture;
+use futures_util::{FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::fmt;
 use std::sync::{
-    atomic::{AtomicUsize},
+    atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::Duration;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+use super::{
+    emit_media, MediaCapabilities, MediaSession, MediaState, PlaybackState, RepeatMode, SourceApp,
+};
+use crate::channel::{unix_ms, SubscriberGate};
 
 const TARGET: &str = "media::linux";
 
-pub async fn run_media_loop(_app: tauri::AppHandle, _subscribers: Arc<AtomicUsize>, _poll_interval: std::time::Duration) {
-    tracing::warn!(target: TARGET, "media loop not implemented on Linux");
+/// Every MPRIS2 player owns a well-known name under this prefix, e.g.
+/// `org.mpris.MediaPlayer2.spotify` or `org.mpris.MediaPlayer2.firefox.instance_1_42`.
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Covers are a few hundred KB; anything past this is not one, and isn't worth holding in every
+/// published state.
+const ART_MAX_BYTES: u64 = 8 * 1024 * 1024;
+/// For the whole `artUrl` fetch, so a stalled thumbnail server can't hold the art back forever.
+const ART_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn e<T: fmt::Debug>(e: T) -> String {
+    format!("{:?}", e)
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2",
+    gen_blocking = false
+)]
+trait Player {
+    fn play(&self) -> zbus::Result<()>;
+    fn pause(&self) -> zbus::Result<()>;
+    fn play_pause(&self) -> zbus::Result<()>;
+    fn next(&self) -> zbus::Result<()>;
+    fn previous(&self) -> zbus::Result<()>;
+    /// Relative, in microseconds.
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
+    /// Absolute, in microseconds. Ignored unless `track_id` is the current track's.
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    /// Microseconds. Players never emit `PropertiesChanged` for this one.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> zbus::Result<i64>;
+    #[zbus(property)]
+    fn set_volume(&self, value: f64) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn set_shuffle(&self, value: bool) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn set_loop_status(&self, value: &str) -> zbus::Result<()>;
+}
+
+/// Bus name of the player the media loop is currently following. The control commands target
+/// this same player so the buttons act on whatever the widget is showing, not on whichever
+/// player happens to sort first.
+static ACTIVE_PLAYER: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+fn active_player() -> Option<String> {
+    ACTIVE_PLAYER.lock().ok()?.clone()
+}
+
+fn set_active_player(name: Option<String>) {
+    if let Ok(mut guard) = ACTIVE_PLAYER.lock() {
+        if *guard != name {
+            tracing::info!(target: TARGET, player = ?name, "active player changed");
+            *guard = name;
+        }
+    }
+}
+
+/// The session bus connection, shared by the media loop and the control commands. zbus
+/// connections are cheap `Arc` clones, so handing out copies is fine.
+async fn session_bus() -> Result<zbus::Connection, String> {
+    static BUS: tokio::sync::OnceCell<zbus::Connection> = tokio::sync::OnceCell::const_new();
+    BUS.get_or_try_init(|| async { zbus::Connection::session().await.map_err(e) })
+        .await
+        .cloned()
+}
+
+async fn player_proxy(conn: &zbus::Connection, name: String) -> zbus::Result<PlayerProxy<'static>> {
+    PlayerProxy::builder(conn)
+        .destination(name)?
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await
+}
+
+/// The root interface, for the player's own name.
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2",
+    gen_blocking = false
+)]
+trait MediaPlayer2 {
+    #[zbus(property)]
+    fn identity(&self) -> zbus::Result<String>;
+    /// Basename of the player's `.desktop` file, e.g. `firefox`.
+    #[zbus(property)]
+    fn desktop_entry(&self) -> zbus::Result<String>;
+}
+
+#[derive(Default)]
+struct PlayerCache {
+    /// `mpris:artUrl` of the followed track, and its artwork once `read_art` has loaded it.
+    url: Option<String>,
+    b64: Option<String>,
+    /// Set when `url` changes, for the media loop to start loading it.
+    art_due: Option<String>,
+    /// The last state emitted for an active player, re-emitted with the artwork once it arrives.
+    last: Option<MediaState>,
+    /// Per bus name; a player's name doesn't change while it owns it. Names gone from the bus
+    /// are dropped on the next rescan, so players that come and go (browser tabs, each with
+    /// its own instance name) don't pile up.
+    sources: HashMap<String, SourceApp>,
+    /// Unique connection name behind each player's bus name, to tell which player a signal came
+    /// from. Rebuilt on every rescan.
+    owners: HashMap<String, String>,
+}
+
+/* Media event loop  */
+
+pub async fn run_media_loop(
+    app: tauri::AppHandle,
+    mut gate: SubscriberGate,
+    poll_interval: Duration,
+) {
+    let conn = loop {
+        tracing::info!(target: TARGET, "connecting to D-Bus session bus");
+        match session_bus().await {
+            Ok(c) => {
+                tracing::info!(target: TARGET, "session bus connected");
+                break c;
+            }
+            Err(err) => {
+                tracing::warn!(target: TARGET, error = %err, "session bus connection failed; retrying in 5s");
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    };
+
+    let (mut player_signals, mut owner_signals) = match mpris_signal_streams(&conn).await {
+        Ok(streams) => streams,
+        Err(err) => {
+            tracing::error!(target: TARGET, error = ?err, "failed to subscribe to MPRIS signals — media stream unavailable");
+            return;
+        }
+    };
+
+    let mut follower = Follower {
+        conn,
+        cache: PlayerCache::default(),
+        playing: false,
+    };
+    follower.rescan().await;
+    emit_media(&app, follower.read_active().await);
+    // Set when a change went unread because nobody was subscribed, so the next subscriber gets a
+    // fresh read rather than whatever was last published.
+    let mut stale = false;
+    // The artwork of a new track loads alongside the signals rather than holding them up; a
+    // newer track's replaces (and so cancels) a load still in flight.
+    let mut art: Option<BoxFuture<'static, (String, Option<String>)>> = None;
+
+    // Keepalive poll — only re-reads the timeline position during active playback, since MPRIS
+    // players never signal drift; `emit_media` drops the reading unless it has strayed from
+    // where subscribers extrapolate it to. Everything else arrives as signals.
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        if let Some(url) = follower.cache.art_due.take() {
+            art = Some(
+                async move {
+                    let b64 = read_art(&url).await;
+                    (url, b64)
+                }
+                .boxed(),
+            );
+        }
+        tokio::select! {
+            (url, b64) = async { art.as_mut().expect("guarded by the precondition").await }, if art.is_some() => {
+                art = None;
+                if let Some(state) = follower.art_loaded(&url, b64) {
+                    emit_media(&app, state);
+                }
+            }
+            msg = player_signals.next() => {
+                let Some(msg) = msg else {
+                    tracing::error!(target: TARGET, "MPRIS signal stream closed — media loop exiting");
+                    return;
+                };
+                if !gate.should_run() {
+                    stale = true;
+                } else if let Ok(msg) = msg {
+                    if follower.on_signal(&msg).await {
+                        emit_media(&app, follower.read_active().await);
+                    }
+                }
+            }
+            msg = owner_signals.next() => {
+                if msg.is_none() {
+                    tracing::error!(target: TARGET, "NameOwnerChanged stream closed — media loop exiting");
+                    return;
+                }
+                tracing::debug!(target: TARGET, "MPRIS player appeared or vanished");
+                // Always re-selected, even unsubscribed, so the control commands never target a
+                // player that has gone.
+                follower.rescan().await;
+                stale = true;
+            }
+            _ = interval.tick() => {
+                if gate.should_run() && (stale || follower.playing) {
+                    stale = false;
+                    emit_media(&app, follower.read_active().await);
+                }
+            }
+            _ = super::SESSION_PINNED.notified() => {
+                follower.rescan().await;
+                stale = true;
+            }
+        }
+    }
+}
+
+/// Subscribes to every signal on the MPRIS object path (`PropertiesChanged` and `Seeked`, from any
+/// player) and to bus name changes under the MPRIS prefix, so players that start or quit are
+/// picked up without polling.
+async fn mpris_signal_streams(
+    conn: &zbus::Connection,
+) -> zbus::Result<(zbus::MessageStream, zbus::MessageStream)> {
+    let player_rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .path(MPRIS_PATH)?
+        .build();
+    let owner_rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameOwnerChanged")?
+        .arg0ns("org.mpris.MediaPlayer2")?
+        .build();
+    Ok((
+        zbus::MessageStream::for_match_rule(player_rule, conn, None).await?,
+        zbus::MessageStream::for_match_rule(owner_rule, conn, None).await?,
+    ))
+}
+
+/// The player the media loop follows and what it last read from it. Signals name the player
+/// they concern, so only that one is re-read; the bus is only rescanned when players come or go
+/// or the pin changes.
+struct Follower {
+    conn: zbus::Connection,
+    cache: PlayerCache,
+    /// Whether the last read found the followed player playing, for the keepalive poll.
+    playing: bool,
+}
+
+impl Follower {
+    /// Lists the players on the bus and re-selects the one to follow.
+    async fn rescan(&mut self) {
+        let players = list_players(&self.conn)
+            .await
+            .map_err(|err| tracing::warn!(target: TARGET, error = ?err, "ListNames failed"))
+            .unwrap_or_default();
+        self.cache.sources.retain(|name, _| players.contains(name));
+        self.cache.owners = name_owners(&self.conn, &players).await;
+        let current = active_player();
+        let pinned = super::pinned_session();
+        let chosen =
+            pick_active_player(&self.conn, players, current.as_deref(), pinned.as_deref()).await;
+        if chosen.is_none() && current.is_some() {
+            tracing::info!(target: TARGET, "no active media player");
+        }
+        set_active_player(chosen);
+    }
+
+    /// Reads the followed player's state, or the inactive one if there is none.
+    async fn read_active(&mut self) -> MediaState {
+        let state = match active_player() {
+            Some(name) => fetch_media_state(&self.conn, name, &mut self.cache).await,
+            None => MediaState::inactive(),
+        };
+        self.playing = state.playing;
+        self.cache.last = state.active.then(|| state.clone());
+        state
+    }
+
+    /// Handles a signal from a player's object and returns whether the followed player needs
+    /// re-reading: it sent the signal, or the sender (say, one that just started playing) now
+    /// wins [`choose_player`] over it.
+    async fn on_signal(&mut self, msg: &zbus::Message) -> bool {
+        let header = msg.header();
+        let Some(sender) = header.sender() else {
+            return false;
+        };
+        // One connection can own several MPRIS names. A sender not listed yet is a player whose
+        // `NameOwnerChanged` hasn't been handled; the rescan that follows picks it up.
+        let names: Vec<&String> = self
+            .cache
+            .owners
+            .iter()
+            .filter(|(_, owner)| owner.as_str() == sender.as_str())
+            .map(|(name, _)| name)
+            .collect();
+        let current = active_player();
+        let followed = current.as_ref().is_some_and(|name| names.contains(&name));
+        if followed {
+            return true;
+        }
+
+        let mut statuses: Vec<(String, String)> = current
+            .iter()
+            .map(|name| {
+                let status = if self.playing { "Playing" } else { "Paused" };
+                (name.clone(), status.to_string())
+            })
+            .collect();
+        for name in names {
+            statuses.push((name.clone(), playback_status(&self.conn, name).await));
+        }
+        let pinned = super::pinned_session();
+        let chosen = choose_player(&statuses, current.as_deref(), pinned.as_deref());
+        if chosen == current {
+            return false;
+        }
+        set_active_player(chosen);
+        true
+    }
+
+    /// Takes `read_art`'s result for `url`, returning the last state again with the artwork if it
+    /// is still for the followed track.
+    fn art_loaded(&mut self, url: &str, b64: Option<String>) -> Option<MediaState> {
+        if self.cache.url.as_deref() != Some(url) {
+            return None;
+        }
+        self.cache.b64 = b64;
+        let b64 = self.cache.b64.clone()?;
+        let state = self.cache.last.as_mut()?;
+        state.album_art_b64 = Some(b64);
+        Some(state.clone())
+    }
+}
+
+async fn list_players(conn: &zbus::Connection) -> zbus::Result<Vec<String>> {
+    let dbus = zbus::fdo::DBusProxy::new(conn).await?;
+    let mut players: Vec<String> = dbus
+        .list_names()
+        .await?
+        .into_iter()
+        .map(|n| n.to_string())
+        .filter(|n| n.starts_with(MPRIS_PREFIX))
+        .collect();
+    players.sort();
+    Ok(players)
+}
+
+/// Asks each of `players` (the MPRIS names on the bus) for its `PlaybackStatus`, then leaves
+/// the choice to [`choose_player`].
+async fn pick_active_player(
+    conn: &zbus::Connection,
+    players: Vec<String>,
+    current: Option<&str>,
+    pinned: Option<&str>,
+) -> Option<String> {
+    let mut statuses = Vec::with_capacity(players.len());
+    for name in players {
+        let status = playback_status(conn, &name).await;
+        statuses.push((name, status));
+    }
+    choose_player(&statuses, current, pinned)
+}
+
+/// `name`'s `PlaybackStatus`, or empty if it won't say.
+async fn playback_status(conn: &zbus::Connection, name: &str) -> String {
+    match player_proxy(conn, name.to_string()).await {
+        Ok(p) => p.playback_status().await.unwrap_or_default(),
+        Err(_) => String::new(),
+    }
+}
+
+/// The unique connection name owning each of `players`.
+async fn name_owners(conn: &zbus::Connection, players: &[String]) -> HashMap<String, String> {
+    let Ok(dbus) = zbus::fdo::DBusProxy::new(conn).await else {
+        return HashMap::new();
+    };
+    let mut owners = HashMap::with_capacity(players.len());
+    for name in players {
+        let Ok(bus_name) = zbus::names::BusName::try_from(name.as_str()) else {
+            continue;
+        };
+        if let Ok(owner) = dbus.get_name_owner(bus_name).await {
+            owners.insert(name.clone(), owner.to_string());
+        }
+    }
+    owners
+}
+
+/// Chooses which player to follow from `(bus name, PlaybackStatus)` pairs. The pinned player
+/// wins while it's on the bus. Otherwise a playing player always wins, preferring the one already
+/// followed so two simultaneously playing players don't make the widget flip between them.
+/// Failing that, the current player is kept while it still exists (paused), then any paused
+/// player, then whatever is on the bus.
+fn choose_player(
+    statuses: &[(String, String)],
+    current: Option<&str>,
+    pinned: Option<&str>,
+) -> Option<String> {
+    let on_bus = |name: &str| statuses.iter().any(|(n, _)| n == name);
+    if let Some(pinned) = pinned.filter(|p| on_bus(p)) {
+        return Some(pinned.to_string());
+    }
+
+    let is_current = |name: &str| current == Some(name);
+    statuses
+        .iter()
+        .find(|(n, s)| s == "Playing" && is_current(n))
+        .or_else(|| statuses.iter().find(|(_, s)| s == "Playing"))
+        .or_else(|| statuses.iter().find(|(n, _)| is_current(n)))
+        .or_else(|| statuses.iter().find(|(_, s)| s == "Paused"))
+        .or_else(|| statuses.first())
+        .map(|(n, _)| n.clone())
+}
+
+async fn fetch_media_state(
+    conn: &zbus::Connection,
+    name: String,
+    cache: &mut PlayerCache,
+) -> MediaState {
+    let player = match player_proxy(conn, name.clone()).await {
+        Ok(p) => p,
+        Err(err) => {
+            tracing::warn!(target: TARGET, player = %name, error = ?err, "player proxy creation failed");
+            return MediaState::inactive();
+        }
+    };
+
+    let metadata = match player.metadata().await {
+        Ok(m) => m,
+        Err(err) => {
+            tracing::warn!(target: TARGET, player = %name, error = ?err, "Metadata read failed");
+            return MediaState::inactive();
+        }
+    };
+    let playing = player
+        .playback_status()
+        .await
+        .map(|s| s == "Playing")
+        .unwrap_or(false);
+
+    let title = metadata_str(&metadata, "xesam:title").unwrap_or_default();
+    if title.is_empty() && !playing {
+        tracing::trace!(target: TARGET, "no title and not playing — treating player as inactive");
+        return MediaState::inactive();
+    }
+
+    let artist = metadata_str_list(&metadata, "xesam:artist").join(", ");
+    let album = metadata_str(&metadata, "xesam:album").unwrap_or_default();
+    let duration_ms = metadata_u64(&metadata, "mpris:length").unwrap_or(0) / 1_000;
+    let position_ms = player
+        .position()
+        .await
+        .map(|us| us.max(0) as u64 / 1_000)
+        .unwrap_or(0);
+    let position_updated_at = unix_ms();
+
+    let art_url = metadata_str(&metadata, "mpris:artUrl");
+    if art_url != cache.url {
+        tracing::debug!(target: TARGET, title = %title, artist = %artist, album = %album, art_url = ?art_url, "artwork changed");
+        cache.b64 = None;
+        cache.art_due.clone_from(&art_url);
+        cache.url = art_url;
+    }
+
+    let controls = read_controls(conn, &name).await;
+    let source_app = match cache.sources.get(&name) {
+        Some(source) => source.clone(),
+        None => {
+            let source = source_app(conn, &name).await;
+            tracing::debug!(target: TARGET, player = %name, app = %source.id, client = ?source.client, "identified player");
+            cache.sources.insert(name.clone(), source.clone());
+            source
+        }
+    };
+    MediaState {
+        active: true,
+        playing,
+        title,
+        artist,
+        album,
+        album_art_b64: cache.b64.clone(),
+        palette: None,
+        position_ms,
+        position_updated_at,
+        playback_rate: controls.rate,
+        duration_ms,
+        url: metadata_str(&metadata, "xesam:url"),
+        volume: controls.volume,
+        shuffle: controls.shuffle,
+        repeat_mode: controls.repeat_mode,
+        capabilities: controls.capabilities,
+        source_app: Some(source_app),
+    }
+}
+
+/// The app behind bus name `name`, by its `DesktopEntry` and `Identity`. Players that leave those
+/// out are known by their bus name, minus the prefix and any `.instance…` suffix.
+async fn source_app(conn: &zbus::Connection, name: &str) -> SourceApp {
+    let root = async {
+        MediaPlayer2Proxy::builder(conn)
+            .destination(name.to_string())?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+    };
+    let (entry, identity) = match root.await {
+        Ok(root) => (root.desktop_entry().await.ok(), root.identity().await.ok()),
+        Err(_) => (None, None),
+    };
+    let id = entry.filter(|e| !e.is_empty()).unwrap_or_else(|| {
+        let suffix = name.strip_prefix(MPRIS_PREFIX).unwrap_or(name);
+        match suffix.split_once(".instance") {
+            Some((app, _)) => app.to_string(),
+            None => suffix.to_string(),
+        }
+    });
+    SourceApp::new(id, identity)
+}
+
+struct Controls {
+    rate: f32,
+    volume: Option<f32>,
+    shuffle: Option<bool>,
+    repeat_mode: Option<RepeatMode>,
+    capabilities: MediaCapabilities,
+}
+
+/// Rate, volume, shuffle, repeat and the `Can*` flags, in one `GetAll` rather than a round trip each.
+/// `Shuffle` and `LoopStatus` are optional in MPRIS, so a player without them simply leaves them
+/// out.
+async fn read_controls(conn: &zbus::Connection, name: &str) -> Controls {
+    let props = async {
+        zbus::fdo::PropertiesProxy::builder(conn)
+            .destination(name.to_string())?
+            .path(MPRIS_PATH)?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await?
+            .get_all(zbus::names::InterfaceName::from_static_str_unchecked(
+                MPRIS_PLAYER,
+            ))
+            .await
+            .map_err(zbus::Error::from)
+    }
+    .await
+    .map_err(|err| tracing::warn!(target: TARGET, player = %name, error = ?err, "GetAll failed"))
+    .unwrap_or_default();
+
+    let rate = match props.get("Rate").map(|v| unbox(v)) {
+        Some(Value::F64(r)) if *r > 0.0 => *r as f32,
+        _ => 1.0,
+    };
+    let volume = match props.get("Volume").map(|v| unbox(v)) {
+        Some(Value::F64(v)) => Some(v.clamp(0.0, 1.0) as f32),
+        _ => None,
+    };
+    let shuffle = match props.get("Shuffle").map(|v| unbox(v)) {
+        Some(Value::Bool(b)) => Some(*b),
+        _ => None,
+    };
+    let repeat_mode = match props.get("LoopStatus").map(|v| unbox(v)) {
+        Some(Value::Str(s)) => match s.as_str() {
+            "None" => Some(RepeatMode::Off),
+            "Track" => Some(RepeatMode::Track),
+            "Playlist" => Some(RepeatMode::Playlist),
+            _ => None,
+        },
+        _ => None,
+    };
+    let flag = |key: &str| matches!(props.get(key).map(|v| unbox(v)), Some(Value::Bool(true)));
+    // A player with `CanControl` false takes no commands at all, whatever its other flags say.
+    let control = flag("CanControl");
+    Controls {
+        rate,
+        volume,
+        shuffle,
+        repeat_mode,
+        capabilities: MediaCapabilities {
+            can_play: control && flag("CanPlay"),
+            can_pause: control && flag("CanPause"),
+            can_go_next: control && flag("CanGoNext"),
+            can_go_previous: control && flag("CanGoPrevious"),
+            can_seek: control && flag("CanSeek"),
+            can_set_volume: control && volume.is_some(),
+            can_shuffle: control && shuffle.is_some(),
+            can_repeat: control && repeat_mode.is_some(),
+        },
+    }
+}
+
+/* Metadata helpers  */
+
+/// Unwraps the variant boxing some players add around metadata values.
+fn unbox<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(inner) => unbox(inner),
+        v => v,
+    }
+}
+
+fn metadata_str(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    match unbox(metadata.get(key)?) {
+        Value::Str(s) => Some(s.as_str().to_string()),
+        Value::ObjectPath(p) => Some(p.as_str().to_string()),
+        _ => None,
+    }
+}
+
+/// `xesam:artist` is specified as a string list, but some players send a bare string.
+fn metadata_str_list(metadata: &HashMap<String, OwnedValue>, key: &str) -> Vec<String> {
+    match metadata.get(key).map(|v| unbox(v)) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| match unbox(v) {
+                Value::Str(s) => Some(s.as_str().to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(Value::Str(s)) => vec![s.as_str().to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// `mpris:length` should be an `x` (i64), but `t` and `i` are both seen in the wild.
+fn metadata_u64(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<u64> {
+    match unbox(metadata.get(key)?) {
+        Value::I64(v) => Some((*v).max(0) as u64),
+        Value::U64(v) => Some(*v),
+        Value::I32(v) => Some((*v).max(0) as u64),
+        Value::U32(v) => Some(*v as u64),
+        _ => None,
+    }
+}
+
+/// Shared by every artwork fetch, so they reuse connections and all get `ART_TIMEOUT`.
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(ART_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Reads `mpris:artUrl` into base64. Local players use `file://` URLs (often into a cache dir
+/// they own); browsers and streaming clients hand out `https://` thumbnails. Either is refused
+/// past `ART_MAX_BYTES`.
+async fn read_art(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(
+            |err| tracing::warn!(target: TARGET, url, error = %err, "read_art: invalid artUrl"),
+        )
+        .ok()?;
+    let too_large = |len: u64| {
+        let over = len > ART_MAX_BYTES;
+        if over {
+            tracing::warn!(target: TARGET, url, bytes = len, "read_art: artwork too large");
+        }
+        over
+    };
+    let bytes = match parsed.scheme() {
+        "file" => {
+            let path = parsed.to_file_path().ok()?;
+            let read = async {
+                if too_large(tokio::fs::metadata(&path).await?.len()) {
+                    return Ok(None);
+                }
+                tokio::fs::read(&path).await.map(Some)
+            };
+            read.await
+                .map_err(|err| tracing::warn!(target: TARGET, path = %path.display(), error = %err, "read_art: read failed"))
+                .ok()??
+        }
+        "http" | "https" => {
+            let fetch = async {
+                let mut response = http_client().get(parsed).send().await?.error_for_status()?;
+                if response.content_length().is_some_and(too_large) {
+                    return Ok(None);
+                }
+                // The header is optional (and can lie), so the cap holds while reading too.
+                let mut bytes = Vec::new();
+                while let Some(chunk) = response.chunk().await? {
+                    if too_large((bytes.len() + chunk.len()) as u64) {
+                        return Ok(None);
+                    }
+                    bytes.extend_from_slice(&chunk);
+                }
+                Ok::<_, reqwest::Error>(Some(bytes))
+            };
+            fetch
+                .await
+                .map_err(
+                    |err| tracing::warn!(target: TARGET, url, error = %err, "read_art: fetch failed"),
+                )
+                .ok()??
+        }
+        scheme => {
+            tracing::warn!(target: TARGET, scheme, "read_art: unsupported artUrl scheme");
+            return None;
+        }
+    };
+    if bytes.is_empty() {
+        tracing::warn!(target: TARGET, url, "read_art: empty artwork");
+        return None;
+    }
+    tracing::trace!(target: TARGET, bytes = bytes.len(), "read_art: encoded artwork");
+    Some(base64::engine::general_purpose::STANDARD_NO_PAD.encode(&bytes))
+}
+
+/* Playback controls  */
+
+pub async fn pause_media() -> Result<(), String> {
+    current_player().await?.pause().await.map_err(e)
+}
+
+pub async fn play_media() -> Result<(), String> {
+    current_player().await?.play().await.map_err(e)
+}
+
+pub async fn toggle_playback() -> Result<(), String> {
+    current_player().await?.play_pause().await.map_err(e)
+}
+
+pub async fn next_track() -> Result<(), String> {
+    current_player().await?.next().await.map_err(e)
+}
+
+pub async fn prev_track() -> Result<(), String> {
+    current_player().await?.previous().await.map_err(e)
+}
+
+pub async fn seek_media(position_ms: u64) -> Result<(), String> {
+    let player = current_player().await?;
+    let position = position_ms.saturating_mul(1_000).min(i64::MAX as u64) as i64;
+    let metadata = player.metadata().await.map_err(e)?;
+    match metadata_str(&metadata, "mpris:trackid") {
+        // Tied to the track id, so a seek that races a track change doesn't land in the next one.
+        Some(track_id) => {
+            let track_id = ObjectPath::try_from(track_id).map_err(e)?;
+            player.set_position(&track_id, position).await.map_err(e)
+        }
+        // Without one only the relative `Seek` is left.
+        None => {
+            let current = player.position().await.map_err(e)?;
+            player.seek(position - current).await.map_err(e)
+        }
+    }
+}
+
+pub async fn set_volume(volume: f32) -> Result<(), String> {
+    if !volume.is_finite() {
+        return Err(format!("invalid volume {volume}"));
+    }
+    current_player()
+        .await?
+        .set_volume(volume.clamp(0.0, 1.0) as f64)
+        .await
+        .map_err(e)
+}
+
+pub async fn set_shuffle(shuffle: bool) -> Result<(), String> {
+    current_player()
+        .await?
+        .set_shuffle(shuffle)
+        .await
+        .map_err(e)
+}
+
+pub async fn set_repeat(mode: RepeatMode) -> Result<(), String> {
+    let status = match mode {
+        RepeatMode::Off => "None",
+        RepeatMode::Track => "Track",
+        RepeatMode::Playlist => "Playlist",
+    };
+    current_player()
+        .await?
+        .set_loop_status(status)
+        .await
+        .map_err(e)
+}
+
+async fn current_player() -> Result<PlayerProxy<'static>, String> {
+    let conn = session_bus().await?;
+    let name = match active_player() {
+        Some(name) => name,
+        None => {
+            let players = list_players(&conn).await.map_err(e)?;
+            pick_active_player(&conn, players, None, super::pinned_session().as_deref())
+                .await
+                .ok_or("No active media player")?
+        }
+    };
+    player_proxy(&conn, name).await.map_err(e)
+}
+
+/* Sessions  */
+
+pub async fn list_media_sessions() -> Result<Vec<MediaSession>, String> {
+    let conn = session_bus().await?;
+    let followed = active_player();
+    let pinned = super::pinned_session();
+    let mut sessions = Vec::new();
+    for name in list_players(&conn).await.map_err(e)? {
+        let Ok(player) = player_proxy(&conn, name.clone()).await else {
+            continue;
+        };
+        let state = match player.playback_status().await.as_deref() {
+            Ok("Playing") => PlaybackState::Playing,
+            Ok("Paused") => PlaybackState::Paused,
+            _ => PlaybackState::Stopped,
+        };
+        let title = player
+            .metadata()
+            .await
+            .ok()
+            .and_then(|m| metadata_str(&m, "xesam:title"))
+            .unwrap_or_default();
+        sessions.push(MediaSession {
+            source_app: source_app(&conn, &name).await,
+            state,
+            title,
+            followed: followed.as_deref() == Some(name.as_str()),
+            pinned: pinned.as_deref() == Some(name.as_str()),
+            id: name,
+        });
+    }
+    Ok(sessions)
+}
+
+pub async fn select_media_session(id: Option<String>) -> Result<(), String> {
+    if let Some(id) = &id {
+        let conn = session_bus().await?;
+        if !list_players(&conn).await.map_err(e)?.contains(id) {
+            return Err(format!("no media session `{id}`"));
+        }
+        // So commands sent before the loop has re-selected already go to it.
+        set_active_player(Some(id.clone()));
+    }
+    super::pin_session(id);
+    Ok(())
+}
+
+/* Visualizer capture  */
+
+const VIS_TARGET: &str = "media::visualizer";
+
+/// Capture format requested from PulseAudio; the server resamples/remixes the sink's monitor
+/// into it, so the FFT always sees the same rate regardless of the hardware.
+const CAPTURE_RATE: u32 = 48_000;
+const CAPTURE_CHANNELS: u8 = 2;
+/// Frames per `pa_simple_read` — ~21 ms at 48 kHz, comfortably under one 33 ms visualizer frame.
+const CAPTURE_CHUNK_FRAMES: usize = 1024;
+
+/// How often the visualizer thread asks the server for its default sink. Unlike cpal's
+/// `default_output_device()` on Windows this is a round trip to the sound server, so it is not
+/// done every frame.
+const SINK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A long-lived PulseAudio context used only to ask the server for its default sink. libpulse
+/// mainloops are not `Send`, so this lives on (and never leaves) the visualizer thread. Talks to
+/// PipeWire through `pipewire-pulse` exactly the same way.
+struct PulseServer {
+    mainloop: libpulse_binding::mainloop::standard::Mainloop,
+    context: libpulse_binding::context::Context,
+}
+
+impl PulseServer {
+    fn connect() -> Result<Self, String> {
+        use libpulse_binding::context::{Context, FlagSet, State};
+        use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
+
+        let mut mainloop = Mainloop::new().ok_or("failed to create PulseAudio mainloop")?;
+        let mut context =
+            Context::new(&mainloop, "desk-disp").ok_or("failed to create PulseAudio context")?;
+        context
+            .connect(None, FlagSet::NOAUTOSPAWN, None)
+            .map_err(|err| format!("PulseAudio connect failed: {err}"))?;
+        loop {
+            if let IterateResult::Quit(_) | IterateResult::Err(_) = mainloop.iterate(true) {
+                return Err("PulseAudio mainloop failed while connecting".into());
+            }
+            match context.get_state() {
+                State::Ready => break,
+                State::Failed | State::Terminated => {
+                    return Err("PulseAudio context failed to connect".into())
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { mainloop, context })
+    }
+
+    fn default_sink(&mut self) -> Option<String> {
+        use libpulse_binding::mainloop::standard::IterateResult;
+        use libpulse_binding::operation::State;
+        use std::{cell::RefCell, rc::Rc};
+
+        let result: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let out = Rc::clone(&result);
+        let op = self.context.introspect().get_server_info(move |info| {
+            *out.borrow_mut() = info.default_sink_name.as_ref().map(|s| s.to_string());
+        });
+        while op.get_state() == State::Running {
+            if let IterateResult::Quit(_) | IterateResult::Err(_) = self.mainloop.iterate(true) {
+                return None;
+            }
+        }
+        let sink = result.borrow_mut().take();
+        sink
+    }
+}
+
+impl Drop for PulseServer {
+    fn drop(&mut self) {
+        self.context.disconnect();
+    }
+}
+
+/// Records the default sink's monitor source on a dedicated reader thread. `pa_simple_read`
+/// blocks, so shutdown is a flag the reader checks between chunks; dropping joins the thread,
+/// which takes at most one chunk.
+struct MonitorCapture {
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    reader: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Drop for MonitorCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Opens a record stream on `<default sink>.monitor` — the Linux half of `LoopbackSource`.
+pub fn open_monitor_capture(
+    sender: std::sync::mpsc::Sender<Vec<f32>>,
+) -> Result<super::LoopbackCapture, Box<dyn std::error::Error>> {
+    let sink = PulseServer::connect()?
+        .default_sink()
+        .ok_or("PulseAudio server has no default sink")?;
+    let source = format!("{sink}.monitor");
+    tracing::debug!(target: VIS_TARGET, source = %source, "opening monitor source");
+
+    let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let (ready_tx, ready_rx) = std::sync::mpsc::sync_channel::<Result<(), String>>(1);
+    let reader = {
+        let stop = Arc::clone(&stop);
+        let source = source.clone();
+        std::thread::Builder::new()
+            .name("visualizer-capture".into())
+            .spawn(move || read_monitor(&source, sender, stop, ready_tx))?
+    };
+    // Keep the guard alive from here on so an init failure still joins the reader.
+    let capture = MonitorCapture {
+        stop,
+        reader: Some(reader),
+    };
+    ready_rx
+        .recv()
+        .map_err(|_| "monitor capture thread exited during init")??;
+    tracing::debug!(target: VIS_TARGET, source = %source, "monitor capture running");
+
+    Ok(super::LoopbackCapture {
+        device_name: sink,
+        sample_rate: CAPTURE_RATE as f32,
+        channels: CAPTURE_CHANNELS as usize,
+        handle: Box::new(capture),
+    })
+}
+
+fn read_monitor(
+    source: &str,
+    sender: std::sync::mpsc::Sender<Vec<f32>>,
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    ready: std::sync::mpsc::SyncSender<Result<(), String>>,
+) {
+    use libpulse_binding::{def::BufferAttr, sample, stream::Direction};
+    use libpulse_simple_binding::Simple;
+
+    let spec = sample::Spec {
+        format: sample::Format::FLOAT32NE,
+        channels: CAPTURE_CHANNELS,
+        rate: CAPTURE_RATE,
+    };
+    let chunk_bytes = CAPTURE_CHUNK_FRAMES * CAPTURE_CHANNELS as usize * 4;
+    // Without an explicit fragsize the server picks one (up to ~2 s), and reads arrive in bursts.
+    let attr = BufferAttr {
+        maxlength: u32::MAX,
+        tlength: u32::MAX,
+        prebuf: u32::MAX,
+        minreq: u32::MAX,
+        fragsize: chunk_bytes as u32,
+    };
+    let simple = match Simple::new(
+        None,
+        "desk-disp",
+        Direction::Record,
+        Some(source),
+        "visualizer",
+        &spec,
+        None,
+        Some(&attr),
+    ) {
+        Ok(s) => s,
+        Err(err) => {
+            let _ = ready.send(Err(format!(
+                "failed to open monitor source {source}: {err}"
+            )));
+            return;
+        }
+    };
+    let _ = ready.send(Ok(()));
+
+    let mut bytes = vec![0u8; chunk_bytes];
+    while !stop.load(Ordering::Relaxed) {
+        if let Err(err) = simple.read(&mut bytes) {
+            tracing::error!(target: VIS_TARGET, error = %err, "monitor read failed — capture stopped");
+            return;
+        }
+        let samples: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        if sender.send(samples).is_err() {
+            // FFTStream dropped its receiver; the guard's join is imminent.
+            return;
+        }
+    }
+}
+
+/* Session lock  */
+
+/// Whether logind reports the session this process runs in as locked (`LockedHint`, which GNOME,
+/// KDE and most lockers set). Anything that keeps us from asking — no system bus, not running
+/// inside a logind session — counts as unlocked, so the visualizer never stalls on a guess.
+pub fn session_locked() -> bool {
+    static SYSTEM_BUS: std::sync::OnceLock<Option<zbus::blocking::Connection>> =
+        std::sync::OnceLock::new();
+    let Some(conn) = SYSTEM_BUS.get_or_init(|| {
+        zbus::blocking::Connection::system()
+            .map_err(|err| tracing::debug!(target: VIS_TARGET, error = %err, "no system bus — session lock detection disabled"))
+            .ok()
+    }) else {
+        return false;
+    };
+    zbus::blocking::Proxy::new(
+        conn,
+        "org.freedesktop.login1",
+        // logind resolves `auto` to the caller's own session.
+        "/org/freedesktop/login1/session/auto",
+        "org.freedesktop.login1.Session",
+    )
+    .and_then(|proxy| proxy.get_property::<bool>("LockedHint"))
+    .unwrap_or(false)
 }
 
-pub fn spawn_visualizer_loop(_app: tauri::AppHandle, _subscribers: Arc<AtomicUsize>, _frame_interval: std::time::Duration) {
-    tracing::warn!(target: TARGET, "visualizer loop not implemented on Linux");
+/* Visualizer loop  */
+
+/// Spawns a dedicated thread that owns the FFT stream. Mirrors the Windows loop: creates the
+/// FFTStream on first subscriber, drops it when empty, and re-creates it when the default sink
+/// changes (so the visualizer follows e.g. switching from speakers to a Bluetooth headset).
+/// Only loopback sources watch the sink; file and synthetic sources are built once. The thread
+/// returns once `stop` is set.
+pub fn spawn_visualizer_loop(
+    app: tauri::AppHandle,
+    mut gate: SubscriberGate,
+    frame_interval: Duration,
+    source: super::SourceSpec,
+    stop: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    use tauri::Manager;
+
+    std::thread::Builder::new()
+        .name("visualizer".into())
+        .spawn(move || {
+            tracing::info!(target: VIS_TARGET, "visualizer thread started");
+            let frame = frame_interval;
+            let mut stream: Option<super::FFTStream> = None;
+            let mut server: Option<PulseServer> = None;
+            let mut last_sink_check = std::time::Instant::now();
+            let mut outputs = super::VisualizerOutputs::new(frame);
+            let mut visibility = super::VisibilityWatch::default();
+
+            while !stop.load(Ordering::Relaxed) {
+                let sub_count = gate.subscribers();
+                let paused = if sub_count == 0 { None } else { visibility.check(&app) };
+
+                if !gate.should_run_if(paused.is_none()) {
+                    if stream.is_some() {
+                        match paused {
+                            Some(reason) => tracing::info!(target: VIS_TARGET, ?reason, "output not visible — dropping FFTStream"),
+                            None => tracing::info!(target: VIS_TARGET, "no subscribers — dropping FFTStream"),
+                        }
+                        stream = None;
+                        server = None;
+                        // Frames parked for a consumer that has gone away would be stale on resume.
+                        app.state::<crate::events::FrameGate>().clear();
+                    }
+                    std::thread::sleep(frame);
+                    continue;
+                }
+
+                let mut needs_reinit = stream.is_none();
+                if let Some(ref s) = stream {
+                    if source.is_loopback() && last_sink_check.elapsed() >= SINK_CHECK_INTERVAL {
+                        last_sink_check = std::time::Instant::now();
+                        if server.is_none() {
+                            server = PulseServer::connect()
+                                .map_err(|err| tracing::warn!(target: VIS_TARGET, error = %err, "sink watcher unavailable"))
+                                .ok();
+                        }
+                        let current_sink = server.as_mut().and_then(|srv| srv.default_sink());
+                        if current_sink.is_none() {
+                            // Server went away (e.g. pipewire restarted) — reconnect next check.
+                            server = None;
+                        }
+                        if current_sink.as_deref() != Some(s.device_name.as_str()) {
+                            tracing::info!(target: VIS_TARGET, old_sink = %s.device_name, new_sink = ?current_sink, "default sink changed — reinitialising FFTStream");
+                            needs_reinit = true;
+                        }
+                    }
+                }
+
+                if needs_reinit {
+                    stream = None;
+                    match source.open().map(|src| super::FFTStream::with_source(src, 4096)) {
+                        Ok(s) => {
+                            tracing::info!(target: VIS_TARGET, device = %s.device_name, sample_rate = s.sample_rate, channels = s.channels, fft_size = s.fft_size, "FFTStream created");
+                            stream = Some(s);
+                            last_sink_check = std::time::Instant::now();
+                        }
+                        Err(err) => {
+                            tracing::error!(target: VIS_TARGET, error = %err, "FFTStream init failed; will retry next frame");
+                            std::thread::sleep(frame);
+                            continue;
+                        }
+                    }
+                }
+
+                // Emit whichever analyzers are due, then sleep until the next one is. While
+                // lingering there is nobody to emit to; just keep the capture drained so a
+                // returning subscriber gets current audio rather than a stale backlog.
+                let wait = match stream {
+                    Some(ref mut s) if sub_count > 0 => outputs.tick(&app, s),
+                    Some(ref mut s) => {
+                        s.update();
+                        frame
+                    }
+                    None => frame,
+                };
+                std::thread::sleep(wait);
+            }
+            tracing::info!(target: VIS_TARGET, "visualizer thread stopped");
+        })
+        .expect("failed to spawn visualizer thread")
 }
 
-pub async fn pause_media() -> Result<(), String> { Ok(()) }
-pub async fn play_media() -> Result<(), String> { Ok(()) }
-pub async fn toggle_playback() -> Result<(), String> { Ok(()) }
-pub async fn next_track() -> Result<(), String> { Ok(()) }
-pub async fn prev_track() -> Result<(), String> { Ok(()) }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(entries: Vec<(&str, Value<'static>)>) -> HashMap<String, OwnedValue> {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), OwnedValue::try_from(value).unwrap()))
+            .collect()
+    }
+
+    fn statuses(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(name, status)| (format!("{MPRIS_PREFIX}{name}"), status.to_string()))
+            .collect()
+    }
+
+    fn player(name: &str) -> String {
+        format!("{MPRIS_PREFIX}{name}")
+    }
+
+    #[test]
+    fn metadata_str_reads_strings_and_object_paths() {
+        let metadata = metadata(vec![
+            ("xesam:title", Value::from("Get Lucky")),
+            (
+                "mpris:trackid",
+                Value::from(ObjectPath::try_from("/org/mpris/track/1").unwrap()),
+            ),
+            (
+                "xesam:url",
+                Value::Value(Box::new(Value::from("https://example.com"))),
+            ),
+            ("mpris:length", Value::from(1_i64)),
+        ]);
+        assert_eq!(
+            metadata_str(&metadata, "xesam:title").as_deref(),
+            Some("Get Lucky")
+        );
+        assert_eq!(
+            metadata_str(&metadata, "mpris:trackid").as_deref(),
+            Some("/org/mpris/track/1")
+        );
+        assert_eq!(
+            metadata_str(&metadata, "xesam:url").as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(metadata_str(&metadata, "mpris:length"), None);
+        assert_eq!(metadata_str(&metadata, "xesam:album"), None);
+    }
+
+    #[test]
+    fn metadata_str_list_accepts_lists_and_bare_strings() {
+        let metadata = metadata(vec![
+            (
+                "xesam:artist",
+                Value::from(vec!["Daft Punk", "Pharrell Williams"]),
+            ),
+            ("xesam:albumArtist", Value::from("Daft Punk")),
+            ("xesam:genre", Value::from(7_i32)),
+        ]);
+        assert_eq!(
+            metadata_str_list(&metadata, "xesam:artist"),
+            ["Daft Punk", "Pharrell Williams"]
+        );
+        assert_eq!(
+            metadata_str_list(&metadata, "xesam:albumArtist"),
+            ["Daft Punk"]
+        );
+        assert!(metadata_str_list(&metadata, "xesam:genre").is_empty());
+        assert!(metadata_str_list(&metadata, "xesam:composer").is_empty());
+    }
+
+    #[test]
+    fn metadata_u64_accepts_every_integer_width() {
+        let metadata = metadata(vec![
+            ("x", Value::from(248_000_000_i64)),
+            ("t", Value::from(248_000_000_u64)),
+            ("i", Value::from(248_000_i32)),
+            ("u", Value::from(248_000_u32)),
+            ("negative", Value::from(-5_i64)),
+            ("boxed", Value::Value(Box::new(Value::from(42_i64)))),
+            ("text", Value::from("248000000")),
+        ]);
+        assert_eq!(metadata_u64(&metadata, "x"), Some(248_000_000));
+        assert_eq!(metadata_u64(&metadata, "t"), Some(248_000_000));
+        assert_eq!(metadata_u64(&metadata, "i"), Some(248_000));
+        assert_eq!(metadata_u64(&metadata, "u"), Some(248_000));
+        assert_eq!(metadata_u64(&metadata, "negative"), Some(0));
+        assert_eq!(metadata_u64(&metadata, "boxed"), Some(42));
+        assert_eq!(metadata_u64(&metadata, "text"), None);
+        assert_eq!(metadata_u64(&metadata, "missing"), None);
+    }
+
+    #[test]
+    fn pinned_player_wins_while_on_the_bus() {
+        let players = statuses(&[("spotify", "Playing"), ("vlc", "Paused")]);
+        let vlc = player("vlc");
+        let gone = player("firefox.instance42");
+        assert_eq!(choose_player(&players, None, Some(&vlc)), Some(vlc.clone()));
+        assert_eq!(
+            choose_player(&players, None, Some(&gone)),
+            Some(player("spotify"))
+        );
+    }
+
+    #[test]
+    fn playing_player_wins_preferring_the_current_one() {
+        let players = statuses(&[
+            ("spotify", "Playing"),
+            ("vlc", "Playing"),
+            ("mpv", "Paused"),
+        ]);
+        let vlc = player("vlc");
+        let mpv = player("mpv");
+        assert_eq!(choose_player(&players, Some(&vlc), None), Some(vlc));
+        assert_eq!(
+            choose_player(&players, Some(&mpv), None),
+            Some(player("spotify"))
+        );
+    }
+
+    #[test]
+    fn without_playback_the_current_then_a_paused_player_is_kept() {
+        let players = statuses(&[
+            ("mpv", "Stopped"),
+            ("spotify", "Stopped"),
+            ("vlc", "Paused"),
+        ]);
+        let spotify = player("spotify");
+        assert_eq!(choose_player(&players, Some(&spotify), None), Some(spotify));
+        assert_eq!(choose_player(&players, None, None), Some(player("vlc")));
+
+        let stopped = statuses(&[("mpv", "Stopped"), ("spotify", "")]);
+        assert_eq!(choose_player(&stopped, None, None), Some(player("mpv")));
+        assert_eq!(choose_player(&[], None, None), None);
+    }
+
+    /// An MPRIS player that records the commands it gets, for the backend to follow over a
+    /// private bus.
+    struct FakePlayer {
+        status: &'static str,
+        commands: Arc<std::sync::Mutex<Vec<&'static str>>>,
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        fn play(&self) {
+            self.commands.lock().unwrap().push("Play");
+        }
+
+        fn pause(&self) {
+            self.commands.lock().unwrap().push("Pause");
+        }
+
+        fn next(&self) {
+            self.commands.lock().unwrap().push("Next");
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> &str {
+            self.status
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            metadata(vec![
+                ("xesam:title", Value::from("Get Lucky")),
+                ("xesam:artist", Value::from(vec!["Daft Punk"])),
+                ("xesam:album", Value::from("Random Access Memories")),
+                ("mpris:length", Value::I64(248_000_000)),
+            ])
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            61_000_000
+        }
+
+        #[zbus(property)]
+        fn volume(&self) -> f64 {
+            0.5
+        }
+
+        #[zbus(property)]
+        fn can_control(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_play(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_go_next(&self) -> bool {
+            true
+        }
+    }
+
+    /// Serves a `FakePlayer` as `org.mpris.MediaPlayer2.{name}` on the bus at `address`.
+    async fn serve(
+        address: &str,
+        name: &str,
+        status: &'static str,
+    ) -> (zbus::Connection, Arc<std::sync::Mutex<Vec<&'static str>>>) {
+        let commands = Arc::default();
+        let fake = FakePlayer {
+            status,
+            commands: Arc::clone(&commands),
+        };
+        let conn = zbus::connection::Builder::address(address)
+            .unwrap()
+            .name(player(name))
+            .unwrap()
+            .serve_at(MPRIS_PATH, fake)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        (conn, commands)
+    }
+
+    /// Changes the player's status and sends the `PropertiesChanged` a real player would.
+    async fn set_status(conn: &zbus::Connection, status: &'static str) {
+        let iface = conn
+            .object_server()
+            .interface::<_, FakePlayer>(MPRIS_PATH)
+            .await
+            .unwrap();
+        iface.get_mut().await.status = status;
+        iface
+            .get()
+            .await
+            .playback_status_changed(iface.signal_emitter())
+            .await
+            .unwrap();
+    }
+
+    /// The next signal from a player, handed to `follower` as the media loop would.
+    async fn next_signal(follower: &mut Follower, signals: &mut zbus::MessageStream) -> bool {
+        let msg = tokio::time::timeout(Duration::from_secs(5), signals.next())
+            .await
+            .expect("no signal within 5s")
+            .unwrap()
+            .unwrap();
+        follower.on_signal(&msg).await
+    }
+
+    #[tokio::test]
+    async fn follows_a_fake_player_on_a_private_bus() {
+        // The only test here that touches the session bus, which it points at its own daemon.
+        let daemon = std::process::Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(std::process::Stdio::piped())
+            .spawn();
+        let Ok(mut daemon) = daemon else {
+            eprintln!("dbus-daemon not found; skipping");
+            return;
+        };
+        let mut address = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(daemon.stdout.take().unwrap()),
+            &mut address,
+        )
+        .unwrap();
+        let address = address.trim().to_string();
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+        let (spotify, commands) = serve(&address, "spotify", "Playing").await;
+        let (vlc, _) = serve(&address, "vlc", "Paused").await;
+        let conn = session_bus().await.unwrap();
+        let (mut signals, _) = mpris_signal_streams(&conn).await.unwrap();
+        let mut follower = Follower {
+            conn,
+            cache: PlayerCache::default(),
+            playing: false,
+        };
+
+        follower.rescan().await;
+        assert_eq!(active_player(), Some(player("spotify")));
+        let state = follower.read_active().await;
+        assert!(state.active && state.playing);
+        assert_eq!(state.title, "Get Lucky");
+        assert_eq!(state.artist, "Daft Punk");
+        assert_eq!(state.album, "Random Access Memories");
+        assert_eq!(state.duration_ms, 248_000);
+        assert_eq!(state.position_ms, 61_000);
+        assert_eq!(state.volume, Some(0.5));
+        assert!(state.capabilities.can_play && state.capabilities.can_go_next);
+        assert!(!state.capabilities.can_pause && !state.capabilities.can_seek);
+        assert_eq!(state.source_app.unwrap().id, "spotify");
+
+        play_media().await.unwrap();
+        pause_media().await.unwrap();
+        next_track().await.unwrap();
+        assert_eq!(*commands.lock().unwrap(), ["Play", "Pause", "Next"]);
+
+        // A signal from the followed player re-reads it.
+        set_status(&spotify, "Paused").await;
+        assert!(next_signal(&mut follower, &mut signals).await);
+        assert!(!follower.read_active().await.playing);
+
+        // Another player starting playback takes over from the paused one.
+        set_status(&vlc, "Playing").await;
+        assert!(next_signal(&mut follower, &mut signals).await);
+        assert_eq!(active_player(), Some(player("vlc")));
+        assert!(follower.read_active().await.playing);
+
+        // While it plays, the other starting too doesn't make the widget flip.
+        set_status(&spotify, "Playing").await;
+        assert!(!next_signal(&mut follower, &mut signals).await);
+        assert_eq!(active_player(), Some(player("vlc")));
+
+        daemon.kill().ok();
+    }
+}
@@ -1,10 +1,23 @@
 #![allow(dead_code)]
 
-use std::sync::{atomic::AtomicUsize, Arc};
+use std::sync::{atomic::AtomicBool, Arc};
+use std::time::Duration;
+use tauri::Manager;
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::channel::{run_polled_on_change, unix_ms, Channel, ChannelCore, Producer};
+use crate::events::StreamName;
 
+use cpal::traits::{DeviceTrait, HostTrait};
+
+mod analysis;
+mod dynamics;
+#[cfg(target_os = "linux")]
 mod linux_media;
+mod normalize;
+mod palette;
+mod source;
+mod visibility;
+#[cfg(target_os = "windows")]
 mod windows_media;
 #[cfg(target_os = "windows")]
 mod imp {
@@ -15,41 +28,236 @@ mod imp {
     pub use super::linux_media::*;
 }
 
+pub use analysis::*;
+pub use normalize::ensure_default_rules;
+pub use palette::AlbumPalette;
+pub use source::{AudioSource, LoopbackCapture, LoopbackSource, SourceSpec};
+pub use visibility::VisibilityWatch;
+
 /* Tauri command wrappers  */
 
 macro_rules! spawn_command {
-    (command: $name:ident -> $return_type:ty;) => {
+    (command: $name:ident($($arg:ident: $arg_type:ty),*) -> $return_type:ty;) => {
         #[tauri::command]
-        pub async fn $name() -> Result<$return_type, String> {
-            imp::$name().await
+        pub async fn $name($($arg: $arg_type),*) -> Result<$return_type, String> {
+            imp::$name($($arg),*).await
         }
     };
-    [$(command: $name:ident -> $return_type:ty;)+] => {
-        $(spawn_command!(command: $name -> $return_type;);)+
+    [$(command: $name:ident($($arg:ident: $arg_type:ty),*) -> $return_type:ty;)+] => {
+        $(spawn_command!(command: $name($($arg: $arg_type),*) -> $return_type;);)+
     };
 }
 
 spawn_command!(
-    command: pause_media -> ();
-    command: play_media -> ();
-    command: toggle_playback -> ();
-    command: next_track -> ();
-    command: prev_track -> ();
+    command: pause_media() -> ();
+    command: play_media() -> ();
+    command: toggle_playback() -> ();
+    command: next_track() -> ();
+    command: prev_track() -> ();
+    command: seek_media(position_ms: u64) -> ();
+    command: set_volume(volume: f32) -> ();
+    command: set_shuffle(shuffle: bool) -> ();
+    command: set_repeat(mode: RepeatMode) -> ();
+    command: list_media_sessions() -> Vec<MediaSession>;
+    command: select_media_session(id: Option<String>) -> ();
 );
 
-// Event-loop entry points (called from lib.rs setup)
+/* Session selection  */
+
+/// Session `select_media_session` pinned widgets to. While it exists the backends follow it
+/// instead of choosing a session themselves; while it doesn't, they choose as usual.
+static PINNED_SESSION: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+/// Wakes the media loop to re-select after the pin changes.
+static SESSION_PINNED: tokio::sync::Notify = tokio::sync::Notify::const_new();
+
+fn pinned_session() -> Option<String> {
+    PINNED_SESSION.lock().ok()?.clone()
+}
+
+fn pin_session(id: Option<String>) {
+    if let Ok(mut guard) = PINNED_SESSION.lock() {
+        tracing::info!(target: "media", session = ?id, "media session pinned");
+        *guard = id;
+    }
+    SESSION_PINNED.notify_one();
+}
+
+/* Channels  */
+
+/// How far the reported position may stray from the extrapolated one before it's worth a
+/// publish of its own.
+const POSITION_TOLERANCE_MS: u64 = 500;
+
+/// The last state published, for `emit_media` to compare against and `stream::media_tick` to
+/// extrapolate from.
+static LAST_MEDIA: std::sync::Mutex<Option<MediaState>> = std::sync::Mutex::new(None);
+
+/// Every backend publishes through here, so the media rules and the album-art palette apply
+/// whichever produced the state.
+///
+/// States that differ from the last one only by a position that extrapolation already predicts
+/// are dropped, so subscribers hear about real changes only.
+fn emit_media(app: &tauri::AppHandle, state: MediaState) {
+    let mut state = normalize::apply(state);
+    state.palette = palette::of(state.album_art_b64.as_deref());
+    if let Ok(mut last) = LAST_MEDIA.lock() {
+        if last.as_ref().is_some_and(|last| state.continues(last)) {
+            return;
+        }
+        *last = Some(state.clone());
+    }
+    MediaChannel::publish(app, state);
+}
+
+pub struct MediaChannel {
+    core: ChannelCore,
+    producer: Producer,
+    poll_interval: Duration,
+}
+
+impl MediaChannel {
+    pub fn new(poll_interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Media),
+            producer: Producer::default(),
+            poll_interval,
+        }
+    }
+}
+
+impl Channel for MediaChannel {
+    type Payload = MediaState;
+    const NAME: StreamName = StreamName::Media;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn start(&self, app: &tauri::AppHandle) {
+        // A reset empties the channel's cache, so the first state must be published again.
+        if let Ok(mut last) = LAST_MEDIA.lock() {
+            *last = None;
+        }
+        let gate = self.core.gate(app);
+        self.producer.spawn(imp::run_media_loop(app.clone(), gate, self.poll_interval));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+/// `stream::media_tick`: the position of the media session, extrapolated at whatever rate
+/// subscribers ask for, for widgets that can't interpolate `MediaState` themselves. Ticks while
+/// playing, and once more when playback stops or the track changes.
+pub struct MediaTickChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl MediaTickChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::MediaTick),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for MediaTickChannel {
+    type Payload = MediaTick;
+    const NAME: StreamName = StreamName::MediaTick;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn start(&self, app: &tauri::AppHandle) {
+        let mut previous: Option<MediaTick> = None;
+        self.producer.spawn(run_polled_on_change::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || {
+                let tick = LAST_MEDIA
+                    .lock()
+                    .ok()?
+                    .as_ref()
+                    .map(|state| MediaTick {
+                        position_ms: state.position_at(unix_ms()),
+                        duration_ms: state.duration_ms,
+                        playing: state.playing,
+                    })
+                    .unwrap_or_default();
+                if !tick.playing && previous.as_ref() == Some(&tick) {
+                    return None;
+                }
+                previous = Some(tick.clone());
+                Some(tick)
+            },
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
 
-pub async fn run_media_loop(app: tauri::AppHandle, subscribers: Arc<AtomicUsize>, poll_interval: std::time::Duration) {
-    imp::run_media_loop(app, subscribers, poll_interval).await
+/// `stream::visualizer` carries the default analyser's readings; per-widget outputs go out as
+/// `visualizer::*` events. Frames are delivered through the `FrameGate`, not `publish`, so the
+/// channel has no cached last value.
+pub struct VisualizerChannel {
+    core: ChannelCore,
+    producer: Producer,
+    frame_interval: Duration,
+    source: SourceSpec,
 }
 
-pub fn spawn_visualizer_loop(app: tauri::AppHandle, subscribers: Arc<AtomicUsize>, frame_interval: std::time::Duration) {
-    imp::spawn_visualizer_loop(app, subscribers, frame_interval)
+impl VisualizerChannel {
+    pub fn new(frame_interval: Duration, source: SourceSpec) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Visualizer),
+            producer: Producer::default(),
+            frame_interval,
+            source,
+        }
+    }
+}
+
+impl Channel for VisualizerChannel {
+    type Payload = Vec<FrequencyReading>;
+    const NAME: StreamName = StreamName::Visualizer;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn start(&self, app: &tauri::AppHandle) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = imp::spawn_visualizer_loop(
+            app.clone(),
+            self.core.gate(app),
+            self.frame_interval,
+            self.source.clone(),
+            Arc::clone(&stop),
+        );
+        self.producer.adopt_thread(stop, thread);
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+
+    fn release(&self, app: &tauri::AppHandle, id: &str) {
+        app.state::<VisualizerConfigs>().remove(id);
+    }
 }
 
 /* Shared payload types  */
 
-#[derive(serde::Serialize, Clone, ts_rs::TS)]
+#[derive(serde::Serialize, Clone, PartialEq, ts_rs::TS)]
 #[ts(export, export_to = "../../src/ffi_types.ts")]
 pub struct MediaState {
     /// False when no media session is active; all other fields are empty/zero.
@@ -60,8 +268,29 @@ pub struct MediaState {
     pub album: String,
     /// Base64-encoded thumbnail image (JPEG or PNG).
     pub album_art_b64: Option<String>,
+    /// Colours of the album art; `None` without art, or with art that won't decode.
+    pub palette: Option<AlbumPalette>,
+    /// As of `position_updated_at`. While playing, the position now is
+    /// `position_ms + (now - position_updated_at) * playback_rate`, up to `duration_ms`; states
+    /// aren't re-sent just because it moved on.
     pub position_ms: u64,
+    /// Unix ms.
+    pub position_updated_at: u64,
+    /// 1.0 at normal speed.
+    pub playback_rate: f32,
     pub duration_ms: u64,
+    /// What's playing, where the player says; browsers give the page.
+    pub url: Option<String>,
+    /// The player's own volume, `0.0..=1.0`. `None` where the platform doesn't expose it
+    /// (Windows).
+    pub volume: Option<f32>,
+    /// `None` when the player has no shuffle.
+    pub shuffle: Option<bool>,
+    /// `None` when the player has no repeat.
+    pub repeat_mode: Option<RepeatMode>,
+    pub capabilities: MediaCapabilities,
+    /// The app the session belongs to; `None` when inactive.
+    pub source_app: Option<SourceApp>,
 }
 
 impl MediaState {
@@ -73,10 +302,186 @@ impl MediaState {
             artist: String::new(),
             album: String::new(),
             album_art_b64: None,
+            palette: None,
             position_ms: 0,
+            position_updated_at: 0,
+            playback_rate: 1.0,
             duration_ms: 0,
+            url: None,
+            volume: None,
+            shuffle: None,
+            repeat_mode: None,
+            capabilities: MediaCapabilities::default(),
+            source_app: None,
         }
     }
+
+    /// The position at `at` (Unix ms), extrapolated from the last reading.
+    pub fn position_at(&self, at: u64) -> u64 {
+        if !self.playing {
+            return self.position_ms;
+        }
+        let elapsed = at.saturating_sub(self.position_updated_at) as f64;
+        let position = self
+            .position_ms
+            .saturating_add((elapsed * self.playback_rate as f64) as u64);
+        match self.duration_ms {
+            0 => position,
+            duration => position.min(duration),
+        }
+    }
+
+    /// Whether this is `last` played on: the same in everything but a position that
+    /// extrapolating `last` predicts.
+    fn continues(&self, last: &MediaState) -> bool {
+        let predicted = last.position_at(self.position_updated_at);
+        self.position_ms.abs_diff(predicted) < POSITION_TOLERANCE_MS
+            && *self
+                == MediaState {
+                    position_ms: self.position_ms,
+                    position_updated_at: self.position_updated_at,
+                    ..last.clone()
+                }
+    }
+}
+
+/// Payload of `stream::media_tick`.
+#[derive(serde::Serialize, Clone, Default, PartialEq, Debug, ts_rs::TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct MediaTick {
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub playing: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug, ts_rs::TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum RepeatMode {
+    Off,
+    Track,
+    Playlist,
+}
+
+/// Which controls the current player accepts, so widgets can grey out the others. Commands for
+/// a control the player lacks fail or are ignored, depending on the player.
+#[derive(serde::Serialize, Clone, Copy, Default, PartialEq, Debug, ts_rs::TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct MediaCapabilities {
+    pub can_play: bool,
+    pub can_pause: bool,
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub can_seek: bool,
+    pub can_set_volume: bool,
+    pub can_shuffle: bool,
+    pub can_repeat: bool,
+}
+
+/// Players with quirks worth handling, recognised from their app id. Everything else is
+/// `Other`.
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, ts_rs::TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum MediaClient {
+    Spotify,
+    AppleMusic,
+    Firefox,
+    Chrome,
+    Edge,
+    Vlc,
+    Other,
+}
+
+impl MediaClient {
+    /// From an MPRIS desktop entry or bus name suffix (`spotify`, `firefox`,
+    /// `org.videolan.vlc`), or a Windows app user model id (`Spotify.exe`,
+    /// `AppleInc.AppleMusicWin_nzyj5cx40ttqa!App`).
+    pub fn from_app_id(id: &str) -> Self {
+        let id = id.to_ascii_lowercase();
+        let has = |needle: &str| id.contains(needle);
+        if has("spotify") {
+            Self::Spotify
+        } else if has("applemusic") {
+            Self::AppleMusic
+        } else if has("firefox") {
+            Self::Firefox
+        } else if has("msedge") || has("microsoftedge") {
+            Self::Edge
+        } else if has("chrome") {
+            Self::Chrome
+        } else if has("vlc") {
+            Self::Vlc
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Display name for when the platform has none of its own.
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Spotify => Some("Spotify"),
+            Self::AppleMusic => Some("Apple Music"),
+            Self::Firefox => Some("Firefox"),
+            Self::Chrome => Some("Google Chrome"),
+            Self::Edge => Some("Microsoft Edge"),
+            Self::Vlc => Some("VLC"),
+            Self::Other => None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, PartialEq, Debug, ts_rs::TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct SourceApp {
+    /// The MPRIS desktop entry (`firefox`) on Linux, the app user model id on Windows.
+    pub id: String,
+    pub name: String,
+    pub client: MediaClient,
+    /// From the media rules: an icon name or image URL for widgets to show.
+    pub icon: Option<String>,
+}
+
+impl SourceApp {
+    /// `name` is the platform's display name for the app, if it has one.
+    pub fn new(id: String, name: Option<String>) -> Self {
+        let client = MediaClient::from_app_id(&id);
+        let name = name
+            .filter(|n| !n.is_empty())
+            .or_else(|| client.name().map(str::to_string))
+            .unwrap_or_else(|| id.clone());
+        Self {
+            id,
+            name,
+            client,
+            icon: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, ts_rs::TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+/// One row of `list_media_sessions`.
+#[derive(serde::Serialize, Clone, Debug, ts_rs::TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct MediaSession {
+    /// Pass to `select_media_session`. The MPRIS bus name on Linux, the app user model id on
+    /// Windows.
+    pub id: String,
+    pub source_app: SourceApp,
+    pub state: PlaybackState,
+    pub title: String,
+    /// The session `stream::media` is following.
+    pub followed: bool,
+    /// Pinned with `select_media_session`.
+    pub pinned: bool,
 }
 
 #[derive(serde::Serialize, Clone, Debug, ts_rs::TS)]
@@ -136,194 +541,47 @@ pub async fn get_visualiser_device() -> Result<String, String> {
 
 /* FFT stream  */
 
-use cpal::{Device, Stream, StreamConfig};
 use rustfft::{num_complex::Complex, Fft, FftPlanner};
-use std::sync::mpsc::{Receiver, Sender};
 
 const FFT_TARGET: &str = "media::fft";
 
+/// Audio capture plus one shared FFT. Produces the raw magnitude spectrum of the newest
+/// `fft_size` samples (mixed to mono, and optionally of the left and right channels on their
+/// own); turning that into bars is `analysis::SpectrumAnalyzer`'s job, so any number of
+/// differently-configured analyzers can share a single capture. The interleaved frames from the
+/// last `update` stay available for per-channel metering.
 pub struct FFTStream {
     pub device_name: String,
-    fft: Arc<dyn Fft<f32>>,
-    fft_buffer: Vec<Complex<f32>>,
+    transform: Transform,
     pub fft_size: usize,
     pub channels: usize,
     pub sample_rate: f32,
-    _stream: Stream,
-    data_receiver: Receiver<Vec<f32>>,
-    pub audio_buffer: Vec<f32>,
-    frequency_bins: Vec<(f32, f32)>,
-    bin_indices: Vec<Vec<usize>>,
-    smoothed_magnitudes: Vec<f32>,
-    attack_coeff: f32,
-    decay_coeff: f32,
-    a_weights: Vec<f32>,
+    source: Box<dyn AudioSource>,
+    /// Interleaved frames read by the last `update`.
+    incoming: Vec<f32>,
+    mono: AnalysisBuffer,
+    /// Left/right analysis, only while some output wants stereo (see `set_stereo`).
+    stereo: Option<[AnalysisBuffer; 2]>,
+}
+
+/// The planned FFT, its scratch buffer, and the Hann window applied before it.
+struct Transform {
+    fft: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex<f32>>,
     window: Vec<f32>,
+    window_scale: f32,
 }
 
-impl FFTStream {
-    pub fn new(fft_size: usize) -> Result<Self, Box<dyn std::error::Error>> {
-        tracing::debug!(target: FFT_TARGET, fft_size, "FFTStream::new");
+impl Transform {
+    fn new(fft_size: usize) -> Self {
         let mut planner = FftPlanner::new();
-        let fft = planner.plan_fft_forward(fft_size);
-        let fft_buffer = vec![Complex::new(0.0, 0.0); fft_size];
-
-        let host = cpal::default_host();
-        tracing::debug!(target: FFT_TARGET, host = ?host.id(), "querying default output device");
-        let device = host
-            .default_output_device()
-            .ok_or("No default output device found")?;
-
-        let device_name = device.name().unwrap_or_else(|_| "<unknown>".into());
-        tracing::debug!(target: FFT_TARGET, device = %device_name, "selected output device");
-
-        let supported_config = device
-            .default_output_config()
-            .map_err(|e| format!("Failed to get default output config: {}", e))?;
-
-        let sample_rate = supported_config.sample_rate().0 as f32;
-        let channels = supported_config.channels() as usize;
-        let sample_format = supported_config.sample_format();
-        tracing::debug!(target: FFT_TARGET, sample_rate, channels, format = ?sample_format, "stream config");
-        let config: StreamConfig = supported_config.into();
-
-        let (sender, receiver) = std::sync::mpsc::channel();
-
-        tracing::debug!(target: FFT_TARGET, format = ?sample_format, "building WASAPI loopback input stream");
-        let stream =
-            Self::build_wasapi_loopback_stream(&device, &config, sample_format, sender, channels)?;
-        tracing::debug!(target: FFT_TARGET, "input stream built — calling play()");
-        stream
-            .play()
-            .map_err(|e| format!("Failed to start WASAPI loopback stream: {}", e))?;
-        tracing::debug!(target: FFT_TARGET, "WASAPI loopback stream playing");
-
-        let (frequency_bins, bin_indices) =
-            Self::create_log_frequency_bins(sample_rate, fft_size, 64);
-        let smoothed_magnitudes = vec![0.0; frequency_bins.len()];
-        let attack_coeff = Self::time_constant_to_coeff(0.01, sample_rate / fft_size as f32);
-        let decay_coeff = Self::time_constant_to_coeff(0.3, sample_rate / fft_size as f32);
-        let a_weights = Self::create_a_weighting(&frequency_bins);
         let window = Self::create_hann_window(fft_size);
-
-        Ok(Self {
-            device_name: device.name()?,
-            fft,
-            fft_buffer,
-            sample_rate,
-            fft_size,
-            _stream: stream,
-            data_receiver: receiver,
-            audio_buffer: Vec::new(),
-            channels,
-            frequency_bins,
-            bin_indices,
-            smoothed_magnitudes,
-            attack_coeff,
-            decay_coeff,
-            a_weights,
+        Self {
+            fft: planner.plan_fft_forward(fft_size),
+            buffer: vec![Complex::new(0.0, 0.0); fft_size],
+            window_scale: window.iter().map(|&w| w * w).sum::<f32>().sqrt(),
             window,
-        })
-    }
-
-    fn build_wasapi_loopback_stream(
-        device: &Device,
-        config: &StreamConfig,
-        sample_format: cpal::SampleFormat,
-        sender: Sender<Vec<f32>>,
-        channels: usize,
-    ) -> Result<Stream, Box<dyn std::error::Error>> {
-        let err_fn = |err: cpal::StreamError| {
-            tracing::error!(target: FFT_TARGET, error = %err, "WASAPI loopback stream error callback fired");
-        };
-
-        let stream = match sample_format {
-            cpal::SampleFormat::F32 => {
-                tracing::debug!(target: FFT_TARGET, "building F32 loopback stream");
-                device.build_input_stream(
-                    config,
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        let mono = mix_to_mono_f32(data, channels);
-                        let _ = sender.send(mono);
-                    },
-                    err_fn,
-                    None,
-                )?
-            }
-            cpal::SampleFormat::I16 => {
-                tracing::debug!(target: FFT_TARGET, "building I16 loopback stream");
-                device.build_input_stream(
-                    config,
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        let float: Vec<f32> =
-                            data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-                        let _ = sender.send(mix_to_mono_f32(&float, channels));
-                    },
-                    err_fn,
-                    None,
-                )?
-            }
-            cpal::SampleFormat::U16 => {
-                tracing::debug!(target: FFT_TARGET, "building U16 loopback stream");
-                device.build_input_stream(
-                    config,
-                    move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                        let float: Vec<f32> = data
-                            .iter()
-                            .map(|&s| (s as f32 / u16::MAX as f32) - 0.5)
-                            .collect();
-                        let _ = sender.send(mix_to_mono_f32(&float, channels));
-                    },
-                    err_fn,
-                    None,
-                )?
-            }
-            fmt => {
-                tracing::error!(target: FFT_TARGET, format = ?fmt, "unsupported sample format for WASAPI loopback");
-                return Err("Unsupported sample format for WASAPI loopback".into());
-            }
-        };
-
-        Ok(stream)
-    }
-
-    fn create_log_frequency_bins(
-        sample_rate: f32,
-        fft_size: usize,
-        num_bins: usize,
-    ) -> (Vec<(f32, f32)>, Vec<Vec<usize>>) {
-        let nyquist = sample_rate / 2.0;
-        let log_min = 20.0_f32.ln();
-        let log_max = nyquist.min(20000.0).ln();
-        let log_step = (log_max - log_min) / num_bins as f32;
-
-        (0..num_bins)
-            .map(|i| {
-                let freq_lo = (log_min + i as f32 * log_step).exp();
-                let freq_hi = (log_min + (i + 1) as f32 * log_step).exp();
-                let bin_lo = ((freq_lo / nyquist) * (fft_size / 2) as f32).floor() as usize;
-                let bin_hi = ((freq_hi / nyquist) * (fft_size / 2) as f32).ceil() as usize;
-                let indices = (bin_lo..=bin_hi.min(fft_size / 2)).collect();
-                ((freq_lo, freq_hi), indices)
-            })
-            .unzip()
-    }
-
-    fn create_a_weighting(bins: &[(f32, f32)]) -> Vec<f32> {
-        bins.iter()
-            .map(|(lo, hi)| Self::a_weighting_response((lo + hi) / 2.0))
-            .collect()
-    }
-
-    fn a_weighting_response(freq: f32) -> f32 {
-        let f2 = freq * freq;
-        let f4 = f2 * f2;
-        let num = 12194.0_f32.powi(2) * f4;
-        let den = (f2 + 20.6_f32.powi(2))
-            * ((f2 + 107.7_f32.powi(2)) * (f2 + 737.9_f32.powi(2))).sqrt()
-            * (f2 + 12194.0_f32.powi(2));
-        let db = 20.0 * (num / den).log10() + 2.0;
-        10.0_f32.powf(db / 20.0)
+        }
     }
 
     fn create_hann_window(size: usize) -> Vec<f32> {
@@ -334,93 +592,140 @@ impl FFTStream {
             .collect()
     }
 
-    fn time_constant_to_coeff(tc: f32, sr: f32) -> f32 {
-        (-1.0 / (tc * sr)).exp()
+    /// Windows and transforms the newest `fft_size` samples of `target` into its spectrum.
+    fn run(&mut self, target: &mut AnalysisBuffer) {
+        let fft_size = self.buffer.len();
+        let audio_slice = &target.samples[target.samples.len() - fft_size..];
+        for (i, &s) in audio_slice.iter().enumerate() {
+            self.buffer[i] = Complex::new(s * self.window[i] / self.window_scale, 0.0);
+        }
+        self.fft.process(&mut self.buffer);
+
+        for (mag, c) in target.spectrum.iter_mut().zip(&self.buffer) {
+            *mag = (c.re * c.re + c.im * c.im).sqrt();
+        }
     }
+}
 
-    pub fn get(&mut self) -> Vec<FrequencyReading> {
-        let mut drained = 0usize;
-        while let Ok(data) = self.data_receiver.try_recv() {
-            drained += data.len();
-            self.audio_buffer.extend_from_slice(&data);
+/// One signal's rolling sample buffer (trimmed to between one and two FFT windows) and the
+/// magnitude spectrum of its newest window.
+struct AnalysisBuffer {
+    samples: Vec<f32>,
+    spectrum: Vec<f32>,
+}
+
+impl AnalysisBuffer {
+    fn new(fft_size: usize) -> Self {
+        Self {
+            samples: Vec::new(),
+            spectrum: vec![0.0; fft_size / 2 + 1],
         }
+    }
 
-        if self.audio_buffer.len() < self.fft_size {
-            tracing::trace!(target: FFT_TARGET, buf_len = self.audio_buffer.len(), fft_size = self.fft_size, "buffer underrun — returning smoothed values");
-            return self
-                .frequency_bins
-                .iter()
-                .zip(&self.smoothed_magnitudes)
-                .map(|((lo, hi), &mag)| FrequencyReading {
-                    freq_lo: *lo,
-                    freq_hi: *hi,
-                    magnitude: mag,
-                })
-                .collect();
+    fn is_full(&self, fft_size: usize) -> bool {
+        self.samples.len() >= fft_size
+    }
+
+    fn trim(&mut self, fft_size: usize) {
+        if self.samples.len() > fft_size * 2 {
+            self.samples.drain(..fft_size);
         }
+    }
+}
 
-        let start = self.audio_buffer.len() - self.fft_size;
-        let audio_slice = &self.audio_buffer[start..];
-        let window_scale = self.window.iter().map(|&w| w * w).sum::<f32>().sqrt();
+impl FFTStream {
+    /// Opens an `FFTStream` on the default output device's loopback capture.
+    pub fn new(fft_size: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::with_source(Box::new(LoopbackSource::open_default()?), fft_size))
+    }
 
-        for (i, &s) in audio_slice.iter().enumerate() {
-            self.fft_buffer[i] = Complex::new(s * self.window[i] / window_scale, 0.0);
+    pub fn with_source(source: Box<dyn AudioSource>, fft_size: usize) -> Self {
+        tracing::debug!(target: FFT_TARGET, fft_size, source = source.name(), "FFTStream::with_source");
+        Self {
+            device_name: source.name().to_string(),
+            channels: source.channels(),
+            sample_rate: source.sample_rate(),
+            transform: Transform::new(fft_size),
+            fft_size,
+            source,
+            incoming: Vec::new(),
+            mono: AnalysisBuffer::new(fft_size),
+            stereo: None,
         }
-        self.fft.process(&mut self.fft_buffer);
+    }
 
-        let mut current = Vec::with_capacity(self.frequency_bins.len());
-        let mut max_mag = 0.0_f32;
+    /// Turns separate left/right analysis on or off. Costs two extra FFTs per update, so the
+    /// visualizer loop only enables it while an output asks for stereo. Mono sources feed the
+    /// same signal to both sides.
+    pub fn set_stereo(&mut self, enabled: bool) {
+        if enabled != self.stereo.is_some() {
+            tracing::debug!(target: FFT_TARGET, enabled, channels = self.channels, "stereo analysis");
+            let fft_size = self.fft_size;
+            self.stereo =
+                enabled.then(|| [AnalysisBuffer::new(fft_size), AnalysisBuffer::new(fft_size)]);
+        }
+    }
 
-        for (bin_idx, indices) in self.bin_indices.iter().enumerate() {
-            if indices.is_empty() {
-                current.push(0.0);
-                continue;
-            }
-            let mag: f32 = indices
-                .iter()
-                .map(|&i| {
-                    let c = &self.fft_buffer[i];
-                    (c.re * c.re + c.im * c.im).sqrt()
-                })
-                .sum::<f32>()
-                / indices.len() as f32;
-
-            let weighted = mag * self.a_weights[bin_idx];
-            let scaled = ((weighted + 1e-10).log10() + 6.0) / 6.0;
-            let final_mag = scaled.max(0.0);
-            current.push(final_mag);
-            max_mag = max_mag.max(final_mag);
+    /// Pulls whatever audio the source has buffered and, if there is a full window, recomputes
+    /// the magnitude spectra. Returns `false` on buffer underrun, leaving the previous spectra
+    /// in place.
+    pub fn update(&mut self) -> bool {
+        self.incoming.clear();
+        self.source.read(&mut self.incoming);
+        self.mono.samples.extend(mix_to_mono_f32(&self.incoming, self.channels));
+        if let Some([left, right]) = &mut self.stereo {
+            let (l, r) = split_stereo_f32(&self.incoming, self.channels);
+            left.samples.extend(l);
+            right.samples.extend(r);
         }
 
-        if max_mag > 0.0 {
-            for m in &mut current {
-                *m = (*m / max_mag).min(1.0);
-            }
+        if !self.mono.is_full(self.fft_size) {
+            tracing::trace!(target: FFT_TARGET, buf_len = self.mono.samples.len(), fft_size = self.fft_size, "buffer underrun — keeping previous spectrum");
+            return false;
         }
 
-        for (i, &cur) in current.iter().enumerate() {
-            let s = &mut self.smoothed_magnitudes[i];
-            let coeff = if cur > *s {
-                self.attack_coeff
-            } else {
-                self.decay_coeff
-            };
-            *s = *s * coeff + cur * (1.0 - coeff);
+        self.transform.run(&mut self.mono);
+        self.mono.trim(self.fft_size);
+        if let Some(buffers) = &mut self.stereo {
+            for buffer in buffers {
+                if buffer.is_full(self.fft_size) {
+                    self.transform.run(buffer);
+                    buffer.trim(self.fft_size);
+                }
+            }
         }
+        true
+    }
+
+    /// Mono samples waiting in the buffer — normally between one and two FFT windows.
+    pub fn buffered(&self) -> usize {
+        self.mono.samples.len()
+    }
+
+    /// Magnitude per FFT bin, `0..=fft_size / 2`, of the mono mix from the last successful
+    /// `update`.
+    pub fn spectrum(&self) -> &[f32] {
+        &self.mono.spectrum
+    }
 
-        if self.audio_buffer.len() > self.fft_size * 2 {
-            self.audio_buffer.drain(..self.fft_size);
+    /// Left and right magnitude spectra, once stereo analysis is enabled and has a full window.
+    pub fn stereo_spectra(&self) -> Option<(&[f32], &[f32])> {
+        match &self.stereo {
+            Some([l, r]) if l.is_full(self.fft_size) => Some((&l.spectrum, &r.spectrum)),
+            _ => None,
         }
+    }
 
-        self.frequency_bins
-            .iter()
-            .zip(&self.smoothed_magnitudes)
-            .map(|((lo, hi), &mag)| FrequencyReading {
-                freq_lo: *lo,
-                freq_hi: *hi,
-                magnitude: mag,
-            })
-            .collect()
+    /// Interleaved frames (`channels` samples each) read by the last `update`, whether or not
+    /// there were enough for an FFT.
+    pub fn incoming(&self) -> &[f32] {
+        &self.incoming
+    }
+
+    /// The newest `len` mono samples (fewer while the buffer is still filling).
+    pub fn latest(&self, len: usize) -> &[f32] {
+        let samples = &self.mono.samples;
+        &samples[samples.len().saturating_sub(len)..]
     }
 }
 
@@ -432,3 +737,14 @@ fn mix_to_mono_f32(data: &[f32], channels: usize) -> Vec<f32> {
         .map(|frame| frame.iter().sum::<f32>() / channels as f32)
         .collect()
 }
+
+/// Splits interleaved frames into left and right. Surround layouts put front-left/front-right
+/// first, so anything past the second channel is ignored; mono goes to both sides.
+fn split_stereo_f32(data: &[f32], channels: usize) -> (Vec<f32>, Vec<f32>) {
+    if channels == 1 {
+        return (data.to_vec(), data.to_vec());
+    }
+    data.chunks_exact(channels)
+        .map(|frame| (frame[0], frame[1]))
+        .unzip()
+}
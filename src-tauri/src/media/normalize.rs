@@ -0,0 +1,317 @@
+//! Per-client clean-up of what the platform backends report, between them and `stream::media`.
+//! Rules come from `media_rules.json` in the config dir (written from `default_rules.json` on
+//! first run) and are re-read whenever the file changes:
+//!
+//! - `apps` give an app id a friendly name and an icon;
+//! - `rules` rewrite the track of matching sessions: split `Artist - Title` titles, split
+//!   `Artist — Album` artists, and strip tags like `(Official Video)`.
+//!
+//! Matching is a case-insensitive substring test, against the app id (`SourceApp::id`) and the
+//! URL of what's playing. Only players that report a URL can be matched on it; on Linux that
+//! includes the browsers, on Windows nothing does.
+
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use super::MediaState;
+
+const TARGET: &str = "media::normalize";
+const DEFAULT_RULES: &str = include_str!("default_rules.json");
+
+#[derive(Deserialize, Default, Debug)]
+struct MediaRules {
+    #[serde(default)]
+    apps: Vec<AppRule>,
+    /// All that match apply, in order.
+    #[serde(default)]
+    rules: Vec<MediaRule>,
+}
+
+/// The first that matches wins.
+#[derive(Deserialize, Debug)]
+struct AppRule {
+    app_id: String,
+    name: Option<String>,
+    /// Passed through to `SourceApp::icon`.
+    icon: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MediaRule {
+    /// Unset matches every app.
+    app_id: Option<String>,
+    /// Unset matches with or without a URL.
+    url: Option<String>,
+    /// Separator of `Artist - Title` titles. The artist it yields replaces the reported one,
+    /// which for videos is usually the uploader.
+    split_title: Option<String>,
+    /// Separator of `Artist — Album` artists; applied only while the album is empty.
+    split_artist: Option<String>,
+    /// Trailing `(…)`/`[…]` groups to drop from the title, by their text.
+    #[serde(default)]
+    strip_tags: Vec<String>,
+}
+
+impl MediaRules {
+    /// The first app rule whose id is in `app_id`.
+    fn app(&self, app_id: &str) -> Option<&AppRule> {
+        self.apps.iter().find(|r| contains(app_id, &r.app_id))
+    }
+
+    fn apply(&self, state: &mut MediaState) {
+        let Some(app) = state.source_app.as_mut() else {
+            return;
+        };
+        if let Some(rule) = self.app(&app.id) {
+            if let Some(name) = &rule.name {
+                app.name = name.clone();
+            }
+            app.icon = rule.icon.clone();
+        }
+        let app_id = app.id.clone();
+        let url = state.url.clone();
+        for rule in self
+            .rules
+            .iter()
+            .filter(|r| r.matches(&app_id, url.as_deref()))
+        {
+            rule.apply(state);
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl MediaRule {
+    fn matches(&self, app_id: &str, url: Option<&str>) -> bool {
+        self.app_id.as_deref().is_none_or(|id| contains(app_id, id))
+            && self
+                .url
+                .as_deref()
+                .is_none_or(|u| url.is_some_and(|url| contains(url, u)))
+    }
+
+    fn apply(&self, state: &mut MediaState) {
+        if !self.strip_tags.is_empty() {
+            state.title = strip_tags(&state.title, &self.strip_tags);
+        }
+        if let Some(separator) = &self.split_title {
+            if let Some((artist, title)) = split(&state.title, separator) {
+                state.artist = artist;
+                state.title = title;
+            }
+        }
+        if let (Some(separator), true) = (&self.split_artist, state.album.is_empty()) {
+            if let Some((artist, album)) = split(&state.artist, separator) {
+                state.artist = artist;
+                state.album = album;
+            }
+        }
+    }
+}
+
+/// `text` around the first `separator`, if both sides have something in them.
+fn split(text: &str, separator: &str) -> Option<(String, String)> {
+    let (left, right) = text.split_once(separator)?;
+    let (left, right) = (left.trim(), right.trim());
+    (!left.is_empty() && !right.is_empty()).then(|| (left.to_string(), right.to_string()))
+}
+
+/// Drops trailing bracket groups whose text is one of `tags`, case-insensitively, until the
+/// last one isn't: `Song (Official Video) [HD]` becomes `Song`; `Song (Live)` stays.
+fn strip_tags(title: &str, tags: &[String]) -> String {
+    let mut title = title.trim_end();
+    loop {
+        let open = match title.chars().last() {
+            Some(')') => '(',
+            Some(']') => '[',
+            _ => break,
+        };
+        let Some(start) = title.rfind(open) else {
+            break;
+        };
+        let tag = title[start + 1..title.len() - 1].trim();
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            break;
+        }
+        let rest = title[..start].trim_end();
+        if rest.is_empty() {
+            break;
+        }
+        title = rest;
+    }
+    title.to_string()
+}
+
+struct Loaded {
+    rules: MediaRules,
+    modified: Option<SystemTime>,
+}
+
+static RULES: Mutex<Option<Loaded>> = Mutex::new(None);
+
+fn parse(text: &str, path: &Path) -> Option<MediaRules> {
+    serde_json::from_str(text)
+        .map_err(|err| tracing::warn!(target: TARGET, path = %path.display(), error = %err, "invalid media rules; keeping the previous ones"))
+        .ok()
+}
+
+/// Re-reads the rules file if it changed since the last call. An unreadable or invalid file
+/// leaves the rules as they were (the built-in defaults, before any file was read).
+fn reload(loaded: &mut Option<Loaded>) {
+    let Some(path) = crate::config::get_media_rules_path() else {
+        return;
+    };
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+    if loaded.as_ref().is_some_and(|l| l.modified == modified) {
+        return;
+    }
+    let rules = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| parse(&text, &path));
+    match (rules, loaded.as_mut()) {
+        (Some(rules), _) => {
+            tracing::info!(target: TARGET, path = %path.display(), apps = rules.apps.len(), rules = rules.rules.len(), "media rules loaded");
+            *loaded = Some(Loaded { rules, modified });
+        }
+        // Don't retry a broken file until it changes again.
+        (None, Some(l)) => l.modified = modified,
+        (None, None) => {
+            *loaded = Some(Loaded {
+                rules: parse(DEFAULT_RULES, Path::new("default_rules.json")).unwrap_or_default(),
+                modified,
+            })
+        }
+    }
+}
+
+/// Runs `state` through the rules.
+pub fn apply(mut state: MediaState) -> MediaState {
+    let Ok(mut guard) = RULES.lock() else {
+        return state;
+    };
+    reload(&mut guard);
+    if let Some(loaded) = guard.as_ref() {
+        loaded.rules.apply(&mut state);
+    }
+    state
+}
+
+/// Writes the default rules file if there is none, for users to edit.
+pub fn ensure_default_rules() {
+    let Some(path) = crate::config::get_media_rules_path() else {
+        return;
+    };
+    if path.exists() {
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match std::fs::write(&path, DEFAULT_RULES) {
+        Ok(()) => {
+            tracing::debug!(target: TARGET, path = %path.display(), "default media rules written")
+        }
+        Err(err) => {
+            tracing::warn!(target: TARGET, error = %err, "failed to write default media rules")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::SourceApp;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn rule(app_id: Option<&str>, url: Option<&str>) -> MediaRule {
+        MediaRule {
+            app_id: app_id.map(str::to_string),
+            url: url.map(str::to_string),
+            split_title: None,
+            split_artist: None,
+            strip_tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn strips_stacked_tags_but_keeps_the_rest() {
+        let video = tags(&["official video", "hd", "lyrics"]);
+        assert_eq!(strip_tags("Song (Official Video) [HD]", &video), "Song");
+        assert_eq!(strip_tags("Song [hd] (LYRICS)  ", &video), "Song");
+        assert_eq!(strip_tags("Song (Live)", &video), "Song (Live)");
+        // Only trailing groups, and only up to the first that isn't a tag.
+        assert_eq!(strip_tags("Song (Live) (HD)", &video), "Song (Live)");
+        assert_eq!(strip_tags("Song (HD) - Remix", &video), "Song (HD) - Remix");
+        // A title that is nothing but a tag is left whole rather than emptied.
+        assert_eq!(strip_tags("(Official Video)", &video), "(Official Video)");
+        assert_eq!(strip_tags("Lyrics [HD]", &video), "Lyrics");
+    }
+
+    #[test]
+    fn splits_at_the_first_separator_when_both_sides_are_filled() {
+        let split = |text| split(text, " - ");
+        assert_eq!(
+            split("Daft Punk - Get Lucky"),
+            Some(("Daft Punk".into(), "Get Lucky".into()))
+        );
+        assert_eq!(split("A - B - C"), Some(("A".into(), "B - C".into())));
+        assert_eq!(split("Get Lucky"), None);
+        assert_eq!(split(" - Get Lucky"), None);
+        assert_eq!(split("Daft Punk - "), None);
+    }
+
+    #[test]
+    fn rules_match_on_app_and_url() {
+        let watch = "https://www.YouTube.com/watch?v=5NV6Rdv1a3I";
+        let youtube = rule(None, Some("youtube.com/watch"));
+        assert!(youtube.matches("firefox", Some(watch)));
+        assert!(!youtube.matches("firefox", Some("https://music.example.com/")));
+        // A URL rule never matches a player that doesn't report one.
+        assert!(!youtube.matches("firefox", None));
+
+        let apple = rule(Some("AppleMusic"), None);
+        assert!(apple.matches("com.apple.applemusic", None));
+        assert!(apple.matches("com.apple.applemusic", Some(watch)));
+        assert!(!apple.matches("spotify", None));
+
+        assert!(rule(Some("firefox"), Some("youtube.com")).matches("firefox", Some(watch)));
+        assert!(!rule(Some("chrome"), Some("youtube.com")).matches("firefox", Some(watch)));
+    }
+
+    #[test]
+    fn the_first_matching_app_rule_names_the_app() {
+        let rules: MediaRules = serde_json::from_str(DEFAULT_RULES).unwrap();
+        let name = |id: &str| rules.app(id).and_then(|r| r.name.as_deref());
+        assert_eq!(name("spotify"), Some("Spotify"));
+        assert_eq!(name("MSEdge"), Some("Microsoft Edge"));
+        assert_eq!(
+            rules.app("firefox").unwrap().icon.as_deref(),
+            Some("firefox")
+        );
+        assert!(rules.app("mpv").is_none());
+
+        let mut state = MediaState::inactive();
+        state.source_app = Some(SourceApp::new("msedge".into(), None));
+        state.url = Some("https://www.youtube.com/watch?v=5NV6Rdv1a3I".into());
+        state.title = "Daft Punk - Get Lucky (Official Audio)".into();
+        state.artist = "DaftPunkVEVO".into();
+        rules.apply(&mut state);
+        let app = state.source_app.unwrap();
+        assert_eq!(
+            (app.name.as_str(), app.icon.as_deref()),
+            ("Microsoft Edge", Some("edge"))
+        );
+        assert_eq!(
+            (state.artist.as_str(), state.title.as_str()),
+            ("Daft Punk", "Get Lucky")
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Colours pulled from album art for widgets and themes to follow what's playing. The thumbnail
+//! is decoded, shrunk, and its pixels k-means clustered in OKLab — the Cartesian form of OKLCH,
+//! so distances are perceptual — then the clusters are picked from by their OKLCH lightness and
+//! chroma. Runs once per artwork: the last result is kept against a hash of the base64.
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use ts_rs::TS;
+
+use crate::config::{
+    oklab_to_linear_srgb, oklab_to_oklch, oklch_to_hex, oklch_to_oklab, srgb_to_oklab,
+};
+
+const TARGET: &str = "media::palette";
+/// Thumbnails are shrunk to fit this before clustering; more pixels don't change the palette.
+const SAMPLE_SIZE: u32 = 64;
+const CLUSTERS: usize = 8;
+const MAX_ITERATIONS: usize = 12;
+/// Clusters smaller than this share of the image are specks, not colours of the artwork.
+const MIN_SHARE: f32 = 0.02;
+/// The backends encode artwork without padding; accept it either way.
+const ART_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Payload of `MediaState::palette`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct AlbumPalette {
+    /// The colour covering most of the artwork.
+    pub dominant: PaletteColor,
+    /// The most saturated colour of mid lightness; the dominant hue, saturated, when the artwork
+    /// has none, and a mid grey when the artwork is greyscale.
+    pub vibrant: PaletteColor,
+    /// The largest greyish colour of mid lightness; the dominant hue, desaturated, when the
+    /// artwork has none.
+    pub muted: PaletteColor,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct PaletteColor {
+    /// `#rrggbb`.
+    pub color: String,
+    /// Near-white or near-black in the same hue, whichever contrasts more with `color`, for text
+    /// drawn on it.
+    pub foreground: String,
+    /// Share of the artwork's pixels, `0.0..=1.0`; 0 for colours made up as a fallback.
+    pub population: f32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Lab {
+    l: f64,
+    a: f64,
+    b: f64,
+}
+
+impl Lab {
+    fn distance_sq(self, other: Lab) -> f64 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+struct Cluster {
+    /// OKLCH of the centroid.
+    lch: (f64, f64, f64),
+    share: f32,
+}
+
+static LAST: Mutex<Option<(u64, Option<AlbumPalette>)>> = Mutex::new(None);
+
+/// The palette of `art_b64`, or `None` without artwork or when it won't decode.
+pub fn of(art_b64: Option<&str>) -> Option<AlbumPalette> {
+    let art = art_b64?;
+    let mut hasher = DefaultHasher::new();
+    art.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let mut last = LAST.lock().ok()?;
+    if let Some((cached, palette)) = last.as_ref() {
+        if *cached == key {
+            return palette.clone();
+        }
+    }
+    let palette = extract(art);
+    *last = Some((key, palette.clone()));
+    palette
+}
+
+fn extract(art_b64: &str) -> Option<AlbumPalette> {
+    let bytes = ART_BASE64
+        .decode(art_b64)
+        .map_err(|err| tracing::debug!(target: TARGET, error = %err, "artwork isn't base64"))
+        .ok()?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|err| tracing::debug!(target: TARGET, error = %err, "artwork didn't decode"))
+        .ok()?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgba8();
+
+    // Transparent pixels are background, not artwork.
+    let pixels: Vec<Lab> = image
+        .pixels()
+        .filter(|p| p[3] >= 128)
+        .map(|p| {
+            let (l, a, b) = srgb_to_oklab(p[0], p[1], p[2]);
+            Lab { l, a, b }
+        })
+        .collect();
+    if pixels.is_empty() {
+        return None;
+    }
+
+    let clusters = kmeans(&pixels);
+    let palette = pick(&clusters);
+    tracing::debug!(target: TARGET, dominant = %palette.dominant.color, vibrant = %palette.vibrant.color, muted = %palette.muted.color, "palette extracted");
+    Some(palette)
+}
+
+/// Clusters `pixels`, largest first. Seeded from evenly spaced pixels in lightness order, so the
+/// same artwork always gives the same palette.
+fn kmeans(pixels: &[Lab]) -> Vec<Cluster> {
+    let mut sorted = pixels.to_vec();
+    sorted.sort_by(|x, y| x.l.total_cmp(&y.l));
+    let k = CLUSTERS.min(sorted.len());
+    let mut centroids: Vec<Lab> = (0..k)
+        .map(|i| sorted[(2 * i + 1) * sorted.len() / (2 * k)])
+        .collect();
+
+    let mut assignment = vec![0; pixels.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut moved = false;
+        for (pixel, slot) in pixels.iter().zip(assignment.iter_mut()) {
+            let nearest = (0..k)
+                .min_by(|&i, &j| {
+                    pixel
+                        .distance_sq(centroids[i])
+                        .total_cmp(&pixel.distance_sq(centroids[j]))
+                })
+                .unwrap_or(0);
+            moved |= nearest != *slot;
+            *slot = nearest;
+        }
+
+        let mut sums = vec![(Lab::default(), 0usize); k];
+        for (pixel, &slot) in pixels.iter().zip(&assignment) {
+            let (sum, count) = &mut sums[slot];
+            sum.l += pixel.l;
+            sum.a += pixel.a;
+            sum.b += pixel.b;
+            *count += 1;
+        }
+        for (centroid, (sum, count)) in centroids.iter_mut().zip(&sums) {
+            // An emptied cluster keeps its centroid, and stays empty.
+            if *count > 0 {
+                let n = *count as f64;
+                *centroid = Lab {
+                    l: sum.l / n,
+                    a: sum.a / n,
+                    b: sum.b / n,
+                };
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    let mut counts = vec![0usize; k];
+    for &slot in &assignment {
+        counts[slot] += 1;
+    }
+    let mut clusters: Vec<Cluster> = centroids
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(c, count)| Cluster {
+            lch: oklab_to_oklch(c.l, c.a, c.b),
+            share: count as f32 / pixels.len() as f32,
+        })
+        .collect();
+    clusters.sort_by(|x, y| y.share.total_cmp(&x.share));
+    clusters
+}
+
+fn pick(clusters: &[Cluster]) -> AlbumPalette {
+    let dominant = &clusters[0];
+    let (_, dominant_chroma, dominant_hue) = dominant.lch;
+    // The hue of a grey is noise.
+    let fallback_chroma = if dominant_chroma < 0.02 { 0.0 } else { 0.15 };
+    let mid = |c: &&Cluster| (0.35..=0.85).contains(&c.lch.0) && c.share >= MIN_SHARE;
+
+    let vibrant = clusters
+        .iter()
+        .filter(mid)
+        .filter(|c| c.lch.1 >= 0.08)
+        .max_by(|x, y| x.lch.1.total_cmp(&y.lch.1))
+        .map(color)
+        .unwrap_or_else(|| swatch((0.65, fallback_chroma, dominant_hue), 0.0));
+    let muted = clusters
+        .iter()
+        .filter(mid)
+        .find(|c| c.lch.1 < 0.06)
+        .map(color)
+        .unwrap_or_else(|| swatch((0.55, 0.03, dominant_hue), 0.0));
+
+    AlbumPalette {
+        dominant: color(dominant),
+        vibrant,
+        muted,
+    }
+}
+
+fn color(cluster: &Cluster) -> PaletteColor {
+    swatch(cluster.lch, cluster.share)
+}
+
+fn swatch((l, c, h): (f64, f64, f64), population: f32) -> PaletteColor {
+    let light = (0.97, c.min(0.02), h);
+    let dark = (0.2, c.min(0.04), h);
+    let background = luminance((l, c, h));
+    let (fl, fc, fh) =
+        if contrast(background, luminance(light)) >= contrast(background, luminance(dark)) {
+            light
+        } else {
+            dark
+        };
+    PaletteColor {
+        color: oklch_to_hex(l, c, h),
+        foreground: oklch_to_hex(fl, fc, fh),
+        population,
+    }
+}
+
+/// WCAG relative luminance, with the channels clamped into gamut as `oklch_to_hex` does.
+fn luminance((l, c, h): (f64, f64, f64)) -> f64 {
+    let (lab_l, lab_a, lab_b) = oklch_to_oklab(l, c, h);
+    let (r, g, b) = oklab_to_linear_srgb(lab_l, lab_a, lab_b);
+    0.2126 * r.clamp(0.0, 1.0) + 0.7152 * g.clamp(0.0, 1.0) + 0.0722 * b.clamp(0.0, 1.0)
+}
+
+/// WCAG contrast ratio of two luminances.
+fn contrast(x: f64, y: f64) -> f64 {
+    (x.max(y) + 0.05) / (x.min(y) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    const RED: [u8; 3] = [0xd0, 0x20, 0x20];
+
+    /// Three quarters red over a near-white quarter, encoded as the backends would hand it over.
+    fn artwork(format: ImageFormat) -> Vec<u8> {
+        let image = RgbImage::from_fn(64, 64, |_, y| {
+            Rgb(if y < 48 { RED } else { [0xf0, 0xf0, 0xf0] })
+        });
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn channels(hex: &str) -> [u8; 3] {
+        let n = u32::from_str_radix(hex.trim_start_matches('#'), 16).unwrap();
+        [(n >> 16) as u8, (n >> 8) as u8, n as u8]
+    }
+
+    fn assert_red(palette: &AlbumPalette, tolerance: u8) {
+        let dominant = channels(&palette.dominant.color);
+        assert!(
+            dominant
+                .iter()
+                .zip(RED)
+                .all(|(c, r)| c.abs_diff(r) <= tolerance),
+            "dominant {}",
+            palette.dominant.color
+        );
+        assert!((palette.dominant.population - 0.75).abs() < 0.05);
+        assert_eq!(palette.vibrant.color, palette.dominant.color);
+        // No mid-lightness grey in the artwork: made up from the dominant hue.
+        assert_eq!(palette.muted.population, 0.0);
+    }
+
+    #[test]
+    fn extracts_from_unpadded_png_and_jpeg() {
+        let png = STANDARD_NO_PAD.encode(artwork(ImageFormat::Png));
+        assert_red(&of(Some(&png)).unwrap(), 1);
+
+        let jpeg = STANDARD_NO_PAD.encode(artwork(ImageFormat::Jpeg));
+        assert_red(&of(Some(&jpeg)).unwrap(), 8);
+    }
+
+    #[test]
+    fn decodes_base64_with_or_without_padding() {
+        assert_eq!(ART_BASE64.decode("YQ").unwrap(), b"a");
+        assert_eq!(ART_BASE64.decode("YQ==").unwrap(), b"a");
+        let png = artwork(ImageFormat::Png);
+        assert_eq!(
+            extract(&STANDARD.encode(&png)),
+            extract(&STANDARD_NO_PAD.encode(&png))
+        );
+    }
+
+    #[test]
+    fn gives_none_without_artwork_or_for_garbage() {
+        assert_eq!(of(None), None);
+        assert_eq!(of(Some("not base64!")), None);
+        assert_eq!(of(Some(&STANDARD_NO_PAD.encode(b"not an image"))), None);
+    }
+}
@@ -0,0 +1,619 @@
+//! Audio inputs for `FFTStream`. It only ever sees interleaved `f32` frames pulled through
+//! [`AudioSource::read`], so it can run on the platform loopback capture, a WAV file, or a
+//! generated signal — the latter two need no audio hardware at all, which is what makes the bin
+//! placement and smoothing checkable against known inputs and the visualizer demoable headless.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Instant;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, Stream, StreamConfig};
+
+use super::FFT_TARGET;
+
+pub trait AudioSource {
+    /// Identifies the underlying device or file. The visualizer loops compare this against the
+    /// current default device to detect switches, so loopback sources must report the device name.
+    fn name(&self) -> &str;
+    fn sample_rate(&self) -> f32;
+    /// Samples per frame in what `read` hands out.
+    fn channels(&self) -> usize;
+    /// Appends every frame (interleaved, `channels()` samples each) that became available since
+    /// the last call.
+    fn read(&mut self, out: &mut Vec<f32>);
+}
+
+/// Which source the visualizer should build its `FFTStream` on, parsed from `--visualizer-source`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceSpec {
+    /// Whatever is playing on the default output device.
+    Loopback,
+    /// A WAV file, looped.
+    Wav(PathBuf),
+    Synthetic(Signal),
+}
+
+impl SourceSpec {
+    /// Parses `loopback`, `wav:<path>`, `sine:<hz>`, `sweep` or `noise`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, arg) = match spec.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (spec, None),
+        };
+        match (kind, arg) {
+            ("loopback", None) => Ok(Self::Loopback),
+            ("wav", Some(path)) if !path.is_empty() => Ok(Self::Wav(PathBuf::from(path))),
+            ("sine", Some(hz)) => hz
+                .parse::<f32>()
+                .ok()
+                .filter(|hz| *hz > 0.0)
+                .map(|freq| Self::Synthetic(Signal::Sine { freq, amplitude: 0.5 }))
+                .ok_or_else(|| format!("invalid sine frequency: {hz}")),
+            ("sweep", None) => Ok(Self::Synthetic(Signal::Sweep {
+                from: 20.0,
+                to: 20_000.0,
+                period_secs: 10.0,
+                amplitude: 0.5,
+            })),
+            ("noise", None) => Ok(Self::Synthetic(Signal::WhiteNoise { amplitude: 0.25 })),
+            _ => Err(format!(
+                "unknown visualizer source '{spec}' (expected loopback, wav:<path>, sine:<hz>, sweep or noise)"
+            )),
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        matches!(self, Self::Loopback)
+    }
+
+    pub fn open(&self) -> Result<Box<dyn AudioSource>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Self::Loopback => Box::new(LoopbackSource::open_default()?),
+            Self::Wav(path) => Box::new(WavSource::open(path, Pacing::Realtime)?),
+            Self::Synthetic(signal) => Box::new(SyntheticSource::new(
+                signal.clone(),
+                48_000.0,
+                Pacing::Realtime,
+            )),
+        })
+    }
+}
+
+/// How many frames a file or generated source hands out per `read`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pacing {
+    /// As many as the wall clock says have "played" since the last read, like a live capture.
+    Realtime,
+    /// Exactly this many per read, independent of time — deterministic, for checking FFT output.
+    Fixed(usize),
+}
+
+/// Longest gap a realtime source will catch up on in one read; after a stall (e.g. the
+/// visualizer thread paused for lack of subscribers) it resumes instead of dumping a backlog.
+const MAX_CATCH_UP_SECS: f32 = 0.25;
+
+struct Pacer {
+    pacing: Pacing,
+    last: Option<Instant>,
+    /// Fractional frame carried between realtime reads so the long-run rate is exact.
+    remainder: f32,
+}
+
+impl Pacer {
+    fn new(pacing: Pacing) -> Self {
+        Self {
+            pacing,
+            last: None,
+            remainder: 0.0,
+        }
+    }
+
+    fn take(&mut self, sample_rate: f32) -> usize {
+        match self.pacing {
+            Pacing::Fixed(n) => n,
+            Pacing::Realtime => {
+                let now = Instant::now();
+                let elapsed = self
+                    .last
+                    .replace(now)
+                    .map(|last| (now - last).as_secs_f32().min(MAX_CATCH_UP_SECS))
+                    .unwrap_or(0.0);
+                let exact = elapsed * sample_rate + self.remainder;
+                let whole = exact.floor();
+                self.remainder = exact - whole;
+                whole as usize
+            }
+        }
+    }
+}
+
+/* Loopback capture  */
+
+/// A running platform capture feeding interleaved `f32` frames into a channel. `handle` owns the
+/// platform stream (a cpal `Stream` on Windows, the PulseAudio reader on Linux) — dropping it
+/// stops capture.
+pub struct LoopbackCapture {
+    pub device_name: String,
+    pub sample_rate: f32,
+    pub channels: usize,
+    pub handle: Box<dyn std::any::Any>,
+}
+
+/// Captures whatever is playing on the default output device: WASAPI loopback through cpal on
+/// Windows, the default sink's monitor source on Linux.
+pub struct LoopbackSource {
+    capture: LoopbackCapture,
+    receiver: Receiver<Vec<f32>>,
+}
+
+impl LoopbackSource {
+    pub fn open_default() -> Result<Self, Box<dyn std::error::Error>> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        #[cfg(target_os = "linux")]
+        let capture = super::imp::open_monitor_capture(sender)?;
+        #[cfg(not(target_os = "linux"))]
+        let capture = open_wasapi_loopback(sender)?;
+        Ok(Self { capture, receiver })
+    }
+}
+
+impl AudioSource for LoopbackSource {
+    fn name(&self) -> &str {
+        &self.capture.device_name
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.capture.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        self.capture.channels
+    }
+
+    fn read(&mut self, out: &mut Vec<f32>) {
+        while let Ok(data) = self.receiver.try_recv() {
+            out.extend_from_slice(&data);
+        }
+    }
+}
+
+/// Opens a WASAPI loopback capture on the default output device.
+fn open_wasapi_loopback(
+    sender: Sender<Vec<f32>>,
+) -> Result<LoopbackCapture, Box<dyn std::error::Error>> {
+    let host = cpal::default_host();
+    tracing::debug!(target: FFT_TARGET, host = ?host.id(), "querying default output device");
+    let device = host
+        .default_output_device()
+        .ok_or("No default output device found")?;
+
+    let device_name = device.name().unwrap_or_else(|_| "<unknown>".into());
+    tracing::debug!(target: FFT_TARGET, device = %device_name, "selected output device");
+
+    let supported_config = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get default output config: {}", e))?;
+
+    let sample_rate = supported_config.sample_rate().0 as f32;
+    let channels = supported_config.channels() as usize;
+    let sample_format = supported_config.sample_format();
+    tracing::debug!(target: FFT_TARGET, sample_rate, channels, format = ?sample_format, "stream config");
+    let config: StreamConfig = supported_config.into();
+
+    tracing::debug!(target: FFT_TARGET, format = ?sample_format, "building WASAPI loopback input stream");
+    let stream = build_wasapi_loopback_stream(&device, &config, sample_format, sender)?;
+    tracing::debug!(target: FFT_TARGET, "input stream built — calling play()");
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start WASAPI loopback stream: {}", e))?;
+    tracing::debug!(target: FFT_TARGET, "WASAPI loopback stream playing");
+
+    Ok(LoopbackCapture {
+        device_name: device.name()?,
+        sample_rate,
+        channels,
+        handle: Box::new(stream),
+    })
+}
+
+fn build_wasapi_loopback_stream(
+    device: &Device,
+    config: &StreamConfig,
+    sample_format: cpal::SampleFormat,
+    sender: Sender<Vec<f32>>,
+) -> Result<Stream, Box<dyn std::error::Error>> {
+    let err_fn = |err: cpal::StreamError| {
+        tracing::error!(target: FFT_TARGET, error = %err, "WASAPI loopback stream error callback fired");
+    };
+
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => {
+            tracing::debug!(target: FFT_TARGET, "building F32 loopback stream");
+            device.build_input_stream(
+                config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let _ = sender.send(data.to_vec());
+                },
+                err_fn,
+                None,
+            )?
+        }
+        cpal::SampleFormat::I16 => {
+            tracing::debug!(target: FFT_TARGET, "building I16 loopback stream");
+            device.build_input_stream(
+                config,
+                move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                    let float: Vec<f32> =
+                        data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
+                    let _ = sender.send(float);
+                },
+                err_fn,
+                None,
+            )?
+        }
+        cpal::SampleFormat::U16 => {
+            tracing::debug!(target: FFT_TARGET, "building U16 loopback stream");
+            device.build_input_stream(
+                config,
+                move |data: &[u16], _: &cpal::InputCallbackInfo| {
+                    let float: Vec<f32> = data
+                        .iter()
+                        .map(|&s| (s as f32 / u16::MAX as f32) - 0.5)
+                        .collect();
+                    let _ = sender.send(float);
+                },
+                err_fn,
+                None,
+            )?
+        }
+        fmt => {
+            tracing::error!(target: FFT_TARGET, format = ?fmt, "unsupported sample format for WASAPI loopback");
+            return Err("Unsupported sample format for WASAPI loopback".into());
+        }
+    };
+
+    Ok(stream)
+}
+
+/* WAV file  */
+
+/// Plays a WAV file (looped) at its own sample rate and channel count.
+pub struct WavSource {
+    name: String,
+    sample_rate: f32,
+    channels: usize,
+    samples: Vec<f32>,
+    cursor: usize,
+    pacer: Pacer,
+}
+
+impl WavSource {
+    pub fn open(
+        path: &std::path::Path,
+        pacing: Pacing,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        tracing::debug!(target: FFT_TARGET, path = %path.display(), sample_rate = spec.sample_rate, channels = spec.channels, bits = spec.bits_per_sample, format = ?spec.sample_format, "opening WAV source");
+
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let channels = spec.channels as usize;
+        let mut samples = interleaved;
+        samples.truncate(samples.len() - samples.len() % channels.max(1));
+        if samples.is_empty() {
+            return Err(format!("WAV file {} contains no samples", path.display()).into());
+        }
+
+        Ok(Self {
+            name: path.display().to_string(),
+            sample_rate: spec.sample_rate as f32,
+            channels,
+            samples,
+            cursor: 0,
+            pacer: Pacer::new(pacing),
+        })
+    }
+}
+
+impl AudioSource for WavSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn read(&mut self, out: &mut Vec<f32>) {
+        let mut remaining = self.pacer.take(self.sample_rate) * self.channels;
+        out.reserve(remaining);
+        while remaining > 0 {
+            let end = (self.cursor + remaining).min(self.samples.len());
+            out.extend_from_slice(&self.samples[self.cursor..end]);
+            remaining -= end - self.cursor;
+            self.cursor = if end == self.samples.len() { 0 } else { end };
+        }
+    }
+}
+
+/* Synthetic signals  */
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Signal {
+    Sine {
+        freq: f32,
+        amplitude: f32,
+    },
+    /// Logarithmic sine sweep from `from` to `to` Hz, restarting every `period_secs`.
+    Sweep {
+        from: f32,
+        to: f32,
+        period_secs: f32,
+        amplitude: f32,
+    },
+    WhiteNoise {
+        amplitude: f32,
+    },
+}
+
+/// Generates a [`Signal`] on the fly.
+pub struct SyntheticSource {
+    name: String,
+    signal: Signal,
+    sample_rate: f32,
+    /// Samples generated so far; the signal is a pure function of this (plus the noise state).
+    position: u64,
+    /// Running phase in cycles, so sweeps stay continuous as their frequency changes.
+    phase: f64,
+    noise_state: u64,
+    pacer: Pacer,
+}
+
+impl SyntheticSource {
+    pub fn new(signal: Signal, sample_rate: f32, pacing: Pacing) -> Self {
+        Self {
+            name: format!("synthetic:{signal:?}"),
+            signal,
+            sample_rate,
+            position: 0,
+            phase: 0.0,
+            // Fixed seed: the same source always produces the same noise.
+            noise_state: 0x9E37_79B9_7F4A_7C15,
+            pacer: Pacer::new(pacing),
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let sr = self.sample_rate as f64;
+        let t = self.position as f64 / sr;
+        self.position += 1;
+        match self.signal {
+            Signal::Sine { freq, amplitude } => {
+                amplitude * (std::f64::consts::TAU * freq as f64 * t).sin() as f32
+            }
+            Signal::Sweep {
+                from,
+                to,
+                period_secs,
+                amplitude,
+            } => {
+                let progress = (t % period_secs as f64) / period_secs as f64;
+                let freq = from as f64 * (to as f64 / from as f64).powf(progress);
+                self.phase = (self.phase + freq / sr).fract();
+                amplitude * (std::f64::consts::TAU * self.phase).sin() as f32
+            }
+            Signal::WhiteNoise { amplitude } => {
+                // xorshift64 — plenty for noise, and keeps `rand` out of the dependency tree.
+                let mut x = self.noise_state;
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                self.noise_state = x;
+                let unit = (x >> 11) as f64 / (1_u64 << 53) as f64;
+                amplitude * (unit * 2.0 - 1.0) as f32
+            }
+        }
+    }
+}
+
+impl AudioSource for SyntheticSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        1
+    }
+
+    fn read(&mut self, out: &mut Vec<f32>) {
+        let n = self.pacer.take(self.sample_rate);
+        out.reserve(n);
+        for _ in 0..n {
+            let sample = self.next_sample();
+            out.push(sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::analysis::{SpectrumAnalyzer, VisualizerConfig, Weighting};
+    use crate::media::{FFTStream, FrequencyReading};
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const FFT_SIZE: usize = 4096;
+
+    fn sine(freq: f32, pacing: Pacing) -> SyntheticSource {
+        SyntheticSource::new(
+            Signal::Sine {
+                freq,
+                amplitude: 0.5,
+            },
+            SAMPLE_RATE,
+            pacing,
+        )
+    }
+
+    fn peak_bin(spectrum: &[f32]) -> usize {
+        spectrum
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(bin, _)| bin)
+            .unwrap()
+    }
+
+    fn bin_of(freq: f32) -> usize {
+        (freq * FFT_SIZE as f32 / SAMPLE_RATE).round() as usize
+    }
+
+    /// A stream whose every `update` is a fresh window of the sine.
+    fn sine_stream(freq: f32) -> FFTStream {
+        FFTStream::with_source(Box::new(sine(freq, Pacing::Fixed(FFT_SIZE))), FFT_SIZE)
+    }
+
+    fn bar_of(readings: &[FrequencyReading], freq: f32) -> usize {
+        readings
+            .iter()
+            .position(|r| r.freq_lo <= freq && freq < r.freq_hi)
+            .unwrap()
+    }
+
+    #[test]
+    fn sine_peaks_in_its_bin() {
+        for freq in [100.0, 440.0, 1_000.0, 8_000.0] {
+            let mut stream = sine_stream(freq);
+            assert!(stream.update());
+            assert_eq!(peak_bin(stream.spectrum()), bin_of(freq), "{freq} Hz");
+        }
+    }
+
+    #[test]
+    fn sine_lands_in_the_log_bar_that_spans_it() {
+        let config = VisualizerConfig {
+            weighting: Weighting::None,
+            attack_ms: 0.0,
+            ..VisualizerConfig::default()
+        };
+        for freq in [440.0, 1_000.0, 5_000.0, 12_000.0] {
+            let mut stream = sine_stream(freq);
+            let mut analyzer = SpectrumAnalyzer::new(&config, SAMPLE_RATE, FFT_SIZE);
+            assert!(stream.update());
+            let readings = analyzer.process(Some(stream.spectrum()));
+            assert_eq!(readings.len(), config.bins as usize);
+            let bar = bar_of(&readings, freq);
+            assert_eq!(readings[bar].magnitude, 1.0, "{freq} Hz");
+            for (i, reading) in readings.iter().enumerate() {
+                if i.abs_diff(bar) > 1 {
+                    assert!(reading.magnitude < 0.9, "{freq} Hz leaks into bar {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_step_rises_with_attack_and_falls_with_decay() {
+        let config = VisualizerConfig {
+            weighting: Weighting::None,
+            attack_ms: 100.0,
+            decay_ms: 500.0,
+            frame_rate: 30.0,
+            ..VisualizerConfig::default()
+        };
+        let mut stream = sine_stream(1_000.0);
+        let mut analyzer = SpectrumAnalyzer::new(&config, SAMPLE_RATE, FFT_SIZE);
+        let bar = bar_of(&analyzer.process(None), 1_000.0);
+        // One time constant is 3 frames rising and 15 falling; each closes 1 - 1/e of the gap.
+        let attack = (-1.0_f32 / 3.0).exp();
+        let decay = (-1.0_f32 / 15.0).exp();
+
+        for frame in 1..=30 {
+            assert!(stream.update());
+            let level = analyzer.process(Some(stream.spectrum()))[bar].magnitude;
+            let expected = 1.0 - attack.powi(frame);
+            assert!((level - expected).abs() < 1e-4, "frame {frame}: {level}");
+        }
+        let top = 1.0 - attack.powi(30);
+        let silence = vec![0.0; FFT_SIZE / 2 + 1];
+        for frame in 1..=30 {
+            let level = analyzer.process(Some(&silence))[bar].magnitude;
+            let expected = top * decay.powi(frame);
+            assert!((level - expected).abs() < 1e-4, "frame {frame}: {level}");
+        }
+        // Underruns hold the bars where they are.
+        let held = analyzer.process(None)[bar].magnitude;
+        assert!((held - top * decay.powi(30)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn underruns_until_a_window_is_buffered() {
+        let source = sine(440.0, Pacing::Fixed(FFT_SIZE / 2));
+        let mut stream = FFTStream::with_source(Box::new(source), FFT_SIZE);
+        assert!(!stream.update());
+        assert!(stream.update());
+        assert_eq!(stream.buffered(), FFT_SIZE);
+    }
+
+    #[test]
+    fn wav_peaks_in_the_same_bin() {
+        let file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: SAMPLE_RATE as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(file.path(), spec).unwrap();
+        let mut generator = sine(1_000.0, Pacing::Fixed(SAMPLE_RATE as usize / 10));
+        let mut samples = Vec::new();
+        generator.read(&mut samples);
+        for sample in samples {
+            let value = (sample * i16::MAX as f32) as i16;
+            writer.write_sample(value).unwrap();
+            writer.write_sample(value).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let source = WavSource::open(file.path(), Pacing::Fixed(FFT_SIZE)).unwrap();
+        assert_eq!(source.channels(), 2);
+        let mut stream = FFTStream::with_source(Box::new(source), FFT_SIZE);
+        assert!(stream.update());
+        assert_eq!(stream.incoming().len(), FFT_SIZE * 2);
+        assert_eq!(peak_bin(stream.spectrum()), bin_of(1_000.0));
+    }
+
+    #[test]
+    fn parses_source_specs() {
+        assert_eq!(SourceSpec::parse("loopback"), Ok(SourceSpec::Loopback));
+        assert_eq!(
+            SourceSpec::parse("sine:440"),
+            Ok(SourceSpec::Synthetic(Signal::Sine {
+                freq: 440.0,
+                amplitude: 0.5
+            }))
+        );
+        assert!(SourceSpec::parse("sine:-1").is_err());
+        assert!(SourceSpec::parse("sine:abc").is_err());
+        assert!(SourceSpec::parse("wav:").is_err());
+        assert!(SourceSpec::parse("pink").is_err());
+    }
+}
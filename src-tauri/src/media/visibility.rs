@@ -0,0 +1,47 @@
+//! Decides when the visualizer should stop capturing even though widgets are subscribed: nobody
+//! can see its output while the main window is hidden or minimised, or while the session is
+//! locked. The visualizer loops treat a pause like losing their last subscriber — the capture is
+//! dropped rather than left filling buffers nobody reads.
+
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+
+/// Window state and the lock check are cheap but not free (the latter is an IPC round trip on
+/// Linux), so re-evaluate a few times a second rather than every frame.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseReason {
+    Hidden,
+    Minimised,
+    SessionLocked,
+}
+
+#[derive(Default)]
+pub struct VisibilityWatch {
+    checked_at: Option<Instant>,
+    reason: Option<PauseReason>,
+}
+
+impl VisibilityWatch {
+    /// Why output should pause right now, if it should.
+    pub fn check(&mut self, app: &tauri::AppHandle) -> Option<PauseReason> {
+        if self
+            .checked_at
+            .is_some_and(|at| at.elapsed() < CHECK_INTERVAL)
+        {
+            return self.reason;
+        }
+        self.checked_at = Some(Instant::now());
+
+        let window = app.get_webview_window("main");
+        self.reason = match window {
+            Some(w) if !w.is_visible().unwrap_or(true) => Some(PauseReason::Hidden),
+            Some(w) if w.is_minimized().unwrap_or(false) => Some(PauseReason::Minimised),
+            _ if super::imp::session_locked() => Some(PauseReason::SessionLocked),
+            _ => None,
+        };
+        self.reason
+    }
+}
@@ -1,11 +1,11 @@
 use base64::Engine;
 use std::fmt;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, Ordering},
     Arc,
 };
 use std::time::Duration;
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 use tokio::sync::Mutex;
 use tracing::trace;
 use windows::Foundation::TypedEventHandler;
@@ -13,15 +13,13 @@ use windows::Media::Control::{
     GlobalSystemMediaTransportControlsSession, GlobalSystemMediaTransportControlsSessionManager,
     GlobalSystemMediaTransportControlsSessionPlaybackStatus as PlaybackStatus,
 };
+use windows::Media::MediaPlaybackAutoRepeatMode;
 
-use super::{FFTStream, FrequencyReading, MediaState};
-
-fn emit_media(app: &tauri::AppHandle, state: MediaState) {
-    if let Ok(value) = serde_json::to_value(&state) {
-        app.state::<crate::ChannelCache>().set(crate::events::StreamName::Media, value);
-    }
-    let _ = app.emit(crate::events::STREAM_MEDIA, state);
-}
+use super::{
+    emit_media, FFTStream, MediaCapabilities, MediaClient, MediaSession, MediaState,
+    PlaybackState, RepeatMode, SourceApp, SourceSpec, VisibilityWatch, VisualizerOutputs,
+};
+use crate::channel::{unix_ms, SubscriberGate};
 
 const TARGET: &str = "media::windows";
 
@@ -72,11 +70,31 @@ impl Drop for AttachedSession {
     }
 }
 
+// Revokes the manager-level handlers when the media loop ends — including when its task is
+// aborted by a channel reset — so a restarted loop doesn't leave the old handlers re-attaching
+// sessions alongside its own. Releasing the handlers also drops their `AttachedSession`.
+struct SessionChangedRegistration {
+    manager: GlobalSystemMediaTransportControlsSessionManager,
+    current_token: Option<i64>,
+    sessions_token: Option<i64>,
+}
+
+impl Drop for SessionChangedRegistration {
+    fn drop(&mut self) {
+        if let Some(token) = self.current_token {
+            let _ = self.manager.RemoveCurrentSessionChanged(token);
+        }
+        if let Some(token) = self.sessions_token {
+            let _ = self.manager.RemoveSessionsChanged(token);
+        }
+    }
+}
+
 /* Media event loop  */
 
 pub async fn run_media_loop(
     app: tauri::AppHandle,
-    subscribers: Arc<AtomicUsize>,
+    mut gate: SubscriberGate,
     poll_interval: Duration,
 ) {
     let session_manager = loop {
@@ -102,16 +120,14 @@ pub async fn run_media_loop(
     // Attach to whichever session is already active.
     attach_current_session(&session_manager, &attached, &cache, &fetch_state, &app, &handle).await;
 
-    // Re-attach whenever the foreground media app changes or all sessions close.
-    {
+    let reattach = {
         let attached = Arc::clone(&attached);
         let app_clone = app.clone();
         let cache_clone = Arc::clone(&cache);
         let fetch_state_clone = Arc::clone(&fetch_state);
         let handle_clone = handle.clone();
         let mgr_clone = session_manager.clone();
-        let handler = TypedEventHandler::new(move |_, _| {
-            tracing::info!(target: TARGET, "current session changed");
+        Arc::new(move || {
             let attached = Arc::clone(&attached);
             let app = app_clone.clone();
             let cache = Arc::clone(&cache_clone);
@@ -122,23 +138,55 @@ pub async fn run_media_loop(
             handle.spawn(async move {
                 attach_current_session(&mgr, &attached, &cache, &fetch_state, &app, &inner_handle).await;
             });
-            Ok(())
-        });
-        if let Err(err) = session_manager.CurrentSessionChanged(&handler) {
-            tracing::error!(target: TARGET, error = ?err, "CurrentSessionChanged registration failed");
+        })
+    };
+
+    // Re-attach whenever the foreground media app changes or all sessions close, and, while a
+    // session is pinned, whenever sessions come and go (the pinned one may be among them).
+    let _session_changed = {
+        let on_current = Arc::clone(&reattach);
+        let current = session_manager
+            .CurrentSessionChanged(&TypedEventHandler::new(move |_, _| {
+                tracing::info!(target: TARGET, "current session changed");
+                on_current();
+                Ok(())
+            }))
+            .map_err(|err| tracing::error!(target: TARGET, error = ?err, "CurrentSessionChanged registration failed"))
+            .ok();
+        let on_sessions = Arc::clone(&reattach);
+        let sessions = session_manager
+            .SessionsChanged(&TypedEventHandler::new(move |_, _| {
+                if super::pinned_session().is_some() {
+                    tracing::debug!(target: TARGET, "sessions changed while pinned");
+                    on_sessions();
+                }
+                Ok(())
+            }))
+            .map_err(|err| tracing::error!(target: TARGET, error = ?err, "SessionsChanged registration failed"))
+            .ok();
+        SessionChangedRegistration {
+            manager: session_manager.clone(),
+            current_token: current,
+            sessions_token: sessions,
         }
-    }
+    };
 
     // Keepalive poll — only updates timeline position during active playback.
     // Events cover play/pause/track changes; this catches continuous position drift.
     let mut interval = tokio::time::interval(poll_interval);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     loop {
-        interval.tick().await;
-        if subscribers.load(Ordering::Relaxed) == 0 {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = super::SESSION_PINNED.notified() => {
+                reattach();
+                continue;
+            }
+        }
+        if !gate.should_run() {
             continue;
         }
-        let Ok(session) = session_manager.GetCurrentSession() else {
+        let Ok(session) = followed_session(&session_manager) else {
             continue;
         };
         let is_playing = session
@@ -168,16 +216,10 @@ async fn attach_current_session(
     let mut guard = attached.lock().await;
     *guard = None; // revokes old event tokens via Drop
 
-    match mgr.GetCurrentSession() {
+    match followed_session(mgr) {
         Ok(session) => {
-            let source = session
-                .SourceAppUserModelId()
-                .map(|s| s.to_string())
-                .unwrap_or_default();
+            let source = session_id(&session);
             tracing::info!(target: TARGET, source = %source, "attaching to media session");
-            if source.to_ascii_lowercase().contains("applemusic") {
-                tracing::warn!(target: TARGET, "Apple Music session detected: artist\u{2014}album SMTC field will be split on em-dash");
-            }
             let mut cache_guard = cache.lock().await;
             let state = fetch_media_state_from_session(&session, &mut cache_guard, true).await;
             drop(cache_guard);
@@ -289,27 +331,13 @@ async fn fetch_media_state_from_session(
     };
 
     let title = props.Title().map(|s| s.to_string()).unwrap_or_default();
-    let raw_artist = props.Artist().map(|s| s.to_string()).unwrap_or_default();
-    let raw_album = props
+    // Apple Music's "Artist — Album" artists are split by the media rules, after this.
+    let artist = props.Artist().map(|s| s.to_string()).unwrap_or_default();
+    let album = props
         .AlbumTitle()
         .map(|s| s.to_string())
         .unwrap_or_default();
-
-    // Apple Music via SMTC reports "Artist — Album" in the Artist field with Album empty.
-    let source = session
-        .SourceAppUserModelId()
-        .map(|s| s.to_string())
-        .unwrap_or_default();
-    let is_apple_music = source.to_ascii_lowercase().contains("applemusic");
-    let (artist, album) = if is_apple_music && raw_album.is_empty() {
-        if let Some((a, b)) = raw_artist.split_once(" \u{2014} ") {
-            (a.to_string(), b.to_string())
-        } else {
-            (raw_artist, raw_album)
-        }
-    } else {
-        (raw_artist, raw_album)
-    };
+    let source_app = source_app(session);
 
     let title_changed = (title.as_str(), artist.as_str())
         != (
@@ -327,7 +355,7 @@ async fn fetch_media_state_from_session(
         cache.cached_art = read_thumbnail(&props).await;
     }
 
-    let (position_ms, duration_ms) = session
+    let (position_ms, duration_ms, position_updated_at) = session
         .GetTimelineProperties()
         .map(|t| {
             let pos = t
@@ -338,13 +366,20 @@ async fn fetch_media_state_from_session(
                 .EndTime()
                 .map(|ts| ts.Duration.max(0) / 10_000)
                 .unwrap_or(0) as u64;
-            (pos, end)
+            // SMTC timestamps the position itself; apps update it only now and then, so the
+            // time of this read would put it ahead of where it really is.
+            let at = t
+                .LastUpdatedTime()
+                .ok()
+                .and_then(|dt| filetime_to_unix_ms(dt.UniversalTime))
+                .unwrap_or_else(unix_ms);
+            (pos, end, at)
         })
-        .unwrap_or((0, 0));
+        .unwrap_or((0, 0, unix_ms()));
     
-    let playing = session
-        .GetPlaybackInfo()
-        .ok()
+    let info = session.GetPlaybackInfo().ok();
+    let playing = info
+        .as_ref()
         .and_then(|p| p.PlaybackStatus().ok())
         .map(|s| s == PlaybackStatus::Playing)
         .unwrap_or(false);
@@ -361,8 +396,84 @@ async fn fetch_media_state_from_session(
         artist,
         album,
         album_art_b64: cache.cached_art.clone(),
+        palette: None,
         position_ms,
+        position_updated_at,
+        playback_rate: info
+            .as_ref()
+            .and_then(|p| p.PlaybackRate().ok())
+            .and_then(|v| v.Value().ok())
+            .filter(|rate| *rate > 0.0)
+            .map_or(1.0, |rate| rate as f32),
         duration_ms,
+        url: None,
+        // SMTC leaves volume to the app's audio session.
+        volume: None,
+        shuffle: info
+            .as_ref()
+            .and_then(|p| p.IsShuffleActive().ok())
+            .and_then(|v| v.Value().ok()),
+        repeat_mode: info
+            .as_ref()
+            .and_then(|p| p.AutoRepeatMode().ok())
+            .and_then(|v| v.Value().ok())
+            .map(|mode| match mode {
+                MediaPlaybackAutoRepeatMode::Track => RepeatMode::Track,
+                MediaPlaybackAutoRepeatMode::List => RepeatMode::Playlist,
+                _ => RepeatMode::Off,
+            }),
+        capabilities: info
+            .and_then(|p| p.Controls().ok())
+            .map(|c| MediaCapabilities {
+                can_play: c.IsPlayEnabled().unwrap_or(false),
+                can_pause: c.IsPauseEnabled().unwrap_or(false),
+                can_go_next: c.IsNextEnabled().unwrap_or(false),
+                can_go_previous: c.IsPreviousEnabled().unwrap_or(false),
+                can_seek: c.IsPlaybackPositionEnabled().unwrap_or(false),
+                can_set_volume: false,
+                can_shuffle: c.IsShuffleEnabled().unwrap_or(false),
+                can_repeat: c.IsRepeatEnabled().unwrap_or(false),
+            })
+            .unwrap_or_default(),
+        source_app: Some(source_app),
+    }
+}
+
+/// `DateTime::UniversalTime` counts 100 ns ticks since 1601; `None` for the zero it's left at
+/// when unset.
+fn filetime_to_unix_ms(ticks: i64) -> Option<u64> {
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+    (ticks > UNIX_EPOCH_TICKS).then(|| ((ticks - UNIX_EPOCH_TICKS) / 10_000) as u64)
+}
+
+fn session_id(session: &GlobalSystemMediaTransportControlsSession) -> String {
+    session
+        .SourceAppUserModelId()
+        .map(|s| s.to_string())
+        .unwrap_or_default()
+}
+
+fn source_app(session: &GlobalSystemMediaTransportControlsSession) -> SourceApp {
+    let mut app = SourceApp::new(session_id(session), None);
+    if app.client == MediaClient::Other {
+        app.name = app_name_from_id(&app.id);
+    }
+    app
+}
+
+/// SMTC gives only the app user model id: `Spotify.exe` becomes `Spotify`,
+/// `Microsoft.ZuneMusic_8wekyb3d8bbwe!Microsoft.ZuneMusic` becomes `ZuneMusic`.
+fn app_name_from_id(id: &str) -> String {
+    let name = id.split('!').next().unwrap_or(id);
+    let name = name.rsplit('\\').next().unwrap_or(name);
+    let name = name
+        .strip_suffix(".exe")
+        .or_else(|| name.strip_suffix(".EXE"))
+        .unwrap_or(name);
+    let name = name.split('_').next().unwrap_or(name);
+    match name.rsplit('.').next() {
+        Some(last) if !last.is_empty() => last.to_string(),
+        _ => id.to_string(),
     }
 }
 
@@ -466,21 +577,150 @@ pub async fn prev_track() -> Result<(), String> {
     Ok(())
 }
 
+pub async fn seek_media(position_ms: u64) -> Result<(), String> {
+    let session = current_session().await?;
+    // 100 ns ticks.
+    let position = position_ms.saturating_mul(10_000).min(i64::MAX as u64) as i64;
+    session
+        .TryChangePlaybackPositionAsync(position)
+        .map_err(e)?
+        .await
+        .map_err(e)?;
+    Ok(())
+}
+
+pub async fn set_volume(_volume: f32) -> Result<(), String> {
+    Err("Windows media sessions don't expose a volume".to_string())
+}
+
+pub async fn set_shuffle(shuffle: bool) -> Result<(), String> {
+    let session = current_session().await?;
+    session
+        .TryChangeShuffleActiveAsync(shuffle)
+        .map_err(e)?
+        .await
+        .map_err(e)?;
+    Ok(())
+}
+
+pub async fn set_repeat(mode: RepeatMode) -> Result<(), String> {
+    let session = current_session().await?;
+    let mode = match mode {
+        RepeatMode::Off => MediaPlaybackAutoRepeatMode::None,
+        RepeatMode::Track => MediaPlaybackAutoRepeatMode::Track,
+        RepeatMode::Playlist => MediaPlaybackAutoRepeatMode::List,
+    };
+    session
+        .TryChangeAutoRepeatModeAsync(mode)
+        .map_err(e)?
+        .await
+        .map_err(e)?;
+    Ok(())
+}
+
 async fn current_session(
 ) -> Result<windows::Media::Control::GlobalSystemMediaTransportControlsSession, String> {
-    get_session_manager().await?.GetCurrentSession().map_err(e)
+    followed_session(&get_session_manager().await?).map_err(e)
+}
+
+/* Sessions  */
+
+fn find_session(
+    mgr: &GlobalSystemMediaTransportControlsSessionManager,
+    id: &str,
+) -> Option<GlobalSystemMediaTransportControlsSession> {
+    mgr.GetSessions()
+        .ok()?
+        .into_iter()
+        .find(|session| session_id(session) == id)
+}
+
+/// The pinned session while it exists, otherwise whichever one Windows calls current.
+fn followed_session(
+    mgr: &GlobalSystemMediaTransportControlsSessionManager,
+) -> windows::core::Result<GlobalSystemMediaTransportControlsSession> {
+    match super::pinned_session().and_then(|id| find_session(mgr, &id)) {
+        Some(session) => Ok(session),
+        None => mgr.GetCurrentSession(),
+    }
+}
+
+pub async fn list_media_sessions() -> Result<Vec<MediaSession>, String> {
+    let mgr = get_session_manager().await?;
+    let followed = followed_session(&mgr).ok().map(|s| session_id(&s));
+    let pinned = super::pinned_session();
+    let mut sessions = Vec::new();
+    for session in mgr.GetSessions().map_err(e)? {
+        let status = session.GetPlaybackInfo().and_then(|p| p.PlaybackStatus());
+        let state = match status {
+            Ok(s) if s == PlaybackStatus::Playing => PlaybackState::Playing,
+            Ok(s) if s == PlaybackStatus::Paused => PlaybackState::Paused,
+            _ => PlaybackState::Stopped,
+        };
+        let title = match session.TryGetMediaPropertiesAsync() {
+            Ok(props) => props
+                .await
+                .and_then(|p| p.Title())
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+            Err(_) => String::new(),
+        };
+        let source_app = source_app(&session);
+        sessions.push(MediaSession {
+            id: source_app.id.clone(),
+            followed: followed.as_deref() == Some(source_app.id.as_str()),
+            pinned: pinned.as_deref() == Some(source_app.id.as_str()),
+            source_app,
+            state,
+            title,
+        });
+    }
+    Ok(sessions)
+}
+
+pub async fn select_media_session(id: Option<String>) -> Result<(), String> {
+    if let Some(id) = &id {
+        if find_session(&get_session_manager().await?, id).is_none() {
+            return Err(format!("no media session `{id}`"));
+        }
+    }
+    super::pin_session(id);
+    Ok(())
+}
+
+/* Session lock  */
+
+/// Whether the workstation is locked. While it is, the input desktop is Winlogon's secure
+/// desktop, which an ordinary process can't open. (A UAC prompt switches to the same desktop,
+/// so the visualizer also pauses for the prompt's duration.)
+pub fn session_locked() -> bool {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP,
+    };
+
+    match unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP) } {
+        Ok(desktop) => {
+            let _ = unsafe { CloseDesktop(desktop) };
+            false
+        }
+        Err(_) => true,
+    }
 }
 
 /* Visualizer loop  */
 
 /// Spawns a dedicated thread that owns the FFT stream. Manages its own
 /// FFTStream lifecycle — creates on first subscriber, drops when empty,
-/// and re-creates automatically if the default output device changes.
+/// and re-creates automatically if the default output device changes
+/// (loopback sources only; file and synthetic sources never need to).
+/// Returns once `stop` is set.
 pub fn spawn_visualizer_loop(
     app: tauri::AppHandle,
-    subscribers: Arc<AtomicUsize>,
+    mut gate: SubscriberGate,
     frame_interval: Duration,
-) {
+    source: SourceSpec,
+    stop: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
     use cpal::traits::{DeviceTrait, HostTrait};
 
     const VIS_TARGET: &str = "media::visualizer";
@@ -523,33 +763,39 @@ pub fn spawn_visualizer_loop(
             let run = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 let frame = frame_interval;
                 let mut stream: Option<FFTStream> = None;
-                let mut last_sub_nonzero = false;
-
-                loop {
-                    let tick = std::time::Instant::now();
-                    let sub_count = subscribers.load(Ordering::Relaxed);
-
-                    if sub_count == 0 {
-                        if last_sub_nonzero {
-                            tracing::info!(target: VIS_TARGET, "no subscribers — dropping FFTStream and pausing");
+                let mut outputs = VisualizerOutputs::new(frame);
+                let mut visibility = VisibilityWatch::default();
+
+                while !stop.load(Ordering::Relaxed) {
+                    let sub_count = gate.subscribers();
+                    let paused = if sub_count == 0 { None } else { visibility.check(&app) };
+
+                    if !gate.should_run_if(paused.is_none()) {
+                        if stream.is_some() {
+                            match paused {
+                                Some(reason) => tracing::info!(target: VIS_TARGET, ?reason, "output not visible — dropping FFTStream"),
+                                None => tracing::info!(target: VIS_TARGET, "no subscribers — dropping FFTStream"),
+                            }
                             stream = None;
-                            last_sub_nonzero = false;
+                            // Frames parked for a consumer that has gone away would be stale on resume.
+                            app.state::<crate::events::FrameGate>().clear();
                         }
                         std::thread::sleep(frame);
                         continue;
                     }
-                    if !last_sub_nonzero {
-                        tracing::info!(target: VIS_TARGET, sub_count, "subscriber(s) active — initialising FFTStream");
-                        last_sub_nonzero = true;
-                    }
 
                     // Check whether the default output device has changed.
-                    let current_device = cpal::default_host()
-                        .default_output_device()
-                        .and_then(|d| d.name().ok());
+                    let current_device = if source.is_loopback() {
+                        cpal::default_host()
+                            .default_output_device()
+                            .and_then(|d| d.name().ok())
+                    } else {
+                        None
+                    };
 
                     let needs_reinit = match (&stream, &current_device) {
                         (None, _) => true,
+                        (Some(_), _) if !source.is_loopback() => false,
                         (Some(s), Some(name)) => &s.device_name != name,
                         (Some(_), None) => true,
                     };
@@ -561,7 +807,7 @@ pub fn spawn_visualizer_loop(
                             tracing::info!(target: VIS_TARGET, device = ?current_device, "creating FFTStream");
                         }
                         stream = None;
-                        match FFTStream::new(4096) {
+                        match source.open().map(|src| FFTStream::with_source(src, 4096)) {
                             Ok(s) => {
                                 tracing::info!(target: VIS_TARGET, device = %s.device_name, sample_rate = s.sample_rate, channels = s.channels, fft_size = s.fft_size, "FFTStream created");
                                 stream = Some(s);
@@ -574,20 +820,23 @@ pub fn spawn_visualizer_loop(
                         }
                     }
 
-                    if let Some(ref mut s) = stream {
-                        let buf_len = s.audio_buffer.len();
-                        if buf_len > s.fft_size * 8 {
-                            tracing::warn!(target: VIS_TARGET, buf_len, fft_size = s.fft_size, "audio_buffer unexpectedly large");
+                    // Emit whichever analyzers are due, then sleep until the next one is. While
+                    // lingering there is nobody to emit to; just keep the capture drained.
+                    let wait = match stream {
+                        Some(ref mut s) if sub_count == 0 => {
+                            s.update();
+                            frame
                         }
-                        let data: Vec<FrequencyReading> = s.get();
-                        let _ = app.emit(crate::events::STREAM_VISUALIZER, data);
-                    }
-
-                    // Sleep for the remainder of the frame budget.
-                    let elapsed = tick.elapsed();
-                    if elapsed < frame {
-                        std::thread::sleep(frame - elapsed);
-                    }
+                        Some(ref mut s) => {
+                            let buf_len = s.buffered();
+                            if buf_len > s.fft_size * 8 {
+                                tracing::warn!(target: VIS_TARGET, buf_len, fft_size = s.fft_size, "audio buffer unexpectedly large");
+                            }
+                            outputs.tick(&app, s)
+                        }
+                        None => frame,
+                    };
+                    std::thread::sleep(wait);
                 }
             }));
             if let Err(panic_val) = run {
@@ -597,7 +846,9 @@ pub fn spawn_visualizer_loop(
                     .or_else(|| panic_val.downcast_ref::<String>().map(|s| s.as_str()))
                     .unwrap_or("<non-string panic payload>");
                 tracing::error!(target: VIS_TARGET, panic = msg, "visualizer thread panicked — stream will be unavailable until restart");
+            } else {
+                tracing::info!(target: VIS_TARGET, "visualizer thread stopped");
             }
         })
-        .expect("failed to spawn visualizer thread");
+        .expect("failed to spawn visualizer thread")
 }
@@ -0,0 +1,449 @@
+//! Long-term metric recording, opt-in with `--record-metrics`. The same `(metric, value)` pairs
+//! that feed each channel's short in-memory history (`Channel::metrics`) are bucketed and written
+//! under `app_data_dir()/metrics` in three tiers — 1 s buckets for an hour, 1 min for a day and
+//! 15 min for a month — each rolled up from the closed buckets of the tier below, within a size
+//! cap. `query_metrics` returns min/avg/max per bucket over any range, for "network usage this
+//! week"-style widgets.
+//!
+//! While recording is on, the recorder holds an internal 1 s subscription to each recorded
+//! stream, so their producers keep running with no widget on screen. Internal subscriptions
+//! aren't counted in `get_stream_stats`.
+
+mod store;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use ts_rs::TS;
+
+use crate::channel::{unix_ms, Channels};
+use crate::config::app_data_dir;
+use crate::events::StreamName;
+use store::{floor, Agg, Bucket, Store, TIERS};
+
+const TARGET: &str = "recording";
+
+/// The resource streams recorded. Others publish no metrics or nothing worth keeping.
+const RECORDED: [StreamName; 4] = [
+    StreamName::Cpu,
+    StreamName::Memory,
+    StreamName::Networks,
+    StreamName::DiskIo,
+];
+
+/// Finest tier's resolution; faster producers are averaged into it.
+const RECORD_INTERVAL: Duration = Duration::from_secs(1);
+
+type OpenBuckets = [Option<Bucket>; TIERS.len()];
+
+/// A closed bucket and the tier it goes to.
+type Closed = (usize, Bucket);
+
+/// Managed only when recording is on.
+pub struct Recorder {
+    store: Store,
+    /// Per channel, the bucket still filling in each tier.
+    open: Mutex<HashMap<StreamName, OpenBuckets>>,
+    /// To the writer thread, which does the appending (and the store's upkeep that comes with
+    /// it) so the publish path never waits on the disk.
+    writer: mpsc::Sender<Closed>,
+}
+
+impl Recorder {
+    pub fn new(root: PathBuf, max_bytes: u64) -> Self {
+        let store = Store::new(root, max_bytes);
+        let now = unix_ms();
+        let open = RECORDED
+            .into_iter()
+            .map(|name| (name, restore(&store, name, now)))
+            .collect();
+        let (writer, closed) = mpsc::channel::<Closed>();
+        let writer_store = store.clone();
+        let spawned = std::thread::Builder::new()
+            .name("metrics-writer".into())
+            .spawn(move || {
+                for (tier, bucket) in closed {
+                    write(&writer_store, tier, &bucket);
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::error!(target: TARGET, "failed to start the writer thread, recording nothing: {e}");
+        }
+        Self {
+            store,
+            open: Mutex::new(open),
+            writer,
+        }
+    }
+
+    /// Adds one published sample of a recorded stream. Called from `Channels`' publish path.
+    pub fn record(&self, name: StreamName, at: u64, metrics: &[(String, f32)]) {
+        if metrics.is_empty() || !RECORDED.contains(&name) {
+            return;
+        }
+        let mut closed = Vec::new();
+        if let Ok(mut open) = self.open.lock() {
+            let open = open.entry(name).or_default();
+            add(open, name, at, metrics, &mut closed);
+        }
+        for closed in closed {
+            // Fails only if the writer thread never started, which was logged then.
+            self.writer.send(closed).ok();
+        }
+    }
+
+    /// Reads from the coarsest tier that's still at least as fine as `bucket_ms`, moving coarser
+    /// while the tier doesn't reach back to `from`. Only closed buckets are on disk, so the
+    /// newest bucket of a coarse tier lags by up to its width.
+    fn query(
+        &self,
+        channel: StreamName,
+        from: u64,
+        to: u64,
+        bucket_ms: Option<u64>,
+        metrics: Option<&[String]>,
+    ) -> Vec<MetricBuckets> {
+        let wanted = bucket_ms.unwrap_or(0);
+        let oldest = |tier: usize| unix_ms().saturating_sub(TIERS[tier].retention_ms);
+        let mut tier = TIERS
+            .iter()
+            .rposition(|t| t.resolution_ms <= wanted)
+            .unwrap_or(0);
+        while tier + 1 < TIERS.len() && oldest(tier) > from {
+            tier += 1;
+        }
+        let width = wanted.max(TIERS[tier].resolution_ms);
+
+        let mut rolled: BTreeMap<String, BTreeMap<u64, Agg>> = BTreeMap::new();
+        for bucket in self.store.read(tier, channel, from, to) {
+            for (metric, agg) in bucket.m {
+                if metrics.is_some_and(|wanted| !wanted.contains(&metric)) {
+                    continue;
+                }
+                rolled
+                    .entry(metric)
+                    .or_default()
+                    .entry(floor(bucket.t, width))
+                    .and_modify(|a| a.merge(agg))
+                    .or_insert(agg);
+            }
+        }
+        rolled
+            .into_iter()
+            .map(|(metric, buckets)| MetricBuckets {
+                metric,
+                bucket_ms: width,
+                timestamps: buckets.keys().copied().collect(),
+                min: buckets.values().map(|a| a.0).collect(),
+                avg: buckets.values().map(|a| a.1).collect(),
+                max: buckets.values().map(|a| a.2).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Adds a sample of `name` at `at` to its open 1 s bucket. A sample past that bucket closes it,
+/// cascading into the coarser tiers; every bucket closed on the way is pushed to `closed`.
+fn add(
+    open: &mut OpenBuckets,
+    name: StreamName,
+    at: u64,
+    metrics: &[(String, f32)],
+    closed: &mut Vec<Closed>,
+) {
+    let start = floor(at, TIERS[0].resolution_ms);
+    if open[0].as_ref().is_some_and(|b| b.t != start) {
+        if let Some(finished) = open[0].take() {
+            close(open, 0, finished, closed);
+        }
+    }
+    let bucket = open[0].get_or_insert_with(|| Bucket::new(start, name));
+    for (metric, value) in metrics {
+        bucket.add(metric, Agg::sample(*value));
+    }
+}
+
+/// Pushes a finished tier-`tier` bucket to `closed` and folds it into the tier above, closing
+/// that one first if `finished` belongs to its next span.
+fn close(open: &mut OpenBuckets, tier: usize, finished: Bucket, closed: &mut Vec<Closed>) {
+    let up = tier + 1;
+    if up < TIERS.len() {
+        let start = floor(finished.t, TIERS[up].resolution_ms);
+        if open[up].as_ref().is_some_and(|b| b.t != start) {
+            if let Some(done) = open[up].take() {
+                close(open, up, done, closed);
+            }
+        }
+        let bucket = open[up].get_or_insert_with(|| Bucket::new(start, finished.c));
+        for (metric, agg) in &finished.m {
+            bucket.add(metric, *agg);
+        }
+    }
+    closed.push((tier, finished));
+}
+
+fn write(store: &Store, tier: usize, bucket: &Bucket) {
+    if let Err(e) = store.append(tier, bucket) {
+        tracing::warn!(target: TARGET, tier = TIERS[tier].name, channel = %bucket.c, "failed to write bucket: {e}");
+    }
+}
+
+/// `channel`'s open buckets as they'd stand had the app never stopped. Only closed buckets reach
+/// disk, so each coarser tier's open bucket is rolled up again from the finer tier's buckets
+/// written since that tier's last one; any it should have closed while the app was off — a
+/// minute that ended after the last run's final second — is written on the way. The open 1 s
+/// bucket is lost with the process.
+fn restore(store: &Store, channel: StreamName, now: u64) -> OpenBuckets {
+    let mut open = OpenBuckets::default();
+    for up in 1..TIERS.len() {
+        let width = TIERS[up].resolution_ms;
+        // Nothing older than the finer tier's retention is left to roll up.
+        let since = now.saturating_sub(TIERS[up - 1].retention_ms);
+        let resume = store
+            .read(up, channel, since, now)
+            .last()
+            .map_or(since, |b| b.t + width);
+        // Tier by tier, finest first: what this writes is read back for the tier above.
+        for finer in store.read(up - 1, channel, resume, now) {
+            let start = floor(finer.t, width);
+            if open[up].as_ref().is_some_and(|b| b.t != start) {
+                if let Some(finished) = open[up].take() {
+                    write(store, up, &finished);
+                }
+            }
+            let bucket = open[up].get_or_insert_with(|| Bucket::new(start, channel));
+            for (metric, agg) in finer.m {
+                bucket.add(&metric, agg);
+            }
+        }
+        if open[up].as_ref().is_some_and(|b| b.t != floor(now, width)) {
+            if let Some(finished) = open[up].take() {
+                write(store, up, &finished);
+            }
+        }
+        if let Some(bucket) = &open[up] {
+            tracing::debug!(target: TARGET, tier = TIERS[up].name, %channel, t = bucket.t, "restored open bucket");
+        }
+    }
+    open
+}
+
+/// Starts recording into `app_data_dir()/metrics`, capped at `max_bytes`. Called from `setup`
+/// after the channels are managed.
+pub fn start(app: &tauri::App, max_bytes: u64) {
+    let Some(root) = app_data_dir().map(|dir| dir.join("metrics")) else {
+        tracing::warn!(target: TARGET, "cannot determine data directory, not recording metrics");
+        return;
+    };
+    tracing::info!(target: TARGET, root = %root.display(), max_bytes, "recording metrics");
+    app.manage(Recorder::new(root, max_bytes));
+    let channels = app.state::<Channels>();
+    for name in RECORDED {
+        channels.subscribe_internal(name, Some(RECORD_INTERVAL));
+    }
+}
+
+/// One metric's recorded buckets, as returned by `query_metrics`. Columnar like
+/// `MetricSeries`: `timestamps[i]` is the start (Unix ms) of the bucket `min[i]`, `avg[i]` and
+/// `max[i]` describe. Buckets with no samples are left out.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct MetricBuckets {
+    pub metric: String,
+    /// Width of every bucket — the requested one, or the tier's resolution if that's coarser.
+    pub bucket_ms: u64,
+    pub timestamps: Vec<u64>,
+    pub min: Vec<f32>,
+    pub avg: Vec<f32>,
+    pub max: Vec<f32>,
+}
+
+/// Recorded buckets of `channel`'s metrics (all of them, or just `metrics`) between `from` and
+/// `to` (Unix ms). Fails if the app wasn't started with `--record-metrics`.
+#[tauri::command]
+pub async fn query_metrics(
+    channel: StreamName,
+    from: u64,
+    to: u64,
+    bucket_ms: Option<u64>,
+    metrics: Option<Vec<String>>,
+    app: tauri::AppHandle,
+) -> Result<Vec<MetricBuckets>, String> {
+    tracing::debug!(target: TARGET, %channel, from, to, ?bucket_ms, "invoke: query_metrics");
+    if app.try_state::<Recorder>().is_none() {
+        return Err("metric recording is off (start with --record-metrics)".into());
+    }
+    // Reads and parses whole segment files.
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<Recorder>()
+            .query(channel, from, to, bucket_ms, metrics.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+    /// A quarter-hour boundary.
+    const T0: u64 = 1_760_659_200_000;
+
+    fn second(store: &Store, t: u64, value: f32) {
+        let mut bucket = Bucket::new(t, StreamName::Cpu);
+        bucket.add("global_usage", Agg::sample(value));
+        store.append(0, &bucket).unwrap();
+    }
+
+    #[test]
+    fn restores_open_buckets_from_the_finer_tier() {
+        let root = tempfile::tempdir().unwrap();
+        let store = Store::new(root.path().to_path_buf(), u64::MAX);
+        // The last run recorded the end of one minute and the start of the next, then quit
+        // before either reached the 1 min tier.
+        second(&store, T0 + 58_000, 10.0);
+        second(&store, T0 + 59_000, 20.0);
+        second(&store, T0 + MINUTE, 30.0);
+        second(&store, T0 + MINUTE + 1_000, 50.0);
+
+        let open = restore(&store, StreamName::Cpu, T0 + MINUTE + 30_000);
+
+        let minutes = store.read(1, StreamName::Cpu, T0, T0 + 15 * MINUTE);
+        assert_eq!(minutes.len(), 1, "the finished minute is written");
+        let finished = minutes[0].m["global_usage"];
+        assert_eq!((minutes[0].t, finished.1, finished.3), (T0, 15.0, 2));
+
+        let minute = open[1].as_ref().unwrap();
+        let usage = minute.m["global_usage"];
+        assert_eq!(minute.t, T0 + MINUTE);
+        assert_eq!((usage.0, usage.1, usage.2, usage.3), (30.0, 40.0, 50.0, 2));
+
+        let quarter = open[2].as_ref().unwrap();
+        assert_eq!(quarter.t, T0);
+        assert_eq!(
+            quarter.m["global_usage"].3, 2,
+            "only the written minute is rolled up"
+        );
+        assert!(open[0].is_none());
+
+        // A second start in the same minute rolls up nothing twice.
+        let again = restore(&store, StreamName::Cpu, T0 + MINUTE + 40_000);
+        assert_eq!(
+            store.read(1, StreamName::Cpu, T0, T0 + 15 * MINUTE).len(),
+            1
+        );
+        assert_eq!(again[1].as_ref().unwrap().m["global_usage"].3, 2);
+        assert_eq!(again[2].as_ref().unwrap().m["global_usage"].3, 2);
+    }
+
+    #[test]
+    fn closed_buckets_cascade_into_the_coarser_tiers() {
+        let mut open = OpenBuckets::default();
+        let mut closed = Vec::new();
+        // One sample a second, from T0 until a second into minute 16.
+        for s in 0..=16 * 60 + 1 {
+            let metrics = [("global_usage".to_string(), s as f32)];
+            add(
+                &mut open,
+                StreamName::Cpu,
+                T0 + s * 1_000 + 250,
+                &metrics,
+                &mut closed,
+            );
+        }
+        let tier = |tier: usize| -> Vec<&Bucket> {
+            closed
+                .iter()
+                .filter(|(t, _)| *t == tier)
+                .map(|(_, b)| b)
+                .collect()
+        };
+
+        assert_eq!(tier(0).len(), 16 * 60 + 1);
+        assert_eq!(tier(0)[0].t, T0);
+        let minutes = tier(1);
+        assert_eq!(minutes.len(), 16);
+        let m14 = minutes[14].m["global_usage"];
+        assert_eq!(minutes[14].t, T0 + 14 * MINUTE);
+        assert_eq!((m14.0, m14.1, m14.2, m14.3), (840.0, 869.5, 899.0, 60));
+        let quarters = tier(2);
+        assert_eq!(quarters.len(), 1);
+        let q = quarters[0].m["global_usage"];
+        assert_eq!((quarters[0].t, q.0, q.2, q.3), (T0, 0.0, 899.0, 900));
+        assert!((q.1 - 449.5).abs() < 1e-3);
+
+        // Still filling: the last second, minute 16 and the second quarter-hour.
+        assert_eq!(open[0].as_ref().unwrap().t, T0 + 16 * MINUTE + 1_000);
+        assert_eq!(open[1].as_ref().unwrap().t, T0 + 16 * MINUTE);
+        let quarter = open[2].as_ref().unwrap();
+        assert_eq!(
+            (quarter.t, quarter.m["global_usage"].3),
+            (T0 + 15 * MINUTE, 60)
+        );
+    }
+
+    #[test]
+    fn queries_the_finest_tier_that_reaches_back_and_rebuckets_it() {
+        let root = tempfile::tempdir().unwrap();
+        let recorder = Recorder::new(root.path().to_path_buf(), u64::MAX);
+        let store = &recorder.store;
+        let now = unix_ms();
+        let append = |tier: usize, t: u64, value: f32| {
+            let mut bucket = Bucket::new(t, StreamName::Cpu);
+            bucket.add("global_usage", Agg::sample(value));
+            store.append(tier, &bucket).unwrap();
+        };
+        // Two minutes of seconds counting 0-9, three hours of minutes and three days of
+        // quarter-hours, each tier with a value of its own.
+        let seconds = floor(now, 10_000) - 2 * MINUTE;
+        for i in 0..120 {
+            append(0, seconds + i * 1_000, (i % 10) as f32);
+        }
+        for i in 0..180 {
+            append(1, floor(now, MINUTE) - (i + 1) * MINUTE, 5.0);
+        }
+        for i in 0..288 {
+            append(2, floor(now, 15 * MINUTE) - (i + 1) * 15 * MINUTE, 7.0);
+        }
+        let mut memory = Bucket::new(seconds, StreamName::Memory);
+        memory.add("used", Agg::sample(1.0));
+        store.append(0, &memory).unwrap();
+
+        let query = |from: u64, bucket_ms: Option<u64>| {
+            let mut result = recorder.query(StreamName::Cpu, from, now, bucket_ms, None);
+            assert_eq!(result.len(), 1, "only the queried channel's metric");
+            result.remove(0)
+        };
+
+        let raw = query(seconds, None);
+        assert_eq!((raw.bucket_ms, raw.timestamps.len()), (1_000, 120));
+        assert_eq!(raw.avg[..3], [0.0, 1.0, 2.0]);
+
+        let tens = query(seconds, Some(10_000));
+        assert_eq!((tens.bucket_ms, tens.timestamps.len()), (10_000, 12));
+        assert_eq!(tens.timestamps[1], seconds + 10_000);
+        assert!(tens.min.iter().all(|&v| v == 0.0));
+        assert!(tens.avg.iter().all(|&v| v == 4.5));
+        assert!(tens.max.iter().all(|&v| v == 9.0));
+
+        // A wider bucket reads the coarser tier it fits.
+        let minutes = query(seconds, Some(MINUTE));
+        assert_eq!(minutes.bucket_ms, MINUTE);
+        assert!(minutes.avg.iter().all(|&v| v == 5.0));
+
+        // Further back than the hour of seconds reaches, the minutes are read instead.
+        let hours = query(now - 2 * 60 * MINUTE, None);
+        assert_eq!((hours.bucket_ms, hours.avg[0]), (MINUTE, 5.0));
+        let days = query(now - 3 * 24 * 60 * MINUTE, None);
+        assert_eq!((days.bucket_ms, days.avg[0]), (15 * MINUTE, 7.0));
+        let hourly = query(now - 24 * 60 * MINUTE, Some(60 * MINUTE));
+        assert_eq!((hourly.bucket_ms, hourly.avg[0]), (60 * MINUTE, 7.0));
+
+        let other = ["user_usage".to_string()];
+        let filtered = recorder.query(StreamName::Cpu, seconds, now, None, Some(&other));
+        assert!(filtered.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+//! On-disk layout of the recording: one directory per tier, each holding JSON-lines segment
+//! files named after the Unix ms their span starts at (`1m/1760659200000.jsonl`). A line is one
+//! closed bucket of one channel. Retention and the size cap only ever delete whole segments, so
+//! nothing is rewritten in place.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::events::StreamName;
+
+use super::TARGET;
+
+const SECOND: u64 = 1_000;
+const MINUTE: u64 = 60 * SECOND;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+pub struct Tier {
+    /// Directory name.
+    pub name: &'static str,
+    /// Bucket width.
+    pub resolution_ms: u64,
+    /// How far back the tier reaches before its oldest segments are deleted.
+    pub retention_ms: u64,
+    /// Span of one segment file.
+    segment_ms: u64,
+}
+
+/// Finest first; each tier's buckets are rolled up from the closed buckets of the one before.
+pub const TIERS: [Tier; 3] = [
+    Tier {
+        name: "1s",
+        resolution_ms: SECOND,
+        retention_ms: HOUR,
+        segment_ms: 5 * MINUTE,
+    },
+    Tier {
+        name: "1m",
+        resolution_ms: MINUTE,
+        retention_ms: DAY,
+        segment_ms: HOUR,
+    },
+    Tier {
+        name: "15m",
+        resolution_ms: 15 * MINUTE,
+        retention_ms: 30 * DAY,
+        segment_ms: DAY,
+    },
+];
+
+/// `min, mean, max, count` of the samples in a bucket — a JSON array on disk to keep lines short.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Agg(pub f32, pub f32, pub f32, pub u32);
+
+impl Agg {
+    pub fn sample(value: f32) -> Self {
+        Self(value, value, value, 1)
+    }
+
+    /// Folds `other` in; the mean is weighted by sample count, so rolling up buckets of uneven
+    /// fill (a producer that started mid-minute) doesn't skew it.
+    pub fn merge(&mut self, other: Agg) {
+        let count = self.3 + other.3;
+        if count == 0 {
+            return;
+        }
+        self.1 = (self.1 * self.3 as f32 + other.1 * other.3 as f32) / count as f32;
+        self.0 = self.0.min(other.0);
+        self.2 = self.2.max(other.2);
+        self.3 = count;
+    }
+}
+
+/// One channel's metrics over `[t, t + resolution)` of some tier.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bucket {
+    /// Start, Unix ms.
+    pub t: u64,
+    pub c: StreamName,
+    pub m: BTreeMap<String, Agg>,
+}
+
+impl Bucket {
+    pub fn new(t: u64, c: StreamName) -> Self {
+        Self {
+            t,
+            c,
+            m: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, metric: &str, agg: Agg) {
+        match self.m.get_mut(metric) {
+            Some(existing) => existing.merge(agg),
+            None => {
+                self.m.insert(metric.to_string(), agg);
+            }
+        }
+    }
+}
+
+/// Start of the `width`-wide span `t` falls in.
+pub fn floor(t: u64, width: u64) -> u64 {
+    t - t % width
+}
+
+#[derive(Clone)]
+pub struct Store {
+    root: PathBuf,
+    max_bytes: u64,
+}
+
+impl Store {
+    pub fn new(root: PathBuf, max_bytes: u64) -> Self {
+        Self { root, max_bytes }
+    }
+
+    /// Appends a closed bucket to tier `tier`. Starting a new finest-tier segment (every five
+    /// minutes) is also when expired segments are deleted and the size cap is enforced.
+    pub fn append(&self, tier: usize, bucket: &Bucket) -> io::Result<()> {
+        let dir = self.root.join(TIERS[tier].name);
+        let path = dir.join(format!("{}.jsonl", floor(bucket.t, TIERS[tier].segment_ms)));
+        let new_segment = !path.exists();
+        if new_segment {
+            fs::create_dir_all(&dir)?;
+        }
+        let mut line = serde_json::to_vec(bucket).map_err(io::Error::other)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(&line)?;
+        if new_segment && tier == 0 {
+            self.maintain(bucket.t);
+        }
+        Ok(())
+    }
+
+    /// `channel`'s buckets of tier `tier` starting in `[from, to)`, oldest first. Lines that
+    /// don't parse (a write cut short by a crash) are skipped.
+    pub fn read(&self, tier: usize, channel: StreamName, from: u64, to: u64) -> Vec<Bucket> {
+        let span = TIERS[tier].segment_ms;
+        let mut buckets = Vec::new();
+        for segment in segments(&self.root, tier) {
+            if segment.start >= to || segment.start + span <= from {
+                continue;
+            }
+            let Ok(file) = fs::File::open(&segment.path) else {
+                continue;
+            };
+            buckets.extend(
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str::<Bucket>(&line).ok())
+                    .filter(|b| b.c == channel && b.t >= from && b.t < to),
+            );
+        }
+        buckets
+    }
+
+    /// Deletes segments that ended more than their tier's retention before `now`, then — if the
+    /// recording is still over the cap — the oldest remaining segments, finest tier first: a
+    /// month of 15-minute buckets is worth more than the last hour at 1 s.
+    fn maintain(&self, now: u64) {
+        let mut tiers: Vec<Vec<Segment>> = (0..TIERS.len())
+            .map(|tier| segments(&self.root, tier))
+            .collect();
+        for (tier, segments) in tiers.iter_mut().enumerate() {
+            let cutoff = now.saturating_sub(TIERS[tier].retention_ms);
+            segments.retain(|s| {
+                let expired = s.start + TIERS[tier].segment_ms <= cutoff;
+                if expired {
+                    remove(&s.path);
+                }
+                !expired
+            });
+        }
+
+        let mut total: u64 = tiers.iter().flatten().map(|s| s.size).sum();
+        for segments in tiers.iter_mut() {
+            // Never the newest segment of a tier: it's the one being appended to.
+            while total > self.max_bytes && segments.len() > 1 {
+                let oldest = segments.remove(0);
+                tracing::info!(target: TARGET, path = %oldest.path.display(), total, max = self.max_bytes, "recording over its size cap — dropping segment");
+                remove(&oldest.path);
+                total -= oldest.size;
+            }
+        }
+    }
+}
+
+struct Segment {
+    start: u64,
+    path: PathBuf,
+    size: u64,
+}
+
+/// Tier `tier`'s segment files, oldest first. Anything not named like a segment is ignored.
+fn segments(root: &Path, tier: usize) -> Vec<Segment> {
+    let Ok(entries) = fs::read_dir(root.join(TIERS[tier].name)) else {
+        return Vec::new();
+    };
+    let mut segments: Vec<Segment> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let start = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".jsonl")?
+                .parse()
+                .ok()?;
+            let size = entry.metadata().ok()?.len();
+            Some(Segment { start, path, size })
+        })
+        .collect();
+    segments.sort_by_key(|s| s.start);
+    segments
+}
+
+fn remove(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        tracing::warn!(target: TARGET, path = %path.display(), "failed to delete recording segment: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A day boundary.
+    const T0: u64 = 1_760_659_200_000;
+
+    fn append(store: &Store, tier: usize, t: u64) {
+        let mut bucket = Bucket::new(t, StreamName::Cpu);
+        bucket.add("global_usage", Agg::sample(50.0));
+        store.append(tier, &bucket).unwrap();
+    }
+
+    fn starts(root: &Path, tier: usize) -> Vec<u64> {
+        segments(root, tier).iter().map(|s| s.start).collect()
+    }
+
+    #[test]
+    fn a_new_second_segment_drops_those_past_their_retention() {
+        let root = tempfile::tempdir().unwrap();
+        let store = Store::new(root.path().to_path_buf(), u64::MAX);
+        append(&store, 2, T0 - 40 * DAY);
+        append(&store, 2, T0 - 10 * DAY);
+        append(&store, 1, T0 - 2 * DAY);
+        append(&store, 1, T0 - 2 * HOUR);
+        append(&store, 0, T0 - 2 * HOUR);
+        append(&store, 0, T0 - 30 * MINUTE);
+
+        append(&store, 0, T0);
+        assert_eq!(starts(root.path(), 0), [T0 - 30 * MINUTE, T0]);
+        assert_eq!(starts(root.path(), 1), [T0 - 2 * HOUR]);
+        assert_eq!(starts(root.path(), 2), [T0 - 10 * DAY]);
+    }
+
+    #[test]
+    fn over_the_cap_the_oldest_segments_go_finest_tier_first() {
+        let root = tempfile::tempdir().unwrap();
+        let store = Store::new(root.path().to_path_buf(), u64::MAX);
+        for minutes in [20, 15, 10, 5] {
+            append(&store, 0, T0 - minutes * MINUTE);
+        }
+        append(&store, 1, T0 - 3 * HOUR);
+        append(&store, 1, T0 - 2 * HOUR);
+        let total: u64 = (0..TIERS.len())
+            .flat_map(|tier| segments(root.path(), tier))
+            .map(|s| s.size)
+            .sum();
+
+        Store::new(root.path().to_path_buf(), total - 1).maintain(T0);
+        let seconds = [15, 10, 5].map(|minutes| T0 - minutes * MINUTE);
+        assert_eq!(starts(root.path(), 0), seconds);
+        assert_eq!(starts(root.path(), 1).len(), 2);
+
+        // Each tier keeps the segment it's appending to, however small the cap.
+        Store::new(root.path().to_path_buf(), 0).maintain(T0);
+        assert_eq!(starts(root.path(), 0), [T0 - 5 * MINUTE]);
+        assert_eq!(starts(root.path(), 1), [T0 - 2 * HOUR]);
+    }
+}
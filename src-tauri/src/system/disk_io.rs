@@ -0,0 +1,236 @@
+//! Per-device disk throughput and IOPS, as rates between consecutive reads. On Linux these come
+//! from the kernel's cumulative counters in `/proc/diskstats` (whole block devices only, so a
+//! partition's traffic isn't counted twice); the reader takes both paths as parameters so it can
+//! run against fixture files. Elsewhere sysinfo's per-disk I/O usage gives bytes but not
+//! operation counts.
+
+// The diskstats reader builds everywhere (it's plain file IO) but only Linux uses it.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use ts_rs::TS;
+
+use super::sysfs::read_number;
+
+pub const DISKSTATS_PATH: &str = "/proc/diskstats";
+pub const SYS_BLOCK_ROOT: &str = "/sys/block";
+
+/// `/proc/diskstats` counts in 512-byte sectors regardless of the device's own sector size.
+const SECTOR_BYTES: f32 = 512.0;
+
+/// One entry of the `stream::disk_io` payload.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct DiskIoStats {
+    /// Kernel block device name on Linux (`nvme0n1`, `sda`), the disk's name elsewhere.
+    pub device: String,
+    pub read_bytes_per_sec: f32,
+    pub write_bytes_per_sec: f32,
+    /// `None` where the platform only reports bytes (everything but Linux).
+    pub reads_per_sec: Option<f32>,
+    pub writes_per_sec: Option<f32>,
+}
+
+/// Reads this machine's disks.
+pub struct DiskIo {
+    #[cfg(target_os = "linux")]
+    diskstats: Diskstats,
+    #[cfg(not(target_os = "linux"))]
+    disks: sysinfo::Disks,
+    #[cfg(not(target_os = "linux"))]
+    read_at: Option<Instant>,
+}
+
+impl DiskIo {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            diskstats: Diskstats::new(DISKSTATS_PATH, SYS_BLOCK_ROOT),
+            #[cfg(not(target_os = "linux"))]
+            disks: sysinfo::Disks::new_with_refreshed_list_specifics(
+                sysinfo::DiskRefreshKind::nothing().with_io_usage(),
+            ),
+            #[cfg(not(target_os = "linux"))]
+            read_at: None,
+        }
+    }
+
+    /// Rates since the previous call, per device. Empty on the first call, which only takes
+    /// the baseline.
+    pub fn read(&mut self) -> Vec<DiskIoStats> {
+        #[cfg(target_os = "linux")]
+        {
+            self.diskstats.read()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            self.disks
+                .refresh_specifics(true, sysinfo::DiskRefreshKind::nothing().with_io_usage());
+            let now = Instant::now();
+            let Some(previous) = self.read_at.replace(now) else {
+                return Vec::new();
+            };
+            let secs = now.duration_since(previous).as_secs_f32().max(f32::EPSILON);
+            self.disks
+                .iter()
+                .map(|disk| {
+                    let usage = disk.usage();
+                    DiskIoStats {
+                        device: disk.name().to_string_lossy().to_string(),
+                        read_bytes_per_sec: usage.read_bytes as f32 / secs,
+                        write_bytes_per_sec: usage.written_bytes as f32 / secs,
+                        reads_per_sec: None,
+                        writes_per_sec: None,
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/* /proc/diskstats  */
+
+#[derive(Clone, Copy)]
+struct Counters {
+    reads: u64,
+    sectors_read: u64,
+    writes: u64,
+    sectors_written: u64,
+}
+
+/// `/proc/diskstats` reader — [`DISKSTATS_PATH`] and [`SYS_BLOCK_ROOT`] in the app, any file
+/// and directory laid out the same way otherwise.
+pub struct Diskstats {
+    path: PathBuf,
+    sys_block: PathBuf,
+    previous: HashMap<String, Counters>,
+    read_at: Option<Instant>,
+}
+
+impl Diskstats {
+    pub fn new(path: impl Into<PathBuf>, sys_block: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            sys_block: sys_block.into(),
+            previous: HashMap::new(),
+            read_at: None,
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<DiskIoStats> {
+        let Ok(text) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let secs = self
+            .read_at
+            .replace(now)
+            .map(|previous| now.duration_since(previous).as_secs_f32().max(f32::EPSILON));
+
+        let mut samples = Vec::new();
+        let mut current = HashMap::new();
+        for (name, counters) in text.lines().filter_map(parse_line) {
+            if !is_whole_device(&self.sys_block, name) {
+                continue;
+            }
+            if let (Some(secs), Some(before)) = (secs, self.previous.get(name)) {
+                // Saturating: counters restart from zero when a device is removed and re-added.
+                let rate = |now: u64, before: u64| now.saturating_sub(before) as f32 / secs;
+                samples.push(DiskIoStats {
+                    device: name.to_string(),
+                    read_bytes_per_sec: rate(counters.sectors_read, before.sectors_read)
+                        * SECTOR_BYTES,
+                    write_bytes_per_sec: rate(counters.sectors_written, before.sectors_written)
+                        * SECTOR_BYTES,
+                    reads_per_sec: Some(rate(counters.reads, before.reads)),
+                    writes_per_sec: Some(rate(counters.writes, before.writes)),
+                });
+            }
+            current.insert(name.to_string(), counters);
+        }
+        self.previous = current;
+        samples
+    }
+}
+
+/// `major minor name reads reads_merged sectors_read ms_reading writes writes_merged
+/// sectors_written ...` — newer kernels append discard and flush fields, which are ignored.
+fn parse_line(line: &str) -> Option<(&str, Counters)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let number = |i: usize| fields.get(i)?.parse::<u64>().ok();
+    Some((
+        fields.get(2)?,
+        Counters {
+            reads: number(3)?,
+            sectors_read: number(5)?,
+            writes: number(7)?,
+            sectors_written: number(9)?,
+        },
+    ))
+}
+
+/// Whole disks are the entries of `/sys/block` (partitions live under their disk). Loop and RAM
+/// devices are skipped, as is anything with no media (an empty card reader or optical drive).
+fn is_whole_device(sys_block: &Path, name: &str) -> bool {
+    if name.starts_with("loop") || name.starts_with("ram") {
+        return false;
+    }
+    // `/` in a device name (`cciss/c0d0`) becomes `!` in sysfs.
+    let dir = sys_block.join(name.replace('/', "!"));
+    dir.exists() && read_number(&dir.join("size")).is_some_and(|size| size > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    fn diskstats(nvme: (u64, u64, u64, u64)) -> String {
+        let (reads, sectors_read, writes, sectors_written) = nvme;
+        format!(
+            "   7       0 loop0 500 0 4000 10 0 0 0 0 0 10 10\n \
+             259       0 nvme0n1 {reads} 0 {sectors_read} 10 {writes} 0 {sectors_written} 10 0 20 20 0 0 0 0\n \
+             259       1 nvme0n1p1 {reads} 0 {sectors_read} 10 {writes} 0 {sectors_written} 10 0 20 20\n  \
+             11       0 sr0 0 0 0 0 0 0 0 0 0 0 0\n"
+        )
+    }
+
+    #[test]
+    fn rates_whole_devices_between_reads() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("diskstats");
+        let sys_block = root.path().join("block");
+        write_fixture(
+            &sys_block,
+            &[
+                ("loop0/size", "2048\n"),
+                ("nvme0n1/size", "1000215216\n"),
+                // An empty optical drive.
+                ("sr0/size", "0\n"),
+            ],
+        );
+        std::fs::write(&path, diskstats((1000, 8000, 200, 1600))).unwrap();
+        let mut reader = Diskstats::new(&path, &sys_block);
+        assert!(
+            reader.read().is_empty(),
+            "first read only takes the baseline"
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(&path, diskstats((1010, 8080, 205, 1640))).unwrap();
+        let samples = reader.read();
+
+        assert_eq!(samples.len(), 1);
+        let nvme = &samples[0];
+        assert_eq!(nvme.device, "nvme0n1");
+        let reads = nvme.reads_per_sec.unwrap();
+        let writes = nvme.writes_per_sec.unwrap();
+        assert!(reads > 0.0 && writes > 0.0);
+        // 8 sectors of 512 bytes per operation.
+        assert!((nvme.read_bytes_per_sec / reads - 4096.0).abs() < 0.5);
+        assert!((nvme.write_bytes_per_sec / writes - 4096.0).abs() < 0.5);
+        assert!((reads / writes - 2.0).abs() < 1e-3);
+    }
+}
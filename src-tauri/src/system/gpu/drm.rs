@@ -0,0 +1,345 @@
+//! GPUs under the kernel's DRM class (`/sys/class/drm/cardN`). The reader takes its root as a
+//! parameter so it can run against a fixture tree laid out the same way:
+//!
+//! - amdgpu: `device/gpu_busy_percent`, `device/mem_info_vram_{used,total}`,
+//!   `device/pp_dpm_sclk` for the clock range;
+//! - i915: `gt_act_freq_mhz`/`gt_max_freq_mhz` on the card, and the RC6 (idle) residency counter,
+//!   whose growth against wall time gives utilisation;
+//! - xe: the same per GT, under `device/tile0/gt0/{freq0,gtidle}`;
+//! - all of them: the card's hwmon (`device/hwmon/hwmonN`) for temperature and power.
+
+// The sysfs reader builds everywhere (it's plain file IO) but only Linux uses it.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{GpuInfo, GpuVendor};
+use crate::system::sysfs::{natural_key, read_number, read_trimmed};
+
+pub const DRM_ROOT: &str = "/sys/class/drm";
+
+/// How often the card list is rebuilt, for eGPUs and driver reloads.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// µW and µJ to watts and joules.
+const MICRO: f32 = 1_000_000.0;
+
+struct Card {
+    name: String,
+    /// `cardN` itself, where i915 keeps its GT attributes.
+    card: PathBuf,
+    /// `cardN/device`, the PCI device.
+    device: PathBuf,
+    driver: String,
+    hwmon: Option<PathBuf>,
+}
+
+/// Cumulative counters from the previous read, for the rate-derived readings.
+struct Counters {
+    at: Instant,
+    idle_ms: Option<u64>,
+    energy_uj: Option<u64>,
+}
+
+/// DRM reader rooted at `root` — [`DRM_ROOT`] in the app.
+pub struct Drm {
+    root: PathBuf,
+    cards: Vec<Card>,
+    scanned_at: Option<Instant>,
+    previous: HashMap<String, Counters>,
+}
+
+impl Drm {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cards: Vec::new(),
+            scanned_at: None,
+            previous: HashMap::new(),
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<GpuInfo> {
+        if self
+            .scanned_at
+            .is_none_or(|at| at.elapsed() >= RESCAN_INTERVAL)
+        {
+            self.cards = scan(&self.root);
+            self.scanned_at = Some(Instant::now());
+            let cards = &self.cards;
+            self.previous
+                .retain(|name, _| cards.iter().any(|card| card.name == *name));
+            tracing::debug!(target: "gpu", root = %self.root.display(), cards = self.cards.len(), "scanned DRM cards");
+        }
+        let now = Instant::now();
+        let mut gpus = Vec::new();
+        for card in &self.cards {
+            let counters = Counters {
+                at: now,
+                idle_ms: idle_residency_ms(card),
+                energy_uj: card
+                    .hwmon
+                    .as_ref()
+                    .and_then(|h| read_u64(&h.join("energy1_input"))),
+            };
+            let previous = self.previous.insert(card.name.clone(), counters);
+            let current = &self.previous[&card.name];
+            gpus.push(read_card(card, previous.as_ref(), current));
+        }
+        gpus
+    }
+}
+
+fn scan(root: &Path) -> Vec<Card> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<Card> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // `cardN` only; `cardN-DP-1` and friends are its connectors, `renderDN` its render node.
+            let index = name.strip_prefix("card")?;
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let card = entry.path();
+            let device = card.join("device");
+            let driver = std::fs::read_link(device.join("driver"))
+                .ok()?
+                .file_name()?
+                .to_string_lossy()
+                .to_string();
+            if !matches!(driver.as_str(), "amdgpu" | "i915" | "xe") {
+                return None;
+            }
+            let hwmon = std::fs::read_dir(device.join("hwmon"))
+                .ok()
+                .and_then(|mut dirs| dirs.find_map(|d| d.ok()))
+                .map(|d| d.path());
+            Some(Card {
+                name,
+                card,
+                device,
+                driver,
+                hwmon,
+            })
+        })
+        .collect();
+    cards.sort_by_key(|c| natural_key(&c.name));
+    cards
+}
+
+fn read_card(card: &Card, previous: Option<&Counters>, current: &Counters) -> GpuInfo {
+    let elapsed_ms = previous.map(|p| current.at.duration_since(p.at).as_secs_f64() * 1000.0);
+    // Per ms of wall time. Saturating: counters restart when the driver is reloaded.
+    let rate = |now: Option<u64>, before: Option<u64>| -> Option<f32> {
+        let elapsed = elapsed_ms.filter(|ms| *ms > 0.0)?;
+        Some((now?.saturating_sub(before?) as f64 / elapsed) as f32)
+    };
+
+    let hwmon = |file: &str| read_number(&card.hwmon.as_ref()?.join(file));
+    let power_w = hwmon("power1_average")
+        .or_else(|| hwmon("power1_input"))
+        .map(|uw| uw / MICRO)
+        // Intel discrete cards only expose an energy counter; µJ per ms is mW.
+        .or_else(|| {
+            rate(current.energy_uj, previous?.energy_uj).map(|uj_per_ms| uj_per_ms * 1000.0 / MICRO)
+        });
+
+    let mut gpu = GpuInfo {
+        id: card.name.clone(),
+        vendor: GpuVendor::Intel,
+        driver: card.driver.clone(),
+        name: None,
+        busy_percent: None,
+        vram_used: None,
+        vram_total: None,
+        temperature_c: hwmon("temp1_input").map(|mc| mc / 1000.0),
+        power_w,
+        frequency_mhz: None,
+        max_frequency_mhz: None,
+    };
+    match card.driver.as_str() {
+        "amdgpu" => {
+            gpu.vendor = GpuVendor::Amd;
+            gpu.name = read_trimmed(&card.device.join("product_name"));
+            gpu.busy_percent = read_number(&card.device.join("gpu_busy_percent"));
+            gpu.vram_used = read_u64(&card.device.join("mem_info_vram_used"));
+            gpu.vram_total = read_u64(&card.device.join("mem_info_vram_total"));
+            gpu.frequency_mhz = hwmon("freq1_input").map(|hz| hz / 1_000_000.0);
+            gpu.max_frequency_mhz = read_trimmed(&card.device.join("pp_dpm_sclk"))
+                .as_deref()
+                .and_then(max_dpm_level_mhz);
+        }
+        "i915" => {
+            gpu.frequency_mhz = read_number(&card.card.join("gt_act_freq_mhz"))
+                .or_else(|| read_number(&card.card.join("gt_cur_freq_mhz")));
+            gpu.max_frequency_mhz = read_number(&card.card.join("gt_max_freq_mhz"));
+        }
+        _ => {
+            let freq = card.device.join("tile0/gt0/freq0");
+            gpu.frequency_mhz = read_number(&freq.join("act_freq"));
+            gpu.max_frequency_mhz = read_number(&freq.join("max_freq"));
+        }
+    }
+    if gpu.vendor == GpuVendor::Intel {
+        gpu.busy_percent = rate(current.idle_ms, previous.and_then(|p| p.idle_ms))
+            .map(|idle| ((1.0 - idle) * 100.0).clamp(0.0, 100.0));
+    }
+    gpu
+}
+
+/// Cumulative ms the GPU has spent idle (RC6 on i915, `gtidle` on xe); `None` for amdgpu, which
+/// reports utilisation directly.
+fn idle_residency_ms(card: &Card) -> Option<u64> {
+    match card.driver.as_str() {
+        "i915" => read_u64(&card.card.join("gt/gt0/rc6_residency_ms"))
+            .or_else(|| read_u64(&card.card.join("power/rc6_residency_ms"))),
+        "xe" => read_u64(&card.device.join("tile0/gt0/gtidle/idle_residency_ms")),
+        _ => None,
+    }
+}
+
+/// Highest level of an amdgpu DPM table: `0: 500Mhz\n1: 1200Mhz *\n2: 2100Mhz`.
+fn max_dpm_level_mhz(table: &str) -> Option<f32> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let (_, level) = line.split_once(':')?;
+            let mhz = level.split_whitespace().next()?;
+            mhz.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .parse::<f32>()
+                .ok()
+        })
+        .reduce(f32::max)
+}
+
+/// Byte counts and cumulative counters don't survive [`read_number`]'s `f32`.
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    fn driver(root: &Path, card: &str, driver: &str) {
+        let device = root.join(card).join("device");
+        std::fs::create_dir_all(&device).unwrap();
+        std::os::unix::fs::symlink(
+            format!("../../../bus/pci/drivers/{driver}"),
+            device.join("driver"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_amdgpu_from_its_device_files() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("card0/device/product_name", "AMD Radeon RX 7600\n"),
+                ("card0/device/gpu_busy_percent", "37\n"),
+                ("card0/device/mem_info_vram_used", "1073741824\n"),
+                ("card0/device/mem_info_vram_total", "8589934592\n"),
+                (
+                    "card0/device/pp_dpm_sclk",
+                    "0: 500Mhz\n1: 1200Mhz *\n2: 2100Mhz\n",
+                ),
+                ("card0/device/hwmon/hwmon3/temp1_input", "45000\n"),
+                ("card0/device/hwmon/hwmon3/power1_average", "35000000\n"),
+                ("card0/device/hwmon/hwmon3/freq1_input", "1200000000\n"),
+                // Connectors and render nodes aren't cards.
+                ("card0-DP-1/status", "connected\n"),
+                ("renderD128/dev", "226:128\n"),
+                // Nor is a card of a driver without a reader.
+                ("card1/device/vendor", "0x10de\n"),
+            ],
+        );
+        driver(root.path(), "card0", "amdgpu");
+        driver(root.path(), "card1", "nouveau");
+
+        let gpus = Drm::new(root.path()).read();
+        assert_eq!(gpus.len(), 1);
+        let gpu = &gpus[0];
+        assert_eq!(gpu.id, "card0");
+        assert_eq!(gpu.vendor, GpuVendor::Amd);
+        assert_eq!(gpu.name.as_deref(), Some("AMD Radeon RX 7600"));
+        assert_eq!(gpu.busy_percent, Some(37.0));
+        assert_eq!(gpu.vram_used, Some(1 << 30));
+        assert_eq!(gpu.vram_total, Some(8 << 30));
+        assert_eq!(gpu.temperature_c, Some(45.0));
+        assert_eq!(gpu.power_w, Some(35.0));
+        assert_eq!(gpu.frequency_mhz, Some(1200.0));
+        assert_eq!(gpu.max_frequency_mhz, Some(2100.0));
+    }
+
+    #[test]
+    fn derives_i915_busy_from_rc6_residency_between_reads() {
+        let root = tempfile::tempdir().unwrap();
+        let rc6 = root.path().join("card0/gt/gt0/rc6_residency_ms");
+        write_fixture(
+            root.path(),
+            &[
+                ("card0/gt_act_freq_mhz", "350\n"),
+                ("card0/gt_max_freq_mhz", "1300\n"),
+                ("card0/gt/gt0/rc6_residency_ms", "100000\n"),
+                ("card0/device/vendor", "0x8086\n"),
+            ],
+        );
+        driver(root.path(), "card0", "i915");
+
+        let mut drm = Drm::new(root.path());
+        let first = drm.read();
+        assert_eq!(first[0].vendor, GpuVendor::Intel);
+        assert_eq!(first[0].frequency_mhz, Some(350.0));
+        assert_eq!(first[0].max_frequency_mhz, Some(1300.0));
+        assert_eq!(first[0].busy_percent, None, "one read has no rate");
+
+        // Idle for 50 ms of at least 200: busy at least 75 %.
+        std::thread::sleep(Duration::from_millis(200));
+        std::fs::write(&rc6, "100050\n").unwrap();
+        let busy = drm.read()[0].busy_percent.unwrap();
+        assert!((75.0..100.0).contains(&busy), "busy {busy}");
+
+        // Never idle.
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(drm.read()[0].busy_percent, Some(100.0));
+    }
+
+    #[test]
+    fn forgets_counters_of_cards_gone_at_a_rescan() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("card0/gt/gt0/rc6_residency_ms", "100\n"),
+                ("card1/gt/gt0/rc6_residency_ms", "100\n"),
+            ],
+        );
+        driver(root.path(), "card0", "i915");
+        driver(root.path(), "card1", "i915");
+
+        let mut drm = Drm::new(root.path());
+        assert_eq!(drm.read().len(), 2);
+        std::fs::remove_dir_all(root.path().join("card1")).unwrap();
+        drm.scanned_at = None;
+        assert_eq!(drm.read().len(), 1);
+        assert_eq!(drm.previous.keys().collect::<Vec<_>>(), ["card0"]);
+    }
+
+    #[test]
+    fn takes_the_highest_dpm_level() {
+        assert_eq!(
+            max_dpm_level_mhz("0: 500Mhz\n1: 1200Mhz *\n2: 2100Mhz\n"),
+            Some(2100.0)
+        );
+        assert_eq!(max_dpm_level_mhz("0: 96Mhz *\n1: 456MHz\n"), Some(456.0));
+        assert_eq!(max_dpm_level_mhz(""), None);
+    }
+}
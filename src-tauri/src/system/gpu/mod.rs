@@ -0,0 +1,131 @@
+//! GPU utilisation, memory, temperature, power and clocks from vendor-neutral interfaces: the
+//! kernel's DRM class for amdgpu and Intel (i915, xe) on Linux, and NVML for NVIDIA wherever
+//! the driver's `libnvidia-ml` is installed. Each backend reports what it found, or why it found
+//! nothing, so a widget can explain an empty list instead of just showing one.
+
+mod drm;
+mod nvml;
+
+use ts_rs::TS;
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum GpuVendor {
+    Amd,
+    Intel,
+    Nvidia,
+}
+
+/// Every reading is optional: which ones a GPU has depends on its driver and generation.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct GpuInfo {
+    /// Stable across ticks: the DRM card (`card0`) or the NVML index (`nvml0`).
+    pub id: String,
+    pub vendor: GpuVendor,
+    /// Kernel driver (`amdgpu`, `i915`, `xe`), or `nvidia` for NVML.
+    pub driver: String,
+    /// Marketing name, where the driver reports one.
+    pub name: Option<String>,
+    /// `0.0..=100.0`. Intel's is derived from the time the GPU spent in its idle state since the
+    /// previous tick, so it's `None` on the first.
+    pub busy_percent: Option<f32>,
+    /// Dedicated memory in bytes; integrated GPUs have none.
+    pub vram_used: Option<u64>,
+    pub vram_total: Option<u64>,
+    pub temperature_c: Option<f32>,
+    pub power_w: Option<f32>,
+    /// Current graphics clock.
+    pub frequency_mhz: Option<f32>,
+    pub max_frequency_mhz: Option<f32>,
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum GpuBackend {
+    Drm,
+    Nvml,
+}
+
+/// What one backend could do on this machine.
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct GpuSource {
+    pub backend: GpuBackend,
+    /// GPUs it reported on this read.
+    pub devices: u32,
+    /// Why it can't report at all (library missing, unsupported platform), or why it found no
+    /// device. `None` when it reported some.
+    pub unavailable: Option<String>,
+}
+
+/// Payload of `stream::gpu`.
+#[derive(serde::Serialize, Clone, Debug, Default, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct GpuStats {
+    pub gpus: Vec<GpuInfo>,
+    pub sources: Vec<GpuSource>,
+}
+
+/// Every backend, probed once when the producer starts.
+pub struct Gpus {
+    #[cfg(target_os = "linux")]
+    drm: drm::Drm,
+    nvml: Result<nvml::Nvml, String>,
+}
+
+impl Gpus {
+    pub fn new() -> Self {
+        let nvml = nvml::Nvml::load();
+        if let Err(e) = &nvml {
+            tracing::debug!(target: "gpu", "NVML unavailable: {e}");
+        }
+        Self {
+            #[cfg(target_os = "linux")]
+            drm: drm::Drm::new(drm::DRM_ROOT),
+            nvml,
+        }
+    }
+
+    pub fn read(&mut self) -> GpuStats {
+        let mut stats = GpuStats::default();
+
+        #[cfg(target_os = "linux")]
+        let drm = {
+            let gpus = self.drm.read();
+            let unavailable = gpus
+                .is_empty()
+                .then(|| format!("no amdgpu, i915 or xe device under {}", drm::DRM_ROOT));
+            stats.gpus.extend(gpus);
+            unavailable
+        };
+        #[cfg(not(target_os = "linux"))]
+        let drm = Some("DRM sysfs is Linux-only".to_string());
+        stats
+            .sources
+            .push(source(GpuBackend::Drm, stats.gpus.len(), drm));
+
+        let before = stats.gpus.len();
+        let nvml = match &self.nvml {
+            Ok(nvml) => {
+                stats.gpus.extend(nvml.read());
+                (stats.gpus.len() == before).then(|| "NVML reports no device".to_string())
+            }
+            Err(e) => Some(e.clone()),
+        };
+        stats
+            .sources
+            .push(source(GpuBackend::Nvml, stats.gpus.len() - before, nvml));
+        stats
+    }
+}
+
+fn source(backend: GpuBackend, devices: usize, unavailable: Option<String>) -> GpuSource {
+    GpuSource {
+        backend,
+        devices: devices as u32,
+        unavailable,
+    }
+}
@@ -0,0 +1,178 @@
+//! NVIDIA GPUs through NVML, the driver's management library. It's loaded at runtime, so
+//! machines without the NVIDIA driver need none of it. Only the calls the payload needs are bound;
+//! one that fails for a device (older boards report no power draw) leaves its field `None`.
+
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
+
+use super::{GpuInfo, GpuVendor};
+
+#[cfg(target_os = "windows")]
+const LIBRARY: &str = "nvml.dll";
+#[cfg(not(target_os = "windows"))]
+const LIBRARY: &str = "libnvidia-ml.so.1";
+
+const NVML_SUCCESS: c_int = 0;
+const NVML_TEMPERATURE_GPU: c_uint = 0;
+const NVML_CLOCK_GRAPHICS: c_uint = 0;
+/// `NVML_DEVICE_NAME_V2_BUFFER_SIZE`.
+const NAME_BUFFER: usize = 96;
+
+type Device = *mut c_void;
+
+#[repr(C)]
+#[derive(Default)]
+struct Utilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Memory {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+/// The bound entry points.
+struct Api {
+    device_count: unsafe extern "C" fn(*mut c_uint) -> c_int,
+    handle_by_index: unsafe extern "C" fn(c_uint, *mut Device) -> c_int,
+    name: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int,
+    utilization: unsafe extern "C" fn(Device, *mut Utilization) -> c_int,
+    memory: unsafe extern "C" fn(Device, *mut Memory) -> c_int,
+    temperature: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    power_usage: unsafe extern "C" fn(Device, *mut c_uint) -> c_int,
+    clock: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    max_clock: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    shutdown: unsafe extern "C" fn() -> c_int,
+}
+
+/// An initialised NVML. Shut down on drop.
+pub struct Nvml {
+    api: Api,
+    // Dropped last, so the pointers in `api` never outlive the code they point into.
+    _library: Library,
+}
+
+/// Copies `name` out of `library`.
+///
+/// # Safety
+/// `T` must be the symbol's real type, and the value must not be used after `library` is dropped.
+unsafe fn symbol<T: Copy>(library: &Library, name: &str) -> Result<T, String> {
+    library
+        .get::<T>(name.as_bytes())
+        .map(|s| *s)
+        .map_err(|e| format!("{LIBRARY} lacks {name}: {e}"))
+}
+
+impl Nvml {
+    /// Loads and initialises the library; the error says why NVML isn't usable here.
+    pub fn load() -> Result<Self, String> {
+        // SAFETY: the signatures are those of `nvml.h`, and every pointer is kept next to the
+        // library it came from.
+        unsafe {
+            let library = Library::new(LIBRARY).map_err(|e| format!("NVML not loaded: {e}"))?;
+            let init: unsafe extern "C" fn() -> c_int = symbol(&library, "nvmlInit_v2")?;
+            let api = Api {
+                device_count: symbol(&library, "nvmlDeviceGetCount_v2")?,
+                handle_by_index: symbol(&library, "nvmlDeviceGetHandleByIndex_v2")?,
+                name: symbol(&library, "nvmlDeviceGetName")?,
+                utilization: symbol(&library, "nvmlDeviceGetUtilizationRates")?,
+                memory: symbol(&library, "nvmlDeviceGetMemoryInfo")?,
+                temperature: symbol(&library, "nvmlDeviceGetTemperature")?,
+                power_usage: symbol(&library, "nvmlDeviceGetPowerUsage")?,
+                clock: symbol(&library, "nvmlDeviceGetClockInfo")?,
+                max_clock: symbol(&library, "nvmlDeviceGetMaxClockInfo")?,
+                shutdown: symbol(&library, "nvmlShutdown")?,
+            };
+            match init() {
+                NVML_SUCCESS => Ok(Self {
+                    api,
+                    _library: library,
+                }),
+                status => Err(format!("nvmlInit failed with status {status}")),
+            }
+        }
+    }
+
+    pub fn read(&self) -> Vec<GpuInfo> {
+        let mut count: c_uint = 0;
+        // SAFETY: NVML is initialised for as long as `self` lives, and every out-pointer is a
+        // live local of the type the call expects.
+        unsafe {
+            if (self.api.device_count)(&mut count) != NVML_SUCCESS {
+                return Vec::new();
+            }
+            (0..count)
+                .filter_map(|index| {
+                    let mut device: Device = std::ptr::null_mut();
+                    ((self.api.handle_by_index)(index, &mut device) == NVML_SUCCESS)
+                        .then(|| self.read_device(index, device))
+                })
+                .collect()
+        }
+    }
+
+    /// # Safety
+    /// `device` must be a handle NVML returned since it was initialised.
+    unsafe fn read_device(&self, index: c_uint, device: Device) -> GpuInfo {
+        let ok = |status: c_int| status == NVML_SUCCESS;
+
+        let mut name = [0 as c_char; NAME_BUFFER];
+        let name = ok((self.api.name)(
+            device,
+            name.as_mut_ptr(),
+            NAME_BUFFER as c_uint,
+        ))
+        .then(|| CStr::from_ptr(name.as_ptr()).to_string_lossy().to_string());
+        let mut utilization = Utilization::default();
+        let busy_percent =
+            ok((self.api.utilization)(device, &mut utilization)).then_some(utilization.gpu as f32);
+        let mut memory = Memory::default();
+        let memory = ok((self.api.memory)(device, &mut memory)).then_some(memory);
+
+        let mut value: c_uint = 0;
+        let temperature_c = ok((self.api.temperature)(
+            device,
+            NVML_TEMPERATURE_GPU,
+            &mut value,
+        ))
+        .then_some(value as f32);
+        // Milliwatts.
+        let power_w =
+            ok((self.api.power_usage)(device, &mut value)).then_some(value as f32 / 1000.0);
+        let frequency_mhz =
+            ok((self.api.clock)(device, NVML_CLOCK_GRAPHICS, &mut value)).then_some(value as f32);
+        let max_frequency_mhz = ok((self.api.max_clock)(
+            device,
+            NVML_CLOCK_GRAPHICS,
+            &mut value,
+        ))
+        .then_some(value as f32);
+
+        GpuInfo {
+            id: format!("nvml{index}"),
+            vendor: GpuVendor::Nvidia,
+            driver: "nvidia".to_string(),
+            name,
+            busy_percent,
+            vram_used: memory.as_ref().map(|m| m.used),
+            vram_total: memory.as_ref().map(|m| m.total),
+            temperature_c,
+            power_w,
+            frequency_mhz,
+            max_frequency_mhz,
+        }
+    }
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        // SAFETY: `self` only exists after a successful init.
+        unsafe {
+            (self.api.shutdown)();
+        }
+    }
+}
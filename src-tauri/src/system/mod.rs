@@ -1,10 +1,27 @@
-use crate::events::{emit_stream, StreamName, SubscriberGate};
+use crate::channel::{run_polled, run_polled_on_change, Channel, ChannelCore, Producer};
+use crate::events::StreamName;
 use std::collections::HashMap;
-use std::sync::atomic::AtomicUsize;
-use std::sync::Arc;
 use std::time::Duration;
+use tauri::{AppHandle, Manager};
 use ts_rs::TS;
 
+mod disk_io;
+mod gpu;
+mod network;
+mod power;
+mod processes;
+mod sensors;
+mod sysfs;
+mod system_info;
+
+pub use disk_io::DiskIoStats;
+pub use gpu::GpuStats;
+pub use network::NetworkInterfaceInfo;
+pub use power::PowerStats;
+pub use processes::*;
+pub use sensors::SensorReading;
+pub use system_info::SystemInfo;
+
 #[derive(serde::Serialize, Clone, TS)]
 #[ts(export, export_to = "../../src/ffi_types.ts")]
 pub struct Core {
@@ -49,17 +66,6 @@ pub struct DiskInfo {
     pub available_space: u64,
 }
 
-#[derive(serde::Serialize, Clone, TS)]
-#[ts(export, export_to = "../../src/ffi_types.ts")]
-pub struct NetworkInterfaceInfo {
-    pub name: String,
-    pub received: u64,
-    pub transmitted: u64,
-    pub total_received: u64,
-    pub total_transmitted: u64,
-    pub mac_address: String,
-}
-
 fn collect_cpu_stats(sys: &sysinfo::System) -> CpuStats {
     let mut processors: HashMap<String, Processor> = HashMap::new();
     for cpu in sys.cpus() {
@@ -107,110 +113,514 @@ fn collect_disk_stats(disks: &sysinfo::Disks) -> Vec<DiskInfo> {
         .collect()
 }
 
-fn collect_network_stats(networks: &sysinfo::Networks) -> Vec<NetworkInterfaceInfo> {
-    networks
-        .iter()
-        .filter_map(|(name, data)| {
-            let mac = data.mac_address();
-            if mac.is_unspecified() {
-                return None;
-            }
-            Some(NetworkInterfaceInfo {
-                name: name.clone(),
-                received: data.received(),
-                transmitted: data.transmitted(),
-                total_received: data.total_received(),
-                total_transmitted: data.total_transmitted(),
-                mac_address: mac.to_string(),
+/* Channels  */
+
+// Each metric is its own channel with its own task and `sysinfo` handle, created inside the
+// producer so nothing outside this module ever touches them and none needs a shared mutex.
+
+pub struct CpuChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl CpuChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Cpu),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for CpuChannel {
+    type Payload = CpuStats;
+    const NAME: StreamName = StreamName::Cpu;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(stats: &CpuStats) -> Vec<(String, f32)> {
+        let cores = stats.processors.iter().flat_map(|p| &p.cores);
+        std::iter::once(("global_usage".to_string(), stats.global_usage))
+            .chain(cores.map(|c| (format!("{}/usage", c.name), c.usage)))
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut system = sysinfo::System::new_with_specifics(
+            sysinfo::RefreshKind::nothing().with_cpu(sysinfo::CpuRefreshKind::everything()),
+        );
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || {
+                system.refresh_cpu_all();
+                collect_cpu_stats(&system)
+            },
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct MemoryChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl MemoryChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Memory),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for MemoryChannel {
+    type Payload = MemoryStats;
+    const NAME: StreamName = StreamName::Memory;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(stats: &MemoryStats) -> Vec<(String, f32)> {
+        vec![
+            ("used".to_string(), stats.used as f32),
+            ("swap_used".to_string(), stats.swap_used as f32),
+        ]
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut system = sysinfo::System::new_with_specifics(
+            sysinfo::RefreshKind::nothing().with_memory(sysinfo::MemoryRefreshKind::everything()),
+        );
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || {
+                system.refresh_memory();
+                collect_memory_stats(&system)
+            },
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct DisksChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl DisksChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Disks),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for DisksChannel {
+    type Payload = Vec<DiskInfo>;
+    const NAME: StreamName = StreamName::Disks;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(disks: &Vec<DiskInfo>) -> Vec<(String, f32)> {
+        disks
+            .iter()
+            .map(|d| {
+                // `/` and `C:\` would otherwise give `//available_space`, `C:\/available_space`.
+                let mount = d.mount_point.trim_end_matches(['/', '\\']);
+                (format!("{mount}/available_space"), d.available_space as f32)
             })
-        })
-        .collect()
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut disks = sysinfo::Disks::new_with_refreshed_list();
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || {
+                disks.refresh(false);
+                collect_disk_stats(&disks)
+            },
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct DiskIoChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl DiskIoChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::DiskIo),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for DiskIoChannel {
+    type Payload = Vec<DiskIoStats>;
+    const NAME: StreamName = StreamName::DiskIo;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(disks: &Vec<DiskIoStats>) -> Vec<(String, f32)> {
+        disks
+            .iter()
+            .flat_map(|d| {
+                [
+                    Some(("read_bytes_per_sec", d.read_bytes_per_sec)),
+                    Some(("write_bytes_per_sec", d.write_bytes_per_sec)),
+                    d.reads_per_sec.map(|v| ("reads_per_sec", v)),
+                    d.writes_per_sec.map(|v| ("writes_per_sec", v)),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|(field, value)| (format!("{}/{field}", d.device), value))
+            })
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut disks = disk_io::DiskIo::new();
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || disks.read(),
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct NetworksChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl NetworksChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Networks),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for NetworksChannel {
+    type Payload = Vec<NetworkInterfaceInfo>;
+    const NAME: StreamName = StreamName::Networks;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(interfaces: &Vec<NetworkInterfaceInfo>) -> Vec<(String, f32)> {
+        interfaces
+            .iter()
+            .flat_map(|i| {
+                [
+                    (format!("{}/received_per_sec", i.name), i.received_per_sec),
+                    (
+                        format!("{}/transmitted_per_sec", i.name),
+                        i.transmitted_per_sec,
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let ssids = network::Ssids::default();
+        let mut networks = network::Networks::new(ssids.clone());
+        let polled =
+            run_polled::<Self, _>(app.clone(), self.core.clone(), self.interval, move || {
+                networks.read()
+            });
+        let watch = network::watch_ssids(self.core.clone(), ssids);
+        self.producer.spawn(async move {
+            tokio::join!(polled, watch);
+        });
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct SensorsChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl SensorsChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Sensors),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for SensorsChannel {
+    type Payload = Vec<SensorReading>;
+    const NAME: StreamName = StreamName::Sensors;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(readings: &Vec<SensorReading>) -> Vec<(String, f32)> {
+        readings
+            .iter()
+            .map(|r| (format!("{}/{}", r.id, r.kind.as_str()), r.value))
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut sensors = sensors::Sensors::new();
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || sensors.read(),
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
 }
 
-/// `sysinfo` handles for the cpu/memory/disks/networks metrics, owned entirely by
-/// `run_resource_loop`'s task — confirmed (full-crate grep) that nothing outside this module
-/// ever touches them, so unlike the rest of `AppStateInner` they need no shared mutex at all.
-struct ResourceState {
-    system: sysinfo::System,
-    disks: sysinfo::Disks,
-    networks: sysinfo::Networks,
+pub struct PowerChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
 }
 
-impl ResourceState {
-    fn new() -> Self {
+impl PowerChannel {
+    pub fn new(interval: Duration) -> Self {
         Self {
-            system: sysinfo::System::new_with_specifics(
-                sysinfo::RefreshKind::nothing()
-                    .with_cpu(sysinfo::CpuRefreshKind::everything())
-                    .with_memory(sysinfo::MemoryRefreshKind::everything()),
-            ),
-            disks: sysinfo::Disks::new_with_refreshed_list(),
-            networks: sysinfo::Networks::new_with_refreshed_list(),
+            core: ChannelCore::new(StreamName::Power),
+            producer: Producer::default(),
+            interval,
         }
     }
 }
 
-/// Consolidates what used to be `run_system_loop` (cpu+memory) and `run_hardware_loop`
-/// (disks+networks) into one task: a `tokio::select!` over 4 independent per-metric intervals,
-/// so cadences can diverge later without another refactor, while cutting 2 tokio tasks down to
-/// 1. Only one `select!` arm's body ever executes per loop iteration, so plain field access on
-/// the task-owned `ResourceState` needs no interior mutability.
-pub async fn run_resource_loop(
-    app: tauri::AppHandle,
-    cpu_subs: Arc<AtomicUsize>,
-    memory_subs: Arc<AtomicUsize>,
-    disks_subs: Arc<AtomicUsize>,
-    networks_subs: Arc<AtomicUsize>,
-    poll_interval: Duration,
-) {
-    tracing::info!(target: "resource", "resource loop started");
-
-    let mut state = ResourceState::new();
-
-    let mut cpu_interval = tokio::time::interval(poll_interval);
-    let mut memory_interval = tokio::time::interval(poll_interval);
-    let mut disks_interval = tokio::time::interval(poll_interval);
-    let mut networks_interval = tokio::time::interval(poll_interval);
-    for iv in [
-        &mut cpu_interval,
-        &mut memory_interval,
-        &mut disks_interval,
-        &mut networks_interval,
-    ] {
-        iv.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-    }
-
-    let mut cpu_gate = SubscriberGate::new(cpu_subs, StreamName::Cpu);
-    let mut memory_gate = SubscriberGate::new(memory_subs, StreamName::Memory);
-    let mut disks_gate = SubscriberGate::new(disks_subs, StreamName::Disks);
-    let mut networks_gate = SubscriberGate::new(networks_subs, StreamName::Networks);
-
-    loop {
-        tokio::select! {
-            _ = cpu_interval.tick() => {
-                if cpu_gate.should_run() {
-                    state.system.refresh_cpu_all();
-                    emit_stream(&app, StreamName::Cpu, collect_cpu_stats(&state.system));
-                }
-            }
-            _ = memory_interval.tick() => {
-                if memory_gate.should_run() {
-                    state.system.refresh_memory();
-                    emit_stream(&app, StreamName::Memory, collect_memory_stats(&state.system));
-                }
-            }
-            _ = disks_interval.tick() => {
-                if disks_gate.should_run() {
-                    state.disks.refresh(false);
-                    emit_stream(&app, StreamName::Disks, collect_disk_stats(&state.disks));
-                }
-            }
-            _ = networks_interval.tick() => {
-                if networks_gate.should_run() {
-                    state.networks.refresh(false);
-                    emit_stream(&app, StreamName::Networks, collect_network_stats(&state.networks));
-                }
-            }
+impl Channel for PowerChannel {
+    type Payload = PowerStats;
+    const NAME: StreamName = StreamName::Power;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(stats: &PowerStats) -> Vec<(String, f32)> {
+        stats
+            .capacity_percent
+            .map(|capacity| ("capacity_percent".to_string(), capacity))
+            .into_iter()
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            power::current,
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct ProcessesChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl ProcessesChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Processes),
+            producer: Producer::default(),
+            interval,
         }
     }
 }
+
+impl Channel for ProcessesChannel {
+    type Payload = ProcessList;
+    const NAME: StreamName = StreamName::Processes;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut processes = Processes::new();
+        let handle = app.clone();
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || processes.read(&handle.state::<ProcessQueries>().current()),
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+
+    fn release(&self, app: &AppHandle, id: &str) {
+        app.state::<ProcessQueries>().remove(id);
+    }
+}
+
+pub struct GpuChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl GpuChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::Gpu),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for GpuChannel {
+    type Payload = GpuStats;
+    const NAME: StreamName = StreamName::Gpu;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(stats: &GpuStats) -> Vec<(String, f32)> {
+        stats
+            .gpus
+            .iter()
+            .flat_map(|g| {
+                [
+                    ("busy_percent", g.busy_percent),
+                    ("vram_used", g.vram_used.map(|v| v as f32)),
+                    ("temperature_c", g.temperature_c),
+                    ("power_w", g.power_w),
+                    ("frequency_mhz", g.frequency_mhz),
+                ]
+                .into_iter()
+                .filter_map(|(field, value)| Some((format!("{}/{field}", g.id), value?)))
+            })
+            .collect()
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut gpus = gpu::Gpus::new();
+        self.producer.spawn(run_polled::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || gpus.read(),
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
+
+pub struct SystemInfoChannel {
+    core: ChannelCore,
+    producer: Producer,
+    interval: Duration,
+}
+
+impl SystemInfoChannel {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            core: ChannelCore::new(StreamName::SystemInfo),
+            producer: Producer::default(),
+            interval,
+        }
+    }
+}
+
+impl Channel for SystemInfoChannel {
+    type Payload = SystemInfo;
+    const NAME: StreamName = StreamName::SystemInfo;
+
+    fn core(&self) -> &ChannelCore {
+        &self.core
+    }
+
+    fn metrics(info: &SystemInfo) -> Vec<(String, f32)> {
+        vec![
+            ("load_average/one".to_string(), info.load_average.one),
+            ("process_count".to_string(), info.process_count as f32),
+        ]
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let mut reader = system_info::SystemInfoReader::new();
+        self.producer.spawn(run_polled_on_change::<Self, _>(
+            app.clone(),
+            self.core.clone(),
+            self.interval,
+            move || reader.read_changed(),
+        ));
+    }
+
+    fn stop(&self) {
+        self.producer.stop();
+    }
+}
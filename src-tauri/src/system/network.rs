@@ -0,0 +1,450 @@
+//! Network interfaces: throughput as bytes per second over the time actually elapsed between
+//! reads, cumulative packet and error counts, addresses, and link details. Every interface is
+//! reported and tagged as loopback or virtual, so widgets choose what to hide instead of the
+//! backend guessing.
+//!
+//! On Linux, link state and the loopback/virtual classification come from `/sys/class/net`,
+//! signal strength from `/proc/net/wireless`, and SSIDs from NetworkManager over D-Bus when it's
+//! running. Both file readers take their paths as parameters so they can run against fixtures.
+//! Elsewhere only what sysinfo reports is available.
+
+// The sysfs and procfs readers build everywhere (they're plain file IO) but only Linux uses them.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use ts_rs::TS;
+
+use super::sysfs::{natural_key, read_trimmed};
+use crate::channel::ChannelCore;
+
+pub const SYS_CLASS_NET: &str = "/sys/class/net";
+pub const PROC_NET_WIRELESS: &str = "/proc/net/wireless";
+
+/// `type` of a loopback device in `/sys/class/net` (`ARPHRD_LOOPBACK`).
+const ARPHRD_LOOPBACK: &str = "772";
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum LinkState {
+    Up,
+    Down,
+    /// Not reported — everywhere but Linux, and for loopback, which the kernel never marks up.
+    Unknown,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Default, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct WirelessInfo {
+    /// `None` without NetworkManager, or while not associated.
+    pub ssid: Option<String>,
+    pub signal_dbm: Option<f32>,
+    /// Driver-reported link quality, `0.0..=100.0`.
+    pub link_quality: Option<f32>,
+}
+
+#[derive(serde::Serialize, Clone, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct NetworkInterfaceInfo {
+    pub name: String,
+    /// Bytes per second since the previous tick.
+    pub received_per_sec: f32,
+    pub transmitted_per_sec: f32,
+    pub total_received: u64,
+    pub total_transmitted: u64,
+    pub total_packets_received: u64,
+    pub total_packets_transmitted: u64,
+    pub total_errors_received: u64,
+    pub total_errors_transmitted: u64,
+    /// `None` for interfaces without one (loopback, most tunnels).
+    pub mac_address: Option<String>,
+    /// CIDR notation, e.g. `192.168.1.20/24`.
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    pub mtu: u64,
+    pub link: LinkState,
+    pub loopback: bool,
+    /// No hardware behind it: bridges, VPN tunnels, container veths. On Linux that's anything
+    /// without a backing device in sysfs; elsewhere, anything without a MAC address.
+    pub virtual_device: bool,
+    /// Only for wireless interfaces.
+    pub wireless: Option<WirelessInfo>,
+}
+
+/// SSIDs by interface name, kept current by [`watch_ssids`].
+pub type Ssids = Arc<Mutex<HashMap<String, String>>>;
+
+/// Interface reader, owned by the producer.
+pub struct Networks {
+    networks: sysinfo::Networks,
+    refreshed_at: Instant,
+    ssids: Ssids,
+}
+
+impl Networks {
+    pub fn new(ssids: Ssids) -> Self {
+        Self {
+            networks: sysinfo::Networks::new_with_refreshed_list(),
+            refreshed_at: Instant::now(),
+            ssids,
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<NetworkInterfaceInfo> {
+        self.networks.refresh(true);
+        let now = Instant::now();
+        let secs = now
+            .duration_since(std::mem::replace(&mut self.refreshed_at, now))
+            .as_secs_f32()
+            .max(f32::EPSILON);
+        #[cfg(target_os = "linux")]
+        let signals = read_wireless(Path::new(PROC_NET_WIRELESS));
+        let ssids = self.ssids.lock().map(|s| s.clone()).unwrap_or_default();
+
+        let mut interfaces: Vec<NetworkInterfaceInfo> = self
+            .networks
+            .iter()
+            .map(|(name, data)| {
+                let mac = data.mac_address();
+                let mac_address = (!mac.is_unspecified()).then(|| mac.to_string());
+                let (mut ipv4, mut ipv6) = (Vec::new(), Vec::new());
+                for network in data.ip_networks() {
+                    let cidr = format!("{}/{}", network.addr, network.prefix);
+                    match network.addr {
+                        IpAddr::V4(_) => ipv4.push(cidr),
+                        IpAddr::V6(_) => ipv6.push(cidr),
+                    }
+                }
+
+                #[cfg(target_os = "linux")]
+                let (link, loopback, virtual_device, wireless) = {
+                    let class = classify(Path::new(SYS_CLASS_NET), name);
+                    let wireless =
+                        (class.wireless || signals.contains_key(name)).then(|| WirelessInfo {
+                            ssid: ssids.get(name).cloned(),
+                            ..signals.get(name).cloned().unwrap_or_default()
+                        });
+                    (class.link, class.loopback, class.virtual_device, wireless)
+                };
+                #[cfg(not(target_os = "linux"))]
+                let (link, loopback, virtual_device, wireless) = {
+                    let loopback = !data.ip_networks().is_empty()
+                        && data.ip_networks().iter().all(|n| n.addr.is_loopback());
+                    let wireless = ssids.get(name).map(|ssid| WirelessInfo {
+                        ssid: Some(ssid.clone()),
+                        ..WirelessInfo::default()
+                    });
+                    (
+                        LinkState::Unknown,
+                        loopback,
+                        mac_address.is_none() && !loopback,
+                        wireless,
+                    )
+                };
+
+                NetworkInterfaceInfo {
+                    name: name.clone(),
+                    received_per_sec: data.received() as f32 / secs,
+                    transmitted_per_sec: data.transmitted() as f32 / secs,
+                    total_received: data.total_received(),
+                    total_transmitted: data.total_transmitted(),
+                    total_packets_received: data.total_packets_received(),
+                    total_packets_transmitted: data.total_packets_transmitted(),
+                    total_errors_received: data.total_errors_on_received(),
+                    total_errors_transmitted: data.total_errors_on_transmitted(),
+                    mac_address,
+                    ipv4,
+                    ipv6,
+                    mtu: data.mtu(),
+                    link,
+                    loopback,
+                    virtual_device,
+                    wireless,
+                }
+            })
+            .collect();
+        interfaces.sort_by_key(|i| natural_key(&i.name));
+        interfaces
+    }
+}
+
+/* /sys/class/net  */
+
+pub struct Classification {
+    pub link: LinkState,
+    pub loopback: bool,
+    pub virtual_device: bool,
+    pub wireless: bool,
+}
+
+/// What `<root>/<name>` says about an interface — [`SYS_CLASS_NET`] in the app.
+pub fn classify(root: &Path, name: &str) -> Classification {
+    let dir = root.join(name);
+    let link = match read_trimmed(&dir.join("operstate")).as_deref() {
+        Some("up") => LinkState::Up,
+        Some("down" | "dormant" | "lowerlayerdown" | "notpresent") => LinkState::Down,
+        _ => LinkState::Unknown,
+    };
+    Classification {
+        link,
+        loopback: read_trimmed(&dir.join("type")).as_deref() == Some(ARPHRD_LOOPBACK),
+        // Physical NICs link to their bus device; software interfaces live under
+        // `/sys/devices/virtual` and have none.
+        virtual_device: !dir.join("device").exists(),
+        wireless: dir.join("wireless").exists() || dir.join("phy80211").exists(),
+    }
+}
+
+/* /proc/net/wireless  */
+
+/// Signal and link quality per interface from `path` — [`PROC_NET_WIRELESS`] in the app. After
+/// two header lines, each row reads `iface: status link level noise ...`, with values suffixed
+/// `.` when the driver updated them since the last read.
+pub fn read_wireless(path: &Path) -> HashMap<String, WirelessInfo> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<f32> = rest
+                .split_whitespace()
+                .skip(1)
+                .take(2)
+                .filter_map(|f| f.trim_end_matches('.').parse().ok())
+                .collect();
+            let (quality, level) = (fields.first().copied(), fields.get(1).copied());
+            Some((
+                name.trim().to_string(),
+                WirelessInfo {
+                    ssid: None,
+                    // cfg80211 reports quality out of 70.
+                    link_quality: quality.map(|q| (q / 70.0 * 100.0).clamp(0.0, 100.0)),
+                    // Old drivers report dBm offset into an unsigned byte.
+                    signal_dbm: level.filter(|l| *l != 0.0).map(|l| {
+                        if l > 0.0 {
+                            l - 256.0
+                        } else {
+                            l
+                        }
+                    }),
+                },
+            ))
+        })
+        .collect()
+}
+
+/* SSIDs  */
+
+/// Keeps `ssids` current while the channel has subscribers. Runs alongside the producer and for
+/// as long as it does; returns straight away where there's nothing to watch.
+pub async fn watch_ssids(core: ChannelCore, ssids: Ssids) {
+    #[cfg(target_os = "linux")]
+    network_manager::watch(core, ssids).await;
+    #[cfg(not(target_os = "linux"))]
+    let _ = (core, ssids);
+}
+
+#[cfg(target_os = "linux")]
+mod network_manager {
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use zbus::zvariant::OwnedObjectPath;
+
+    use super::Ssids;
+    use crate::channel::ChannelCore;
+
+    const TARGET: &str = "network";
+
+    /// Access point changes are rare; no need to track them more closely than this.
+    const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// `NM_DEVICE_TYPE_WIFI`.
+    const DEVICE_TYPE_WIFI: u32 = 2;
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.NetworkManager",
+        default_service = "org.freedesktop.NetworkManager",
+        default_path = "/org/freedesktop/NetworkManager",
+        gen_blocking = false
+    )]
+    trait NetworkManager {
+        #[zbus(property)]
+        fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    }
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.NetworkManager.Device",
+        default_service = "org.freedesktop.NetworkManager",
+        gen_blocking = false
+    )]
+    trait Device {
+        #[zbus(property)]
+        fn interface(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn device_type(&self) -> zbus::Result<u32>;
+    }
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.NetworkManager.Device.Wireless",
+        default_service = "org.freedesktop.NetworkManager",
+        gen_blocking = false
+    )]
+    trait Wireless {
+        #[zbus(property)]
+        fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+    }
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.NetworkManager.AccessPoint",
+        default_service = "org.freedesktop.NetworkManager",
+        gen_blocking = false
+    )]
+    trait AccessPoint {
+        #[zbus(property)]
+        fn ssid(&self) -> zbus::Result<Vec<u8>>;
+    }
+
+    pub async fn watch(core: ChannelCore, ssids: Ssids) {
+        let conn = match zbus::Connection::system().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                tracing::debug!(target: TARGET, error = %err, "no system bus — SSIDs unavailable");
+                return;
+            }
+        };
+        let mut failing = false;
+        loop {
+            if core.subscribers() > 0 {
+                let found = match read_ssids(&conn).await {
+                    Ok(found) => {
+                        failing = false;
+                        found
+                    }
+                    Err(err) => {
+                        if !failing {
+                            tracing::debug!(target: TARGET, error = %err, "NetworkManager unavailable — SSIDs unavailable");
+                            failing = true;
+                        }
+                        HashMap::new()
+                    }
+                };
+                if let Ok(mut ssids) = ssids.lock() {
+                    *ssids = found;
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn read_ssids(conn: &zbus::Connection) -> zbus::Result<HashMap<String, String>> {
+        let nm = NetworkManagerProxy::builder(conn)
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await?;
+        let mut found = HashMap::new();
+        for path in nm.devices().await? {
+            let device = DeviceProxy::builder(conn)
+                .path(path.clone())?
+                .cache_properties(zbus::proxy::CacheProperties::No)
+                .build()
+                .await?;
+            if device.device_type().await? != DEVICE_TYPE_WIFI {
+                continue;
+            }
+            let wireless = WirelessProxy::builder(conn)
+                .path(path)?
+                .cache_properties(zbus::proxy::CacheProperties::No)
+                .build()
+                .await?;
+            let access_point = wireless.active_access_point().await?;
+            // NetworkManager's "none" object path.
+            if access_point.as_str() == "/" {
+                continue;
+            }
+            let access_point = AccessPointProxy::builder(conn)
+                .path(access_point)?
+                .cache_properties(zbus::proxy::CacheProperties::No)
+                .build()
+                .await?;
+            let ssid = access_point.ssid().await?;
+            found.insert(
+                device.interface().await?,
+                String::from_utf8_lossy(&ssid).to_string(),
+            );
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    #[test]
+    fn classifies_interfaces_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("lo/type", "772\n"),
+                ("lo/operstate", "unknown\n"),
+                ("enp3s0/type", "1\n"),
+                ("enp3s0/operstate", "up\n"),
+                ("enp3s0/device/vendor", "0x8086\n"),
+                ("wlan0/type", "1\n"),
+                ("wlan0/operstate", "dormant\n"),
+                ("wlan0/device/vendor", "0x8086\n"),
+                ("wlan0/wireless/.keep", ""),
+                ("wg0/type", "65534\n"),
+                ("wg0/operstate", "up\n"),
+            ],
+        );
+
+        let lo = classify(root.path(), "lo");
+        assert!(lo.loopback && lo.virtual_device && !lo.wireless);
+        assert_eq!(lo.link, LinkState::Unknown);
+
+        let wired = classify(root.path(), "enp3s0");
+        assert!(!wired.loopback && !wired.virtual_device && !wired.wireless);
+        assert_eq!(wired.link, LinkState::Up);
+
+        let wlan = classify(root.path(), "wlan0");
+        assert!(wlan.wireless && !wlan.virtual_device);
+        assert_eq!(wlan.link, LinkState::Down);
+
+        let tunnel = classify(root.path(), "wg0");
+        assert!(tunnel.virtual_device && !tunnel.loopback);
+    }
+
+    #[test]
+    fn reads_signal_and_quality_from_proc_net_wireless() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("wireless");
+        std::fs::write(
+            &path,
+            "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE\n \
+             face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22\n\
+             wlan0: 0000   35.  -62.  -256        0      0      0      0      0        0\n\
+             wlan1: 0000   70   196   0           0      0      0      0      0        0\n",
+        )
+        .unwrap();
+        let signals = read_wireless(&path);
+
+        let wlan0 = &signals["wlan0"];
+        assert_eq!(wlan0.link_quality, Some(50.0));
+        assert_eq!(wlan0.signal_dbm, Some(-62.0));
+        // An old driver's unsigned-byte dBm.
+        let wlan1 = &signals["wlan1"];
+        assert_eq!(wlan1.link_quality, Some(100.0));
+        assert_eq!(wlan1.signal_dbm, Some(-60.0));
+
+        assert!(read_wireless(&root.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+//! Batteries and AC adapters, read from the kernel's power-supply class
+//! (`/sys/class/power_supply`). The reader takes its root as a parameter so it can run against a
+//! fixture directory. Other platforms report no supplies.
+
+// The sysfs reader builds everywhere (it's plain file IO) but only Linux uses it.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::path::Path;
+use ts_rs::TS;
+
+use super::sysfs::{natural_key, read_number, read_trimmed};
+
+/// Sysfs class directory power supplies register under.
+pub const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge limit.
+    NotCharging,
+    Unknown,
+}
+
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct BatteryInfo {
+    /// Kernel name, e.g. `BAT0`.
+    pub name: String,
+    pub model: Option<String>,
+    /// A device's own battery (wireless mouse, headset) rather than one powering this machine.
+    /// Peripherals are listed but left out of the `PowerStats` totals.
+    pub peripheral: bool,
+    pub state: BatteryState,
+    /// `0.0..=100.0`.
+    pub capacity_percent: Option<f32>,
+    pub energy_now_wh: Option<f32>,
+    pub energy_full_wh: Option<f32>,
+    /// Charge or discharge rate in watts, always positive; `state` says which.
+    pub energy_rate_w: Option<f32>,
+    /// Only while discharging.
+    pub time_to_empty_secs: Option<u64>,
+    /// Only while charging.
+    pub time_to_full_secs: Option<u64>,
+}
+
+/// Payload of `stream::power`.
+#[derive(serde::Serialize, Clone, Debug, Default, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct PowerStats {
+    /// Whether any mains or USB supply is online; `None` if the machine reports none (most
+    /// desktops).
+    pub ac_online: Option<bool>,
+    pub batteries: Vec<BatteryInfo>,
+    /// Energy-weighted charge across the system batteries, `0.0..=100.0`.
+    pub capacity_percent: Option<f32>,
+    /// Across the system batteries, at their combined rate.
+    pub time_to_empty_secs: Option<u64>,
+    pub time_to_full_secs: Option<u64>,
+}
+
+/// This machine's power supplies.
+pub fn current() -> PowerStats {
+    #[cfg(target_os = "linux")]
+    {
+        read(Path::new(POWER_SUPPLY_ROOT))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        PowerStats::default()
+    }
+}
+
+/// Reads every supply under `root` ([`POWER_SUPPLY_ROOT`] in the app).
+pub fn read(root: &Path) -> PowerStats {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return PowerStats::default();
+    };
+    let mut supplies: Vec<(String, std::path::PathBuf)> = entries
+        .flatten()
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+        .collect();
+    supplies.sort_by_key(|(name, _)| natural_key(name));
+
+    let mut stats = PowerStats::default();
+    for (name, dir) in supplies {
+        match read_trimmed(&dir.join("type")).as_deref() {
+            // Some drivers register a battery slot that's empty.
+            Some("Battery") if read_trimmed(&dir.join("present")).as_deref() != Some("0") => {
+                stats.batteries.push(read_battery(name, &dir));
+            }
+            Some("Mains" | "USB" | "USB_C" | "USB_PD" | "USB_PD_DRP") => {
+                let online = read_trimmed(&dir.join("online")).as_deref() == Some("1");
+                stats.ac_online = Some(stats.ac_online.unwrap_or(false) || online);
+            }
+            _ => {}
+        }
+    }
+    summarise(&mut stats);
+    stats
+}
+
+/// Micro-units (µWh, µAh, µW, µA, µV) to base units.
+const MICRO: f32 = 1_000_000.0;
+
+fn read_battery(name: String, dir: &Path) -> BatteryInfo {
+    let attr = |file: &str| read_number(&dir.join(file));
+    let micro = |file: &str| attr(file).map(|v| v / MICRO);
+    let voltage = micro("voltage_now");
+
+    // Drivers report either energy (µWh, µW) or charge (µAh, µA); charge converts through
+    // the present voltage.
+    let energy = |energy_file: &str, charge_file: &str| {
+        micro(energy_file).or_else(|| Some(micro(charge_file)? * voltage?))
+    };
+    let energy_now_wh = energy("energy_now", "charge_now");
+    let energy_full_wh = energy("energy_full", "charge_full");
+    let energy_rate_w = micro("power_now")
+        .or_else(|| Some(micro("current_now")? * voltage?))
+        .map(f32::abs);
+
+    let state = match read_trimmed(&dir.join("status")).as_deref() {
+        Some("Charging") => BatteryState::Charging,
+        Some("Discharging") => BatteryState::Discharging,
+        Some("Full") => BatteryState::Full,
+        Some("Not charging") => BatteryState::NotCharging,
+        _ => BatteryState::Unknown,
+    };
+    let capacity_percent = attr("capacity").or_else(|| {
+        let (now, full) = (energy_now_wh?, energy_full_wh?);
+        (full > 0.0).then(|| (now / full * 100.0).min(100.0))
+    });
+
+    let (time_to_empty_secs, time_to_full_secs) = match state {
+        BatteryState::Discharging => (
+            attr("time_to_empty_now")
+                .map(|s| s as u64)
+                .or_else(|| hours_to_secs(energy_now_wh?, energy_rate_w?)),
+            None,
+        ),
+        BatteryState::Charging => (
+            None,
+            attr("time_to_full_now")
+                .map(|s| s as u64)
+                .or_else(|| hours_to_secs(energy_full_wh? - energy_now_wh?, energy_rate_w?)),
+        ),
+        _ => (None, None),
+    };
+
+    BatteryInfo {
+        name,
+        model: read_trimmed(&dir.join("model_name")),
+        peripheral: read_trimmed(&dir.join("scope")).as_deref() == Some("Device"),
+        state,
+        capacity_percent,
+        energy_now_wh,
+        energy_full_wh,
+        energy_rate_w,
+        time_to_empty_secs,
+        time_to_full_secs,
+    }
+}
+
+/// `energy_wh / rate_w` hours, in seconds; `None` at a zero rate (idle, or a driver that reports
+/// no current).
+fn hours_to_secs(energy_wh: f32, rate_w: f32) -> Option<u64> {
+    (rate_w > 0.0 && energy_wh >= 0.0).then(|| (energy_wh / rate_w * 3600.0) as u64)
+}
+
+/// Fills the system-wide totals from the non-peripheral batteries. Laptops with two packs
+/// discharge them in turn, so remaining time is total energy over the combined rate rather than
+/// the per-battery figures.
+fn summarise(stats: &mut PowerStats) {
+    let system: Vec<&BatteryInfo> = stats.batteries.iter().filter(|b| !b.peripheral).collect();
+    if system.is_empty() {
+        return;
+    }
+    let sum = |f: fn(&BatteryInfo) -> Option<f32>| -> Option<f32> {
+        system.iter().map(|b| f(b)).sum::<Option<f32>>()
+    };
+    let now = sum(|b| b.energy_now_wh);
+    let full = sum(|b| b.energy_full_wh);
+    let rate = sum(|b| b.energy_rate_w);
+
+    stats.capacity_percent = match (now, full) {
+        (Some(now), Some(full)) if full > 0.0 => Some((now / full * 100.0).min(100.0)),
+        _ => {
+            let known: Vec<f32> = system.iter().filter_map(|b| b.capacity_percent).collect();
+            (!known.is_empty()).then(|| known.iter().sum::<f32>() / known.len() as f32)
+        }
+    };
+    let any = |state: BatteryState| system.iter().any(|b| b.state == state);
+    if any(BatteryState::Discharging) {
+        stats.time_to_empty_secs = now
+            .zip(rate)
+            .and_then(|(now, rate)| hours_to_secs(now, rate));
+    } else if any(BatteryState::Charging) {
+        stats.time_to_full_secs = now
+            .zip(full)
+            .zip(rate)
+            .and_then(|((now, full), rate)| hours_to_secs(full - now, rate));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    fn battery<'a>(stats: &'a PowerStats, name: &str) -> &'a BatteryInfo {
+        stats.batteries.iter().find(|b| b.name == name).unwrap()
+    }
+
+    #[test]
+    fn reads_an_energy_based_battery() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("AC/type", "Mains\n"),
+                ("AC/online", "0\n"),
+                ("BAT0/type", "Battery\n"),
+                ("BAT0/present", "1\n"),
+                ("BAT0/status", "Discharging\n"),
+                ("BAT0/model_name", "5B10W13930\n"),
+                ("BAT0/capacity", "50\n"),
+                ("BAT0/energy_now", "30000000\n"),
+                ("BAT0/energy_full", "60000000\n"),
+                ("BAT0/power_now", "15000000\n"),
+            ],
+        );
+        let stats = read(root.path());
+
+        assert_eq!(stats.ac_online, Some(false));
+        let bat = battery(&stats, "BAT0");
+        assert_eq!(bat.model.as_deref(), Some("5B10W13930"));
+        assert!(!bat.peripheral);
+        assert_eq!(bat.state, BatteryState::Discharging);
+        assert_eq!(bat.capacity_percent, Some(50.0));
+        assert_eq!(bat.energy_now_wh, Some(30.0));
+        assert_eq!(bat.energy_full_wh, Some(60.0));
+        assert_eq!(bat.energy_rate_w, Some(15.0));
+        assert_eq!(bat.time_to_empty_secs, Some(7200));
+        assert_eq!(bat.time_to_full_secs, None);
+        assert_eq!(stats.capacity_percent, Some(50.0));
+        assert_eq!(stats.time_to_empty_secs, Some(7200));
+    }
+
+    #[test]
+    fn converts_a_charge_based_battery_through_its_voltage() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("ADP1/type", "Mains\n"),
+                ("ADP1/online", "1\n"),
+                ("BAT1/type", "Battery\n"),
+                ("BAT1/status", "Charging\n"),
+                ("BAT1/charge_now", "2000000\n"),
+                ("BAT1/charge_full", "4000000\n"),
+                ("BAT1/voltage_now", "12000000\n"),
+                // Some drivers sign the current by direction.
+                ("BAT1/current_now", "-1500000\n"),
+            ],
+        );
+        let stats = read(root.path());
+
+        assert_eq!(stats.ac_online, Some(true));
+        let bat = battery(&stats, "BAT1");
+        assert_eq!(bat.state, BatteryState::Charging);
+        assert_eq!(bat.energy_now_wh, Some(24.0));
+        assert_eq!(bat.energy_full_wh, Some(48.0));
+        assert_eq!(bat.energy_rate_w, Some(18.0));
+        // No `capacity` file: derived from the energies.
+        assert_eq!(bat.capacity_percent, Some(50.0));
+        assert_eq!(bat.time_to_full_secs, Some(4800));
+        assert_eq!(stats.time_to_full_secs, Some(4800));
+        assert_eq!(stats.time_to_empty_secs, None);
+    }
+
+    #[test]
+    fn totals_two_batteries_at_their_combined_rate() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("BAT0/type", "Battery\n"),
+                ("BAT0/status", "Discharging\n"),
+                ("BAT0/energy_now", "20000000\n"),
+                ("BAT0/energy_full", "40000000\n"),
+                ("BAT0/power_now", "10000000\n"),
+                // The second pack waits its turn.
+                ("BAT1/type", "Battery\n"),
+                ("BAT1/status", "Unknown\n"),
+                ("BAT1/energy_now", "30000000\n"),
+                ("BAT1/energy_full", "40000000\n"),
+                ("BAT1/power_now", "0\n"),
+            ],
+        );
+        let stats = read(root.path());
+
+        assert_eq!(stats.batteries.len(), 2);
+        assert_eq!(battery(&stats, "BAT1").time_to_empty_secs, None);
+        assert_eq!(stats.capacity_percent, Some(62.5));
+        assert_eq!(stats.time_to_empty_secs, Some(18_000));
+        assert_eq!(stats.ac_online, None);
+    }
+
+    #[test]
+    fn leaves_peripherals_out_of_the_totals() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("BAT0/type", "Battery\n"),
+                ("BAT0/status", "Discharging\n"),
+                ("BAT0/capacity", "40\n"),
+                ("BAT0/energy_now", "20000000\n"),
+                ("BAT0/energy_full", "50000000\n"),
+                ("BAT0/power_now", "10000000\n"),
+                ("hidpp_battery_0/type", "Battery\n"),
+                ("hidpp_battery_0/scope", "Device\n"),
+                ("hidpp_battery_0/status", "Discharging\n"),
+                ("hidpp_battery_0/capacity", "90\n"),
+            ],
+        );
+        let stats = read(root.path());
+
+        let mouse = battery(&stats, "hidpp_battery_0");
+        assert!(mouse.peripheral);
+        assert_eq!(mouse.capacity_percent, Some(90.0));
+        assert_eq!(stats.capacity_percent, Some(40.0));
+        assert_eq!(stats.time_to_empty_secs, Some(7200));
+
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("hidpp_battery_0/type", "Battery\n"),
+                ("hidpp_battery_0/scope", "Device\n"),
+                ("hidpp_battery_0/capacity", "90\n"),
+            ],
+        );
+        let stats = read(root.path());
+        assert_eq!(stats.batteries.len(), 1);
+        assert_eq!(stats.capacity_percent, None);
+    }
+
+    #[test]
+    fn skips_an_empty_battery_slot() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("BAT0/type", "Battery\n"),
+                ("BAT0/present", "1\n"),
+                ("BAT0/capacity", "80\n"),
+                ("BAT1/type", "Battery\n"),
+                ("BAT1/present", "0\n"),
+            ],
+        );
+        let stats = read(root.path());
+
+        assert_eq!(stats.batteries.len(), 1);
+        assert_eq!(stats.batteries[0].name, "BAT0");
+        assert_eq!(stats.batteries[0].state, BatteryState::Unknown);
+        assert_eq!(stats.capacity_percent, Some(80.0));
+    }
+}
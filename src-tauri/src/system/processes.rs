@@ -0,0 +1,273 @@
+//! Top processes by CPU and by memory. Widgets register what they want — how many, sorted by
+//! what, which fields — with `set_process_query`, the same way visualizer widgets register their
+//! analysis config, and the producer refreshes only what the live queries need: a CPU-only query
+//! never reads memory maps, and owner names are only resolved if someone shows them.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, UpdateKind};
+use tauri::Manager;
+use ts_rs::TS;
+
+const TARGET: &str = "processes";
+
+/// Largest `top` a query may ask for.
+const MAX_TOP: u32 = 100;
+
+/// How often the uid → name table is reloaded while someone wants owners.
+const USERS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum ProcessSort {
+    Cpu,
+    Memory,
+}
+
+/// Optional per-process fields. Pid, name and start time are always filled; the sort key's own
+/// field is implied.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum ProcessField {
+    Cpu,
+    Memory,
+    User,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+#[serde(default)]
+pub struct ProcessQuery {
+    /// How many processes, 1–100.
+    pub top: u32,
+    pub sort_by: ProcessSort,
+    pub fields: Vec<ProcessField>,
+}
+
+impl Default for ProcessQuery {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            sort_by: ProcessSort::Cpu,
+            fields: vec![ProcessField::Memory],
+        }
+    }
+}
+
+impl ProcessQuery {
+    fn sanitized(mut self) -> Self {
+        self.top = self.top.clamp(1, MAX_TOP);
+        self
+    }
+
+    fn wants(&self, field: ProcessField) -> bool {
+        let implied = match self.sort_by {
+            ProcessSort::Cpu => ProcessField::Cpu,
+            ProcessSort::Memory => ProcessField::Memory,
+        };
+        field == implied || self.fields.contains(&field)
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core, as `top` reports it, so a busy multithreaded process can exceed 100.
+    /// `None` unless a query asked for CPU.
+    pub cpu_percent: Option<f32>,
+    /// Resident set size in bytes. `None` unless a query asked for memory.
+    pub rss: Option<u64>,
+    /// Unix seconds.
+    pub start_time: u64,
+    /// Owner's login name. `None` unless a query asked for it, or if it can't be resolved.
+    pub user: Option<String>,
+}
+
+/// Payload of `stream::processes`. Each list is as long as the largest `top` any query asked
+/// for with that sort, and empty if none did; widgets take their own prefix.
+#[derive(serde::Serialize, Clone, Debug, Default, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct ProcessList {
+    pub by_cpu: Vec<ProcessInfo>,
+    pub by_memory: Vec<ProcessInfo>,
+}
+
+/// Process table reader, owned by the producer.
+pub struct Processes {
+    system: sysinfo::System,
+    users: sysinfo::Users,
+    users_refreshed_at: Option<Instant>,
+}
+
+impl Processes {
+    pub fn new() -> Self {
+        Self {
+            system: sysinfo::System::new(),
+            users: sysinfo::Users::new(),
+            users_refreshed_at: None,
+        }
+    }
+
+    pub fn read(&mut self, queries: &[ProcessQuery]) -> ProcessList {
+        let wants = |field| queries.iter().any(|q| q.wants(field));
+        let (cpu, memory, user) = (
+            wants(ProcessField::Cpu),
+            wants(ProcessField::Memory),
+            wants(ProcessField::User),
+        );
+
+        // Threads would otherwise be listed as processes of their own on Linux.
+        let mut kind = ProcessRefreshKind::nothing().without_tasks();
+        if cpu {
+            kind = kind.with_cpu();
+        }
+        if memory {
+            kind = kind.with_memory();
+        }
+        if user {
+            kind = kind.with_user(UpdateKind::OnlyIfNotSet);
+            if self
+                .users_refreshed_at
+                .is_none_or(|at| at.elapsed() >= USERS_REFRESH_INTERVAL)
+            {
+                self.users.refresh();
+                self.users_refreshed_at = Some(Instant::now());
+            }
+        }
+        self.system
+            .refresh_processes_specifics(ProcessesToUpdate::All, true, kind);
+
+        let top = |sort| {
+            queries
+                .iter()
+                .filter(|q| q.sort_by == sort)
+                .map(|q| q.top as usize)
+                .max()
+                .unwrap_or(0)
+        };
+        let list = |sort, n: usize| {
+            if n == 0 {
+                return Vec::new();
+            }
+            let mut processes: Vec<&sysinfo::Process> = self.system.processes().values().collect();
+            match sort {
+                ProcessSort::Cpu => {
+                    processes.sort_unstable_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()))
+                }
+                ProcessSort::Memory => {
+                    processes.sort_unstable_by_key(|p| std::cmp::Reverse(p.memory()))
+                }
+            }
+            processes
+                .into_iter()
+                .take(n)
+                .map(|p| ProcessInfo {
+                    pid: p.pid().as_u32(),
+                    name: p.name().to_string_lossy().to_string(),
+                    cpu_percent: cpu.then(|| p.cpu_usage()),
+                    rss: memory.then(|| p.memory()),
+                    start_time: p.start_time(),
+                    user: user
+                        .then(|| p.user_id())
+                        .flatten()
+                        .and_then(|uid| self.users.get_user_by_id(uid))
+                        .map(|u| u.name().to_string()),
+                })
+                .collect()
+        };
+        ProcessList {
+            by_cpu: list(ProcessSort::Cpu, top(ProcessSort::Cpu)),
+            by_memory: list(ProcessSort::Memory, top(ProcessSort::Memory)),
+        }
+    }
+}
+
+/* Per-subscriber queries  */
+
+/// Queries registered by widgets, keyed by the id the widget chose (normally its widget id),
+/// each with the label of the webview that registered it.
+///
+/// A query lives as long as its widget's subscription: unsubscribing from `processes` with the id
+/// drops it, and so does a reload of the webview it came from.
+#[derive(Default)]
+pub struct ProcessQueries(std::sync::Mutex<HashMap<String, Registered>>);
+
+struct Registered {
+    /// Label of the webview that registered the query.
+    webview: String,
+    query: ProcessQuery,
+}
+
+impl ProcessQueries {
+    /// Every registered query, or the default one if no widget registered any — a plain
+    /// subscriber still gets a useful list.
+    pub fn current(&self) -> Vec<ProcessQuery> {
+        let queries: Vec<ProcessQuery> = self
+            .0
+            .lock()
+            .map(|q| {
+                q.values()
+                    .map(|registered| registered.query.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if queries.is_empty() {
+            vec![ProcessQuery::default()]
+        } else {
+            queries
+        }
+    }
+
+    pub(crate) fn remove(&self, id: &str) {
+        if let Ok(mut queries) = self.0.lock() {
+            queries.remove(id);
+        }
+    }
+
+    /// Drops every query registered from webview `label`.
+    pub fn clear_webview(&self, label: &str) {
+        if let Ok(mut queries) = self.0.lock() {
+            let before = queries.len();
+            queries.retain(|_, registered| registered.webview != label);
+            let dropped = before - queries.len();
+            if dropped > 0 {
+                tracing::debug!(target: TARGET, webview = label, dropped, "dropped process queries of reloaded webview");
+            }
+        }
+    }
+}
+
+/// Registers (or replaces) the process query for `id`. The widget still subscribes to the
+/// `processes` channel as usual — that is what keeps the producer running — and should pass the
+/// same `id` so the query goes when the widget unsubscribes.
+#[tauri::command]
+pub async fn set_process_query(
+    id: String,
+    query: ProcessQuery,
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+) -> Result<(), String> {
+    let query = query.sanitized();
+    tracing::debug!(target: TARGET, id = %id, webview = webview.label(), ?query, "invoke: set_process_query");
+    if let Ok(mut queries) = app.state::<ProcessQueries>().0.lock() {
+        queries.insert(
+            id,
+            Registered {
+                webview: webview.label().to_string(),
+                query,
+            },
+        );
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_process_query(id: String, app: tauri::AppHandle) -> Result<(), String> {
+    tracing::debug!(target: TARGET, id = %id, "invoke: clear_process_query");
+    app.state::<ProcessQueries>().remove(&id);
+    Ok(())
+}
@@ -0,0 +1,313 @@
+//! Hardware sensors: temperatures, fan speeds and voltages. On Linux these are read straight
+//! from hwmon (`/sys/class/hwmon`), which covers fans and voltages as well as temperatures and
+//! takes its root as a parameter so it can be pointed at a fake sysfs tree. Elsewhere only
+//! sysinfo's `Components` temperatures are available — except on Windows, where that path hits
+//! the double-free in `docs/sysinfo-component-bstr-double-free.md` and is skipped entirely.
+
+// The hwmon reader builds everywhere (it's plain file IO) but only Linux uses it.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::time::Duration;
+use ts_rs::TS;
+
+use super::sysfs::{natural_key, read_number, read_trimmed};
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub enum SensorKind {
+    /// °C.
+    Temperature,
+    /// RPM.
+    Fan,
+    /// Volts.
+    Voltage,
+}
+
+impl SensorKind {
+    /// The serde name, e.g. `"temperature"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Temperature => "temperature",
+            Self::Fan => "fan",
+            Self::Voltage => "voltage",
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct SensorReading {
+    /// Stable across ticks, e.g. `hwmon2/temp1`; use it as the React key.
+    pub id: String,
+    /// Chip or driver the sensor belongs to, e.g. `k10temp`, `nvme`, `nct6798`.
+    pub device: String,
+    /// The driver's label (`Tctl`, `Composite`, `CPU Fan`), or the channel name if it has none.
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f32,
+    /// Upper warning threshold, in the same unit as `value`.
+    pub max: Option<f32>,
+    /// Critical threshold (hardware shutdown or throttling), in the same unit as `value`.
+    pub critical: Option<f32>,
+}
+
+/// Sysfs class directory hwmon chips register under.
+pub const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+/// How often the chip/channel list is rebuilt, so hotplugged devices (USB, NVMe behind a
+/// hot-swap bay) appear without a restart.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reads every sensor the platform exposes.
+pub struct Sensors {
+    #[cfg(target_os = "linux")]
+    hwmon: Hwmon,
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    components: sysinfo::Components,
+}
+
+impl Sensors {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            hwmon: Hwmon::new(HWMON_ROOT),
+            #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+            components: sysinfo::Components::new_with_refreshed_list(),
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<SensorReading> {
+        #[cfg(target_os = "linux")]
+        {
+            self.hwmon.read()
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Vec::new()
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        {
+            self.components.refresh(true);
+            self.components
+                .iter()
+                .filter_map(|c| {
+                    Some(SensorReading {
+                        id: c.id().unwrap_or(c.label()).to_string(),
+                        device: c.id().unwrap_or_default().to_string(),
+                        label: c.label().to_string(),
+                        kind: SensorKind::Temperature,
+                        value: c.temperature()?,
+                        max: c.max(),
+                        critical: c.critical(),
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+/* hwmon  */
+
+/// One `<prefix><n>_input` file plus the static metadata read alongside it at scan time.
+struct Input {
+    input: std::path::PathBuf,
+    /// Divisor from the sysfs unit (m°C, mV, RPM) to the reported one.
+    scale: f32,
+    reading: SensorReading,
+}
+
+/// hwmon reader rooted at `root` — [`HWMON_ROOT`] in the app, any directory laid out the same
+/// way (`<root>/hwmonN/{name,temp1_input,temp1_label,...}`) otherwise.
+pub struct Hwmon {
+    root: std::path::PathBuf,
+    inputs: Vec<Input>,
+    scanned_at: Option<std::time::Instant>,
+}
+
+impl Hwmon {
+    pub fn new(root: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            inputs: Vec::new(),
+            scanned_at: None,
+        }
+    }
+
+    /// Current value of every channel whose input is readable right now. Inputs that fail
+    /// (some drivers return `EIO`/`ENODATA` while a device sleeps) are left out of this read.
+    pub fn read(&mut self) -> Vec<SensorReading> {
+        if self
+            .scanned_at
+            .is_none_or(|at| at.elapsed() >= RESCAN_INTERVAL)
+        {
+            self.inputs = scan(&self.root);
+            self.scanned_at = Some(std::time::Instant::now());
+            tracing::debug!(target: "sensors", root = %self.root.display(), inputs = self.inputs.len(), "scanned hwmon");
+        }
+        self.inputs
+            .iter()
+            .filter_map(|c| {
+                let raw = read_number(&c.input)?;
+                Some(SensorReading {
+                    value: raw / c.scale,
+                    ..c.reading.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+/// sysfs prefix, reported kind, and the divisor from the sysfs unit to the reported one.
+const CHANNEL_TYPES: [(&str, SensorKind, f32); 3] = [
+    ("temp", SensorKind::Temperature, 1000.0),
+    ("fan", SensorKind::Fan, 1.0),
+    ("in", SensorKind::Voltage, 1000.0),
+];
+
+fn scan(root: &std::path::Path) -> Vec<Input> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut chips: Vec<(String, std::path::PathBuf)> = entries
+        .flatten()
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+        .collect();
+    chips.sort_by_key(|(name, _)| natural_key(name));
+
+    let mut inputs = Vec::new();
+    for (chip, path) in chips {
+        // Old drivers keep the attributes under `device/` rather than the hwmon dir itself.
+        let dir = if path.join("name").exists() {
+            path
+        } else {
+            path.join("device")
+        };
+        let device = read_trimmed(&dir.join("name")).unwrap_or_else(|| chip.clone());
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<(SensorKind, u32, &str, f32)> = files
+            .flatten()
+            .filter_map(|f| {
+                let name = f.file_name().to_string_lossy().to_string();
+                let stem = name.strip_suffix("_input")?;
+                CHANNEL_TYPES.iter().find_map(|&(prefix, kind, scale)| {
+                    let index = stem.strip_prefix(prefix)?.parse().ok()?;
+                    Some((kind, index, prefix, scale))
+                })
+            })
+            .collect();
+        found.sort_by_key(|&(kind, index, ..)| (kind, index));
+
+        for (kind, index, prefix, scale) in found {
+            let channel = format!("{prefix}{index}");
+            let attr = |suffix: &str| dir.join(format!("{channel}_{suffix}"));
+            let threshold = |suffix: &str| read_number(&attr(suffix)).map(|v| v / scale);
+            inputs.push(Input {
+                input: attr("input"),
+                scale,
+                reading: SensorReading {
+                    id: format!("{chip}/{channel}"),
+                    device: device.clone(),
+                    label: read_trimmed(&attr("label")).unwrap_or_else(|| channel.clone()),
+                    kind,
+                    value: 0.0,
+                    max: threshold("max"),
+                    critical: threshold("crit"),
+                },
+            });
+        }
+    }
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    fn find<'a>(readings: &'a [SensorReading], id: &str) -> &'a SensorReading {
+        readings.iter().find(|r| r.id == id).unwrap()
+    }
+
+    #[test]
+    fn reads_scaled_and_labelled_channels() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("hwmon0/name", "k10temp\n"),
+                ("hwmon0/temp1_input", "45250\n"),
+                ("hwmon0/temp1_label", "Tctl\n"),
+                ("hwmon0/temp1_max", "70000\n"),
+                ("hwmon0/temp1_crit", "95000\n"),
+                ("hwmon0/fan1_input", "1200\n"),
+                ("hwmon0/in0_input", "1250\n"),
+            ],
+        );
+        let readings = Hwmon::new(root.path()).read();
+
+        let ids: Vec<_> = readings.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["hwmon0/temp1", "hwmon0/fan1", "hwmon0/in0"]);
+
+        let temp = find(&readings, "hwmon0/temp1");
+        assert_eq!(temp.device, "k10temp");
+        assert_eq!(temp.label, "Tctl");
+        assert_eq!(temp.kind, SensorKind::Temperature);
+        assert_eq!(temp.value, 45.25);
+        assert_eq!(temp.max, Some(70.0));
+        assert_eq!(temp.critical, Some(95.0));
+
+        let fan = find(&readings, "hwmon0/fan1");
+        assert_eq!(fan.label, "fan1");
+        assert_eq!(fan.kind, SensorKind::Fan);
+        assert_eq!(fan.value, 1200.0);
+        assert_eq!(fan.critical, None);
+
+        let voltage = find(&readings, "hwmon0/in0");
+        assert_eq!(voltage.kind, SensorKind::Voltage);
+        assert_eq!(voltage.value, 1.25);
+    }
+
+    #[test]
+    fn reads_attributes_under_device_and_sorts_chips_naturally() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("hwmon10/name", "nvme\n"),
+                ("hwmon10/temp1_input", "38850\n"),
+                ("hwmon2/device/name", "it87\n"),
+                ("hwmon2/device/temp2_input", "51000\n"),
+            ],
+        );
+        let readings = Hwmon::new(root.path()).read();
+
+        let ids: Vec<_> = readings.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["hwmon2/temp2", "hwmon10/temp1"]);
+        assert_eq!(find(&readings, "hwmon2/temp2").device, "it87");
+        assert_eq!(find(&readings, "hwmon2/temp2").value, 51.0);
+    }
+
+    #[test]
+    fn leaves_out_inputs_that_fail_to_read() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("hwmon0/name", "drivetemp\n"),
+                ("hwmon0/temp1_input", ""),
+                ("hwmon0/temp2_input", "33000\n"),
+            ],
+        );
+        let mut hwmon = Hwmon::new(root.path());
+        let readings = hwmon.read();
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].id, "hwmon0/temp2");
+
+        // The channel stays known, and reads again once it has a value.
+        write_fixture(root.path(), &[("hwmon0/temp1_input", "31000\n")]);
+        assert_eq!(hwmon.read().len(), 2);
+    }
+}
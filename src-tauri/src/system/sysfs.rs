@@ -0,0 +1,34 @@
+//! Small helpers for reading sysfs attribute files, shared by the hwmon, power-supply and DRM
+//! readers. Everything takes plain paths so the readers can run against fixture trees.
+
+use std::path::Path;
+
+/// The file's contents without surrounding whitespace; `None` if it can't be read or is empty.
+/// Many attributes exist but fail with `EIO`/`ENODATA` while a device is asleep or absent.
+pub fn read_trimmed(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn read_number(path: &Path) -> Option<f32> {
+    read_trimmed(path)?.parse::<f32>().ok()
+}
+
+/// Sort key under which `hwmon10` comes after `hwmon9`.
+pub fn natural_key(name: &str) -> (String, u32) {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (stem, number) = name.split_at(name.len() - digits);
+    (stem.to_string(), number.parse().unwrap_or(0))
+}
+
+/// Writes a fixture tree under `root`: each `(path, contents)` pair is a file, relative to
+/// `root`, with its parent directories created as needed.
+#[cfg(test)]
+pub fn write_fixture(root: &Path, files: &[(&str, &str)]) {
+    for (path, contents) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+}
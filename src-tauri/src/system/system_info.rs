@@ -0,0 +1,295 @@
+//! Slow-changing facts about the machine for "system card" widgets: what it is (hostname, OS,
+//! kernel, architecture), how long it has been up, how loaded it is and who is logged in. The
+//! reader polls, but hands back a payload at once only when one of those facts changed. The load
+//! and the process and thread counts move on nearly every poll, so a change in them alone is
+//! passed on at most once a minute, and one in the uptime never is: widgets count it on from
+//! `boot_time` themselves. The Linux-only readers take their paths as parameters so they can run
+//! against fixture files.
+
+// The procfs/logind readers build everywhere (they're plain file IO) but only Linux uses them.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate};
+use ts_rs::TS;
+
+use super::sysfs::read_trimmed;
+
+pub const LOADAVG_PATH: &str = "/proc/loadavg";
+/// One file per login session, written by systemd-logind.
+pub const SESSIONS_ROOT: &str = "/run/systemd/sessions";
+
+/// Least time between two payloads that only carry new load, process or thread counts.
+const COUNTERS_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct LoadAverage {
+    pub one: f32,
+    pub five: f32,
+    pub fifteen: f32,
+}
+
+/// Payload of `stream::system_info`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, TS)]
+#[ts(export, export_to = "../../src/ffi_types.ts")]
+pub struct SystemInfo {
+    pub hostname: Option<String>,
+    /// Distribution or product, e.g. `Fedora Linux`, `Windows`, `Darwin`.
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    /// Display form, e.g. `Linux (Fedora Linux 41)`, `Windows 11 Pro`.
+    pub long_os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    /// e.g. `x86_64`, `aarch64`.
+    pub arch: String,
+    /// Unix seconds.
+    pub boot_time: u64,
+    /// As of this payload, which isn't re-sent just because this grew.
+    pub uptime_secs: u64,
+    /// Rounded to two decimals, as `uptime` shows them. All zero on Windows, which has none.
+    /// Like the counts below, re-sent on its own at most once a minute.
+    pub load_average: LoadAverage,
+    pub process_count: u32,
+    /// Linux only.
+    pub thread_count: Option<u32>,
+    /// Who this app runs as.
+    pub user: Option<String>,
+    /// Everyone with a login session, sorted, each once. From systemd-logind on Linux; elsewhere
+    /// just `user`.
+    pub logged_in_users: Vec<String>,
+}
+
+/// The fields of a [`SystemInfo`] that identify the machine and who uses it; any change is
+/// published at once.
+#[derive(PartialEq)]
+struct Facts<'a> {
+    hostname: &'a Option<String>,
+    os_name: &'a Option<String>,
+    os_version: &'a Option<String>,
+    long_os_version: &'a Option<String>,
+    kernel_version: &'a Option<String>,
+    arch: &'a str,
+    boot_time: u64,
+    user: &'a Option<String>,
+    logged_in_users: &'a [String],
+}
+
+/// The fields that move on their own; see [`COUNTERS_INTERVAL`]. The uptime isn't one of them,
+/// as it is never worth a payload.
+#[derive(PartialEq)]
+struct Counters<'a> {
+    load_average: &'a LoadAverage,
+    process_count: u32,
+    thread_count: Option<u32>,
+}
+
+impl SystemInfo {
+    fn facts(&self) -> Facts<'_> {
+        Facts {
+            hostname: &self.hostname,
+            os_name: &self.os_name,
+            os_version: &self.os_version,
+            long_os_version: &self.long_os_version,
+            kernel_version: &self.kernel_version,
+            arch: &self.arch,
+            boot_time: self.boot_time,
+            user: &self.user,
+            logged_in_users: &self.logged_in_users,
+        }
+    }
+
+    fn counters(&self) -> Counters<'_> {
+        Counters {
+            load_average: &self.load_average,
+            process_count: self.process_count,
+            thread_count: self.thread_count,
+        }
+    }
+}
+
+/// Owned by the producer.
+pub struct SystemInfoReader {
+    system: sysinfo::System,
+    /// The last payload returned, and when.
+    last: Option<(SystemInfo, Instant)>,
+}
+
+impl SystemInfoReader {
+    pub fn new() -> Self {
+        Self {
+            system: sysinfo::System::new(),
+            last: None,
+        }
+    }
+
+    /// The current facts, if they changed since the last ones returned or the counters did and
+    /// are due again.
+    pub fn read_changed(&mut self) -> Option<SystemInfo> {
+        let info = self.read();
+        self.changed(info, Instant::now())
+    }
+
+    fn changed(&mut self, info: SystemInfo, now: Instant) -> Option<SystemInfo> {
+        if let Some((last, sent_at)) = &self.last {
+            let counters_due = now.duration_since(*sent_at) >= COUNTERS_INTERVAL
+                && info.counters() != last.counters();
+            if info.facts() == last.facts() && !counters_due {
+                return None;
+            }
+        }
+        self.last = Some((info.clone(), now));
+        Some(info)
+    }
+
+    fn read(&mut self) -> SystemInfo {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().without_tasks(),
+        );
+        let load = sysinfo::System::load_average();
+        let round = |v: f64| ((v * 100.0).round() / 100.0) as f32;
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok();
+
+        #[cfg(target_os = "linux")]
+        let (thread_count, logged_in_users) = (
+            thread_count(Path::new(LOADAVG_PATH)),
+            session_users(Path::new(SESSIONS_ROOT)),
+        );
+        #[cfg(not(target_os = "linux"))]
+        let (thread_count, logged_in_users) = (None, user.iter().cloned().collect());
+
+        SystemInfo {
+            hostname: sysinfo::System::host_name(),
+            os_name: sysinfo::System::name(),
+            os_version: sysinfo::System::os_version(),
+            long_os_version: sysinfo::System::long_os_version(),
+            kernel_version: sysinfo::System::kernel_version(),
+            arch: sysinfo::System::cpu_arch(),
+            boot_time: sysinfo::System::boot_time(),
+            uptime_secs: sysinfo::System::uptime(),
+            load_average: LoadAverage {
+                one: round(load.one),
+                five: round(load.five),
+                fifteen: round(load.fifteen),
+            },
+            process_count: self.system.processes().len() as u32,
+            thread_count,
+            user,
+            logged_in_users,
+        }
+    }
+}
+
+/// Total scheduling entities — threads — from the fourth field of `/proc/loadavg`
+/// (`0.30 0.27 0.23 2/1234 26835`: running/total).
+pub fn thread_count(path: &Path) -> Option<u32> {
+    let text = read_trimmed(path)?;
+    let (_, total) = text.split_whitespace().nth(3)?.split_once('/')?;
+    total.parse().ok()
+}
+
+/// Users of the sessions under `root` ([`SESSIONS_ROOT`] in the app), from each session file's
+/// `USER=` line. Display-manager greeters and background sessions (`CLASS=greeter`,
+/// `CLASS=background`) aren't people logged in.
+pub fn session_users(root: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut users: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let text = std::fs::read_to_string(entry.path()).ok()?;
+            let field = |key: &str| {
+                text.lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            };
+            if field("CLASS").is_some_and(|class| !class.starts_with("user")) {
+                return None;
+            }
+            field("USER").map(str::to_string)
+        })
+        .collect();
+    users.sort();
+    users.dedup();
+    users
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::sysfs::write_fixture;
+
+    #[test]
+    fn reads_the_thread_count_from_loadavg() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("loadavg");
+        std::fs::write(&path, "0.30 0.27 0.23 2/1234 26835\n").unwrap();
+        assert_eq!(thread_count(&path), Some(1234));
+
+        std::fs::write(&path, "0.30 0.27\n").unwrap();
+        assert_eq!(thread_count(&path), None);
+    }
+
+    #[test]
+    fn lists_each_user_with_a_session_once() {
+        let root = tempfile::tempdir().unwrap();
+        write_fixture(
+            root.path(),
+            &[
+                ("2", "UID=1000\nUSER=sam\nCLASS=user\n"),
+                ("5", "UID=1000\nUSER=sam\nCLASS=user-early\n"),
+                ("7", "UID=1001\nUSER=alex\nCLASS=user\n"),
+                ("c1", "UID=60578\nUSER=gdm\nCLASS=greeter\n"),
+                ("c2", "UID=1000\nUSER=sam\nCLASS=background\n"),
+            ],
+        );
+        assert_eq!(session_users(root.path()), ["alex", "sam"]);
+        assert!(session_users(&root.path().join("missing")).is_empty());
+    }
+
+    fn info(hostname: &str, load: f32, processes: u32, uptime_secs: u64) -> SystemInfo {
+        SystemInfo {
+            hostname: Some(hostname.to_string()),
+            os_name: Some("Fedora Linux".into()),
+            os_version: Some("41".into()),
+            long_os_version: Some("Linux (Fedora Linux 41)".into()),
+            kernel_version: Some("6.11.4".into()),
+            arch: "x86_64".into(),
+            boot_time: 1_700_000_000,
+            uptime_secs,
+            load_average: LoadAverage {
+                one: load,
+                five: 0.5,
+                fifteen: 0.4,
+            },
+            process_count: processes,
+            thread_count: Some(1234),
+            user: Some("sam".into()),
+            logged_in_users: vec!["sam".into()],
+        }
+    }
+
+    #[test]
+    fn counters_alone_are_passed_on_at_most_once_a_minute() {
+        let mut reader = SystemInfoReader::new();
+        let start = Instant::now();
+        let mut read = |info, secs| reader.changed(info, start + Duration::from_secs(secs));
+
+        assert!(read(info("desk", 0.3, 300, 100), 0).is_some());
+        assert!(read(info("desk", 0.7, 312, 105), 5).is_none());
+        assert!(read(info("desk", 1.2, 296, 110), 10).is_none());
+        // The facts go out at once, whatever the counters did.
+        let renamed = read(info("work", 1.2, 296, 115), 15).unwrap();
+        assert_eq!(renamed.hostname.as_deref(), Some("work"));
+        assert!(read(info("work", 0.9, 290, 170), 70).is_none());
+        let due = read(info("work", 0.9, 290, 175), 75).unwrap();
+        assert_eq!((due.load_average.one, due.process_count), (0.9, 290));
+        // Due again, but only the uptime moved.
+        assert!(read(info("work", 0.9, 290, 500), 400).is_none());
+    }
+}
@@ -24,15 +24,29 @@ const ALL_EVENTS: (keyof BackendEvents)[] = [
   "stream::cpu",
   "stream::memory",
   "stream::disks",
+  "stream::disk_io",
   "stream::networks",
   "stream::media",
+  "stream::media_tick",
   "stream::visualizer",
+  "stream::sensors",
+  "stream::power",
+  "stream::processes",
+  "stream::gpu",
+  "stream::system_info",
+  "visualizer::spectrum",
+  "visualizer::stereo",
+  "visualizer::waveform",
+  "visualizer::levels",
+  "visualizer::beat",
   "config::changed",
   "theme::changed",
   "layout::changed",
   "widget::updated",
   "preferences::changed",
   "preferences::preview",
+  "alert::raised",
+  "alert::cleared",
 ];
 
 const eventLog = logger("events");
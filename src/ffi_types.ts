@@ -1,6 +1,129 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type Config = { monitor: string | null, active_theme: string | null, active_layout: string | null, preferences: Preferences, };
+/**
+ * Payload of `MediaState::palette`.
+ */
+export type AlbumPalette = { 
+/**
+ * The colour covering most of the artwork.
+ */
+dominant: PaletteColor, 
+/**
+ * The most saturated colour of mid lightness; the dominant hue, saturated, when the artwork
+ * has none, and a mid grey when the artwork is greyscale.
+ */
+vibrant: PaletteColor, 
+/**
+ * The largest greyish colour of mid lightness; the dominant hue, desaturated, when the
+ * artwork has none.
+ */
+muted: PaletteColor, };
+
+export type AlertCondition = "above" | "below";
+
+/**
+ * Payload of `alert::raised` and `alert::cleared`.
+ */
+export type AlertEvent = { rule_id: string, name: string, 
+/**
+ * The metric that crossed, e.g. `hwmon2/temp1/temperature` for a `*/temperature` rule.
+ */
+metric: string, condition: AlertCondition, threshold: number, 
+/**
+ * At the moment of the transition.
+ */
+value: number, 
+/**
+ * Unix ms.
+ */
+at: bigint, };
+
+/**
+ * One entry of `Config::alerts`. CPU above 90 % for 30 s:
+ * `{ "id": "cpu-busy", "name": "CPU busy", "channel": "cpu", "metric": "global_usage",
+ * "condition": "above", "threshold": 90, "for_secs": 30 }`.
+ */
+export type AlertRule = { 
+/**
+ * Unique among the rules; carried by the `alert::*` events.
+ */
+id: string, 
+/**
+ * Shown as the notification's title.
+ */
+name: string, channel: StreamName, 
+/**
+ * A metric name as `get_stream_history` lists them (`global_usage`, `/home/available_space`,
+ * `hwmon2/temp1/temperature`). `*` matches any run of characters — `*/temperature` watches
+ * every temperature sensor, each as an alert of its own.
+ */
+metric: string, condition: AlertCondition, 
+/**
+ * In the metric's own unit: percent, bytes, °C.
+ */
+threshold: number, 
+/**
+ * How long the condition must hold before the alert is raised.
+ */
+for_secs: bigint, 
+/**
+ * How far back past `threshold` the value must go for the alert to clear.
+ */
+hysteresis: number, 
+/**
+ * Least time between two raises of the same alert.
+ */
+cooldown_secs: bigint, 
+/**
+ * Also show a desktop notification when raised. Linux only; elsewhere the alert is still
+ * emitted, just not shown.
+ */
+notify: boolean, };
+
+export type BatteryInfo = { 
+/**
+ * Kernel name, e.g. `BAT0`.
+ */
+name: string, model: string | null, 
+/**
+ * A device's own battery (wireless mouse, headset) rather than one powering this machine.
+ * Peripherals are listed but left out of the `PowerStats` totals.
+ */
+peripheral: boolean, state: BatteryState, 
+/**
+ * `0.0..=100.0`.
+ */
+capacity_percent: number | null, energy_now_wh: number | null, energy_full_wh: number | null, 
+/**
+ * Charge or discharge rate in watts, always positive; `state` says which.
+ */
+energy_rate_w: number | null, 
+/**
+ * Only while discharging.
+ */
+time_to_empty_secs: bigint | null, 
+/**
+ * Only while charging.
+ */
+time_to_full_secs: bigint | null, };
+
+export type BatteryState = "charging" | "discharging" | "full" | "not_charging" | "unknown";
+
+export type ChannelLevel = { 
+/**
+ * Largest absolute sample since the previous report.
+ */
+peak_dbfs: number, 
+/**
+ * RMS since the previous report; a full-scale sine reads about -3 dBFS.
+ */
+rms_dbfs: number, };
+
+export type Config = { monitor: string | null, active_theme: string | null, active_layout: string | null, preferences: Preferences, 
+/**
+ * Threshold alert rules, evaluated by `crate::alerts` whether or not a widget is showing.
+ */
+alerts: Array<AlertRule>, };
 
 export type Core = { name: string, frequency: bigint, usage: number, };
 
@@ -8,8 +131,74 @@ export type CpuStats = { global_usage: number, processors: Array<Processor>, tot
 
 export type DiskInfo = { name: string, mount_point: string, file_system: string, kind: string, total_space: bigint, available_space: bigint, };
 
+/**
+ * One entry of the `stream::disk_io` payload.
+ */
+export type DiskIoStats = { 
+/**
+ * Kernel block device name on Linux (`nvme0n1`, `sda`), the disk's name elsewhere.
+ */
+device: string, read_bytes_per_sec: number, write_bytes_per_sec: number, 
+/**
+ * `None` where the platform only reports bytes (everything but Linux).
+ */
+reads_per_sec: number | null, writes_per_sec: number | null, };
+
 export type FrequencyReading = { freq_hi: number, freq_lo: number, magnitude: number, };
 
+export type GpuBackend = "drm" | "nvml";
+
+/**
+ * Every reading is optional: which ones a GPU has depends on its driver and generation.
+ */
+export type GpuInfo = { 
+/**
+ * Stable across ticks: the DRM card (`card0`) or the NVML index (`nvml0`).
+ */
+id: string, vendor: GpuVendor, 
+/**
+ * Kernel driver (`amdgpu`, `i915`, `xe`), or `nvidia` for NVML.
+ */
+driver: string, 
+/**
+ * Marketing name, where the driver reports one.
+ */
+name: string | null, 
+/**
+ * `0.0..=100.0`. Intel's is derived from the time the GPU spent in its idle state since the
+ * previous tick, so it's `None` on the first.
+ */
+busy_percent: number | null, 
+/**
+ * Dedicated memory in bytes; integrated GPUs have none.
+ */
+vram_used: bigint | null, vram_total: bigint | null, temperature_c: number | null, power_w: number | null, 
+/**
+ * Current graphics clock.
+ */
+frequency_mhz: number | null, max_frequency_mhz: number | null, };
+
+/**
+ * What one backend could do on this machine.
+ */
+export type GpuSource = { backend: GpuBackend, 
+/**
+ * GPUs it reported on this read.
+ */
+devices: number, 
+/**
+ * Why it can't report at all (library missing, unsupported platform), or why it found no
+ * device. `None` when it reported some.
+ */
+unavailable: string | null, };
+
+/**
+ * Payload of `stream::gpu`.
+ */
+export type GpuStats = { gpus: Array<GpuInfo>, sources: Array<GpuSource>, };
+
+export type GpuVendor = "amd" | "intel" | "nvidia";
+
 export type GridPadding = { top: number, right: number, bottom: number, left: number, };
 
 export type GridSettings = { grid_rows: number, grid_cols: number, gap: number, padding: GridPadding, };
@@ -21,6 +210,40 @@ export type LayoutFile = { id: string, name: string, widgets: Array<WidgetConfig
  */
 export type LayoutInfo = { id: string, name: string, };
 
+export type LinkState = "up" | "down" | "unknown";
+
+export type LoadAverage = { one: number, five: number, fifteen: number, };
+
+/**
+ * Which controls the current player accepts, so widgets can grey out the others. Commands for
+ * a control the player lacks fail or are ignored, depending on the player.
+ */
+export type MediaCapabilities = { can_play: boolean, can_pause: boolean, can_go_next: boolean, can_go_previous: boolean, can_seek: boolean, can_set_volume: boolean, can_shuffle: boolean, can_repeat: boolean, };
+
+/**
+ * Players with quirks worth handling, recognised from their app id. Everything else is
+ * `Other`.
+ */
+export type MediaClient = "spotify" | "apple_music" | "firefox" | "chrome" | "edge" | "vlc" | "other";
+
+/**
+ * One row of `list_media_sessions`.
+ */
+export type MediaSession = { 
+/**
+ * Pass to `select_media_session`. The MPRIS bus name on Linux, the app user model id on
+ * Windows.
+ */
+id: string, source_app: SourceApp, state: PlaybackState, title: string, 
+/**
+ * The session `stream::media` is following.
+ */
+followed: boolean, 
+/**
+ * Pinned with `select_media_session`.
+ */
+pinned: boolean, };
+
 export type MediaState = { 
 /**
  * False when no media session is active; all other fields are empty/zero.
@@ -29,27 +252,299 @@ active: boolean, playing: boolean, title: string, artist: string, album: string,
 /**
  * Base64-encoded thumbnail image (JPEG or PNG).
  */
-album_art_b64: string | null, position_ms: bigint, duration_ms: bigint, };
+album_art_b64: string | null, 
+/**
+ * Colours of the album art; `None` without art, or with art that won't decode.
+ */
+palette: AlbumPalette | null, 
+/**
+ * As of `position_updated_at`. While playing, the position now is
+ * `position_ms + (now - position_updated_at) * playback_rate`, up to `duration_ms`; states
+ * aren't re-sent just because it moved on.
+ */
+position_ms: bigint, 
+/**
+ * Unix ms.
+ */
+position_updated_at: bigint, 
+/**
+ * 1.0 at normal speed.
+ */
+playback_rate: number, duration_ms: bigint, 
+/**
+ * What's playing, where the player says; browsers give the page.
+ */
+url: string | null, 
+/**
+ * The player's own volume, `0.0..=1.0`. `None` where the platform doesn't expose it
+ * (Windows).
+ */
+volume: number | null, 
+/**
+ * `None` when the player has no shuffle.
+ */
+shuffle: boolean | null, 
+/**
+ * `None` when the player has no repeat.
+ */
+repeat_mode: RepeatMode | null, capabilities: MediaCapabilities, 
+/**
+ * The app the session belongs to; `None` when inactive.
+ */
+source_app: SourceApp | null, };
+
+/**
+ * Payload of `stream::media_tick`.
+ */
+export type MediaTick = { position_ms: bigint, duration_ms: bigint, playing: boolean, };
 
 export type MemoryStats = { used: bigint, total: bigint, swap_used: bigint, swap_total: bigint, };
 
-export type NetworkInterfaceInfo = { name: string, received: bigint, transmitted: bigint, total_received: bigint, total_transmitted: bigint, mac_address: string, };
+/**
+ * One metric's recorded buckets, as returned by `query_metrics`. Columnar like
+ * `MetricSeries`: `timestamps[i]` is the start (Unix ms) of the bucket `min[i]`, `avg[i]` and
+ * `max[i]` describe. Buckets with no samples are left out.
+ */
+export type MetricBuckets = { metric: string, 
+/**
+ * Width of every bucket — the requested one, or the tier's resolution if that's coarser.
+ */
+bucket_ms: bigint, timestamps: Array<bigint>, min: Array<number>, avg: Array<number>, max: Array<number>, };
+
+/**
+ * One metric's recorded history, as returned by `get_stream_history`. Every metric published in
+ * the same tick shares its timestamp, so series line up by timestamp.
+ */
+export type MetricSeries = { metric: string, 
+/**
+ * Unix milliseconds, oldest first.
+ */
+timestamps: Array<bigint>, values: Array<number>, };
+
+export type NetworkInterfaceInfo = { name: string, 
+/**
+ * Bytes per second since the previous tick.
+ */
+received_per_sec: number, transmitted_per_sec: number, total_received: bigint, total_transmitted: bigint, total_packets_received: bigint, total_packets_transmitted: bigint, total_errors_received: bigint, total_errors_transmitted: bigint, 
+/**
+ * `None` for interfaces without one (loopback, most tunnels).
+ */
+mac_address: string | null, 
+/**
+ * CIDR notation, e.g. `192.168.1.20/24`.
+ */
+ipv4: Array<string>, ipv6: Array<string>, mtu: bigint, link: LinkState, loopback: boolean, 
+/**
+ * No hardware behind it: bridges, VPN tunnels, container veths. On Linux that's anything
+ * without a backing device in sysfs; elsewhere, anything without a MAC address.
+ */
+virtual_device: boolean, 
+/**
+ * Only for wireless interfaces.
+ */
+wireless: WirelessInfo | null, };
+
+export type PaletteColor = { 
+/**
+ * `#rrggbb`.
+ */
+color: string, 
+/**
+ * Near-white or near-black in the same hue, whichever contrasts more with `color`, for text
+ * drawn on it.
+ */
+foreground: string, 
+/**
+ * Share of the artwork's pixels, `0.0..=1.0`; 0 for colours made up as a fallback.
+ */
+population: number, };
+
+export type PlaybackState = "playing" | "paused" | "stopped";
+
+/**
+ * Payload of `stream::power`.
+ */
+export type PowerStats = { 
+/**
+ * Whether any mains or USB supply is online; `None` if the machine reports none (most
+ * desktops).
+ */
+ac_online: boolean | null, batteries: Array<BatteryInfo>, 
+/**
+ * Energy-weighted charge across the system batteries, `0.0..=100.0`.
+ */
+capacity_percent: number | null, 
+/**
+ * Across the system batteries, at their combined rate.
+ */
+time_to_empty_secs: bigint | null, time_to_full_secs: bigint | null, };
 
 export type Preferences = { rounded: boolean, widget_transparent: boolean, background_transparent: boolean, font_scale: number, };
 
+export type ProcessField = "cpu" | "memory" | "user";
+
+export type ProcessInfo = { pid: number, name: string, 
+/**
+ * Percent of one core, as `top` reports it, so a busy multithreaded process can exceed 100.
+ * `None` unless a query asked for CPU.
+ */
+cpu_percent: number | null, 
+/**
+ * Resident set size in bytes. `None` unless a query asked for memory.
+ */
+rss: bigint | null, 
+/**
+ * Unix seconds.
+ */
+start_time: bigint, 
+/**
+ * Owner's login name. `None` unless a query asked for it, or if it can't be resolved.
+ */
+user: string | null, };
+
+/**
+ * Payload of `stream::processes`. Each list is as long as the largest `top` any query asked
+ * for with that sort, and empty if none did; widgets take their own prefix.
+ */
+export type ProcessList = { by_cpu: Array<ProcessInfo>, by_memory: Array<ProcessInfo>, };
+
+export type ProcessQuery = { 
+/**
+ * How many processes, 1–100.
+ */
+top: number, sort_by: ProcessSort, fields: Array<ProcessField>, };
+
+export type ProcessSort = "cpu" | "memory";
+
 export type Processor = { brand: string, cores: Array<Core>, };
 
+export type RepeatMode = "off" | "track" | "playlist";
+
 export type Scope = { "Widget": string } | { "Group": string };
 
+export type SensorKind = "temperature" | "fan" | "voltage";
+
+export type SensorReading = { 
+/**
+ * Stable across ticks, e.g. `hwmon2/temp1`; use it as the React key.
+ */
+id: string, 
+/**
+ * Chip or driver the sensor belongs to, e.g. `k10temp`, `nvme`, `nct6798`.
+ */
+device: string, 
+/**
+ * The driver's label (`Tctl`, `Composite`, `CPU Fan`), or the channel name if it has none.
+ */
+label: string, kind: SensorKind, value: number, 
+/**
+ * Upper warning threshold, in the same unit as `value`.
+ */
+max: number | null, 
+/**
+ * Critical threshold (hardware shutdown or throttling), in the same unit as `value`.
+ */
+critical: number | null, };
+
+export type SourceApp = { 
+/**
+ * The MPRIS desktop entry (`firefox`) on Linux, the app user model id on Windows.
+ */
+id: string, name: string, client: MediaClient, 
+/**
+ * From the media rules: an icon name or image URL for widgets to show.
+ */
+icon: string | null, };
+
+/**
+ * What a correlation meter widget draws.
+ */
+export type StereoImage = { 
+/**
+ * Pearson correlation of left and right, `-1.0..=1.0`: +1 mono, 0 unrelated (wide or
+ * decorrelated), -1 out of phase. 0 during silence.
+ */
+correlation: number, 
+/**
+ * Side energy as a share of mid + side energy, `0.0..=1.0`: 0 mono, 0.5 unrelated channels,
+ * 1 fully out of phase.
+ */
+width: number, };
+
 /**
  * Single source of truth for stream/subscription channel names, on both sides of the IPC
  * boundary — ts-rs-exported and imported directly by the frontend (`src/ipc/events.ts`)
- * instead of hand-mirrored, the same pattern `file.rs`'s `Scope` enum already uses. `cpu`/
- * `memory`/`disks`/`networks` share one task (`system::run_resource_loop`); `media`/
- * `visualizer` keep their own dedicated task/thread — see CLAUDE.md's "Subscription channels"
- * section.
+ * instead of hand-mirrored, the same pattern `file.rs`'s `Scope` enum already uses. Each name
+ * is served by one [`crate::channel::Channel`] implementation.
  */
-export type StreamName = "cpu" | "memory" | "disks" | "networks" | "media" | "visualizer";
+export type StreamName = "cpu" | "memory" | "disks" | "disk_io" | "networks" | "media" | "media_tick" | "visualizer" | "sensors" | "power" | "processes" | "gpu" | "system_info";
+
+/**
+ * One stream's row in `get_stream_stats`.
+ */
+export type StreamStats = { channel: StreamName, 
+/**
+ * Widgets subscribed. The backend's own subscriptions (alert rules, metric recording) keep
+ * the producer running too, but aren't counted.
+ */
+subscribers: number, 
+/**
+ * Whether the producer is currently running (subscribed, or lingering).
+ */
+running: boolean, 
+/**
+ * Times the producer has started since launch — for the visualizer, each one opens a
+ * new audio capture.
+ */
+created: bigint, 
+/**
+ * Times it has stopped. Both climbing together on every edit-mode toggle means the linger
+ * is too short.
+ */
+destroyed: bigint, };
+
+/**
+ * Payload of `stream::system_info`.
+ */
+export type SystemInfo = { hostname: string | null, 
+/**
+ * Distribution or product, e.g. `Fedora Linux`, `Windows`, `Darwin`.
+ */
+os_name: string | null, os_version: string | null, 
+/**
+ * Display form, e.g. `Linux (Fedora Linux 41)`, `Windows 11 Pro`.
+ */
+long_os_version: string | null, kernel_version: string | null, 
+/**
+ * e.g. `x86_64`, `aarch64`.
+ */
+arch: string, 
+/**
+ * Unix seconds.
+ */
+boot_time: bigint, 
+/**
+ * As of this payload, which isn't re-sent just because this grew.
+ */
+uptime_secs: bigint, 
+/**
+ * Rounded to two decimals, as `uptime` shows them. All zero on Windows, which has none.
+ * Like the counts below, re-sent on its own at most once a minute.
+ */
+load_average: LoadAverage, process_count: number, 
+/**
+ * Linux only.
+ */
+thread_count: number | null, 
+/**
+ * Who this app runs as.
+ */
+user: string | null, 
+/**
+ * Everyone with a login session, sorted, each once. From systemd-logind on Linux; elsewhere
+ * just `user`.
+ */
+logged_in_users: Array<string>, };
 
 export type ThemeData = { id: string, name: string, vars: Array<ThemeVar>, color_scheme: string, };
 
@@ -64,6 +559,89 @@ export type ThemeInfo = { id: string, name: string, };
  */
 export type ThemeVar = { "type": "color", label: string, value: string, } | { "type": "font", label: string, value: Array<string>, };
 
+/**
+ * Payload of `visualizer::beat`, emitted once per detected onset.
+ */
+export type VisualizerBeat = { 
+/**
+ * How far the onset cleared the adaptive threshold, `0.0..=1.0`.
+ */
+strength: number, 
+/**
+ * Tempo estimate from recent onsets; `None` until there are enough of them.
+ */
+bpm: number | null, 
+/**
+ * How consistently recent onsets land on the `bpm` grid, `0.0..=1.0`.
+ */
+confidence: number, };
+
+export type VisualizerConfig = { 
+/**
+ * Number of log-spaced bars, 1–512.
+ */
+bins: number, min_freq: number, 
+/**
+ * Clamped to the capture's Nyquist frequency.
+ */
+max_freq: number, weighting: Weighting, 
+/**
+ * Time constant for rising bars, in milliseconds.
+ */
+attack_ms: number, 
+/**
+ * Time constant for falling bars, in milliseconds.
+ */
+decay_ms: number, 
+/**
+ * Spectra per second, 1–120. Waveform and levels are reported at the same rate.
+ */
+frame_rate: number, 
+/**
+ * Emit `visualizer::spectrum`. Turn off for widgets that only want the outputs below.
+ */
+spectrum: boolean, 
+/**
+ * Emit `visualizer::stereo`: left and right spectra (sharing one normalisation, so the
+ * louder side draws taller) plus phase correlation. Mono sources report identical sides.
+ */
+stereo: boolean, 
+/**
+ * Points per `visualizer::waveform` payload, up to 2048; 0 disables the waveform.
+ */
+waveform_points: number, 
+/**
+ * Emit per-channel peak/RMS levels on `visualizer::levels`.
+ */
+levels: boolean, 
+/**
+ * Run beat detection and emit `visualizer::beat` on each onset. Beats are not tagged with
+ * an id — every widget asking for them receives the same events.
+ */
+beats: boolean, };
+
+/**
+ * Payload of `visualizer::levels`, one entry per captured channel (in capture order).
+ */
+export type VisualizerLevels = { id: string, channels: Array<ChannelLevel>, };
+
+/**
+ * Payload of `visualizer::spectrum`: one configured analyzer's output.
+ */
+export type VisualizerSpectrum = { id: string, readings: Array<FrequencyReading>, };
+
+/**
+ * Payload of `visualizer::stereo`.
+ */
+export type VisualizerStereo = { id: string, left: Array<FrequencyReading>, right: Array<FrequencyReading>, image: StereoImage, };
+
+/**
+ * Payload of `visualizer::waveform`: mono samples in `-1.0..=1.0`, oldest first.
+ */
+export type VisualizerWaveform = { id: string, samples: Array<number>, };
+
+export type Weighting = "none" | "a" | "c";
+
 export type WidgetConfig = { id: string, 
 /**
  * The identifier of the widget type, e.g. "cpu", "memory", "custom-chart", etc.
@@ -89,3 +667,13 @@ col: number,
  * 1-indexed CSS grid row start.
  */
 row: number, col_span: number, row_span: number, };
+
+export type WirelessInfo = { 
+/**
+ * `None` without NetworkManager, or while not associated.
+ */
+ssid: string | null, signal_dbm: number | null, 
+/**
+ * Driver-reported link quality, `0.0..=100.0`.
+ */
+link_quality: number | null, };
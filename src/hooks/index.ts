@@ -1,4 +1,7 @@
 export { useSubscription } from "./useSubscription";
+export type { SubscriptionOptions } from "./useSubscription";
 export { useHistory } from "./useHistory";
 export { useSmoothed } from "./useSmoothed";
 export { useClock } from "./useClock";
+export { useStreamHistory, historyTicks } from "./useStreamHistory";
+export type { HistoryTick } from "./useStreamHistory";
@@ -0,0 +1,78 @@
+import { useEffect, useRef, useState } from "react";
+import type { MetricSeries } from "../ffi_types";
+import type { StreamName } from "../ipc";
+import { ipc } from "../ipc";
+import { useStreamMock } from "../preview/PreviewEnvironment";
+
+/** One recorded tick: every metric the stream published at that moment. */
+export type HistoryTick = Record<string, number>;
+
+/** Pivots `get_stream_history` series into ticks, oldest first. */
+export function historyTicks(series: MetricSeries[]): HistoryTick[] {
+  const ticks = new Map<number, HistoryTick>();
+  for (const { metric, timestamps, values } of series) {
+    timestamps.forEach((t, i) => {
+      const at = Number(t);
+      const tick = ticks.get(at) ?? {};
+      tick[metric] = values[i];
+      ticks.set(at, tick);
+    });
+  }
+  return [...ticks.entries()].sort(([a], [b]) => a - b).map(([, tick]) => tick);
+}
+
+/**
+ * `useHistory`, seeded from the backend's recorded history of `channel` so a
+ * graph starts full after a window reload or a remount instead of filling up
+ * one tick at a time. `value` is `null` until the stream's first payload;
+ * `fromTick` reduces a recorded tick to the same number the widget derives
+ * from a live payload. Preview renders skip the seed and fill from the mock.
+ */
+export function useStreamHistory(
+  channel: StreamName,
+  value: number | null,
+  fromTick: (tick: HistoryTick) => number,
+  length = 60,
+): readonly number[] {
+  const mock = useStreamMock();
+  const [history, setHistory] = useState<number[]>(() => new Array(length).fill(0));
+  const fromTickRef = useRef(fromTick);
+  fromTickRef.current = fromTick;
+  const lengthRef = useRef(length);
+  lengthRef.current = length;
+  // Live values that arrived while the seed request was in flight, so the seed
+  // doesn't drop them.
+  const pending = useRef<number[] | null>(null);
+  const seeded = useRef(false);
+
+  useEffect(() => {
+    if (value === null) return;
+    const trim = (h: number[]) => h.slice(Math.max(0, h.length - lengthRef.current));
+    if (seeded.current || mock) {
+      pending.current?.push(value);
+      setHistory((h) => trim([...h, value]));
+      return;
+    }
+    seeded.current = true;
+    pending.current = [];
+    ipc
+      .getStreamHistory(channel)
+      .then((series) => {
+        // The recorded history already ends with the payload that produced
+        // `value`, so only what arrived after it is appended.
+        const recorded = historyTicks(series).map(fromTickRef.current);
+        const live = pending.current ?? [];
+        setHistory((h) =>
+          recorded.length > 0 ? trim([...h, ...recorded, ...live]) : trim([...h, value, ...live]),
+        );
+      })
+      .catch(() => setHistory((h) => trim([...h, value, ...(pending.current ?? [])])))
+      .finally(() => {
+        pending.current = null;
+      });
+    // lengthRef/fromTickRef intentionally omitted — refs, not reactive state
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [value, channel, mock]);
+
+  return history;
+}
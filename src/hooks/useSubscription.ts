@@ -1,13 +1,36 @@
 import { useEffect, useRef, useState } from "react";
 import type { BackendEvents, StreamName } from "../ipc";
-import { ipcListen, subscribeChannel, unsubscribeChannel } from "../ipc";
+import {
+  ACKED_EVENTS,
+  ackFrame,
+  ipcListen,
+  subscribeChannel,
+  unsubscribeChannel,
+} from "../ipc";
 import { useStreamMock } from "../preview/PreviewEnvironment";
 
-export function useSubscription<T extends StreamName>(channelName: T): {
+export interface SubscriptionOptions {
+  /**
+   * How often this subscriber wants updates. The stream refreshes at the fastest interval any
+   * subscriber asked for; omit for its default. Only the polled resource streams honour it.
+   */
+  intervalMs?: number;
+  /**
+   * The id this subscriber registered per-subscriber state under (a visualizer config), so
+   * the backend drops it when the subscriber unsubscribes.
+   */
+  id?: string;
+}
+
+export function useSubscription<T extends StreamName>(
+  channelName: T,
+  options: SubscriptionOptions = {},
+): {
   data: BackendEvents[`stream::${T}`] | null;
   loading: boolean;
 } {
   type D = BackendEvents[`stream::${T}`];
+  const { intervalMs, id } = options;
   const mock = useStreamMock();
   const [data, setData] = useState<D | null>(null);
   const [loading, setLoading] = useState(true);
@@ -36,7 +59,7 @@ export function useSubscription<T extends StreamName>(channelName: T): {
     let unlisten: (() => void) | null = null;
     let cancelled = false;
 
-    subscribeChannel(channelName).then(({ last_value, is_first_subscriber }) => {
+    subscribeChannel(channelName, intervalMs).then(({ last_value, is_first_subscriber }) => {
       if (cancelled) return;
       if (last_value !== null && last_value !== undefined) {
         setData(last_value as D);
@@ -46,9 +69,11 @@ export function useSubscription<T extends StreamName>(channelName: T): {
       }
     });
 
-    ipcListen(`stream::${channelName}` as keyof BackendEvents, (value) => {
+    const event = `stream::${channelName}` as keyof BackendEvents;
+    ipcListen(event, (value) => {
       setData(value as D);
       setLoading(false);
+      if (ACKED_EVENTS.has(event)) ackFrame(event);
     }).then((fn) => {
       unlisten = fn;
     });
@@ -56,10 +81,10 @@ export function useSubscription<T extends StreamName>(channelName: T): {
     return () => {
       cancelled = true;
       subscribed.current = false;
-      unsubscribeChannel(channelName);
+      unsubscribeChannel(channelName, intervalMs, id);
       unlisten?.();
     };
-  }, [channelName, mock]);
+  }, [channelName, intervalMs, id, mock]);
 
   return { data, loading };
 }
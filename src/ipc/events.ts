@@ -1,15 +1,28 @@
 import type {
+  AlertEvent,
   Config,
   CpuStats,
   MemoryStats,
   DiskInfo,
+  DiskIoStats,
   NetworkInterfaceInfo,
   MediaState,
+  MediaTick,
   FrequencyReading,
+  SensorReading,
+  PowerStats,
+  ProcessList,
+  GpuStats,
+  SystemInfo,
   LayoutFile,
   Preferences,
   WidgetConfig,
   StreamName,
+  VisualizerBeat,
+  VisualizerLevels,
+  VisualizerSpectrum,
+  VisualizerStereo,
+  VisualizerWaveform,
 } from "../ffi_types";
 
 export type { StreamName } from "../ffi_types";
@@ -18,30 +31,70 @@ export type BackendEvents = {
   "stream::cpu": CpuStats;
   "stream::memory": MemoryStats;
   "stream::disks": DiskInfo[];
+  "stream::disk_io": DiskIoStats[];
   "stream::networks": NetworkInterfaceInfo[];
   "stream::media": MediaState;
+  "stream::media_tick": MediaTick;
   "stream::visualizer": FrequencyReading[];
+  "stream::sensors": SensorReading[];
+  "stream::power": PowerStats;
+  "stream::processes": ProcessList;
+  "stream::gpu": GpuStats;
+  "stream::system_info": SystemInfo;
+  "visualizer::spectrum": VisualizerSpectrum;
+  "visualizer::stereo": VisualizerStereo;
+  "visualizer::waveform": VisualizerWaveform;
+  "visualizer::levels": VisualizerLevels;
+  "visualizer::beat": VisualizerBeat;
   "config::changed": Config;
   "theme::changed": { id: string; css: string };
   "layout::changed": { id: string; layout: LayoutFile };
   "widget::updated": { id: string; config: WidgetConfig };
   "preferences::changed": Preferences;
   "preferences::preview": Preferences;
+  "alert::raised": AlertEvent;
+  "alert::cleared": AlertEvent;
 };
 
 export const EVENT_NAMES = [
   "stream::cpu",
   "stream::memory",
   "stream::disks",
+  "stream::disk_io",
   "stream::networks",
   "stream::media",
+  "stream::media_tick",
   "stream::visualizer",
+  "stream::sensors",
+  "stream::power",
+  "stream::processes",
+  "stream::gpu",
+  "stream::system_info",
+  "visualizer::spectrum",
+  "visualizer::stereo",
+  "visualizer::waveform",
+  "visualizer::levels",
+  "visualizer::beat",
   "config::changed",
   "theme::changed",
   "layout::changed",
   "widget::updated",
   "preferences::changed",
   "preferences::preview",
+  "alert::raised",
+  "alert::cleared",
 ] as const satisfies readonly (keyof BackendEvents)[];
 
 export type StreamEvents = { [K in StreamName]: BackendEvents[`stream::${K}`] };
+
+/**
+ * Events the backend delivers through its frame gate: one frame in flight, newest-wins.
+ * Listeners must `ackFrame` each one or delivery slows to one frame per (backing-off) timeout.
+ */
+export const ACKED_EVENTS: ReadonlySet<keyof BackendEvents> = new Set([
+  "stream::visualizer",
+  "visualizer::spectrum",
+  "visualizer::stereo",
+  "visualizer::waveform",
+  "visualizer::levels",
+]);
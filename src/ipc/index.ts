@@ -1,4 +1,5 @@
 export type { BackendEvents, StreamName, StreamEvents } from "./events";
+export { ACKED_EVENTS } from "./events";
 export { ipcListen } from "./listen";
-export { ipc, subscribeChannel, unsubscribeChannel } from "./invoke";
+export { ackFrame, ipc, subscribeChannel, unsubscribeChannel } from "./invoke";
 export type { SubscribeResult } from "./invoke";
@@ -1,12 +1,21 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
+  AlertEvent,
+  AlertRule,
   Config,
   LayoutFile,
   LayoutInfo,
+  MediaSession,
+  MetricBuckets,
+  MetricSeries,
   Preferences,
+  ProcessQuery,
+  RepeatMode,
   Scope,
+  StreamStats,
   ThemeData,
   ThemeInfo,
+  VisualizerConfig,
   WidgetConfig,
 } from "../ffi_types";
 import type { BackendEvents, StreamName } from "./events";
@@ -16,14 +25,46 @@ export interface SubscribeResult<T extends StreamName> {
   last_value: BackendEvents[`stream::${T}`] | null;
 }
 
+/**
+ * `intervalMs` asks the stream to update at least that often; it polls at the fastest rate any
+ * live subscriber asked for. Pass the same value to `unsubscribeChannel`.
+ */
 export async function subscribeChannel<T extends StreamName>(
   channel: T,
+  intervalMs?: number,
 ): Promise<SubscribeResult<T>> {
-  return invoke<SubscribeResult<T>>("subscribe_channel", { channel });
+  return invoke<SubscribeResult<T>>("subscribe_channel", {
+    channel,
+    intervalMs: intervalMs ?? null,
+  });
 }
 
-export async function unsubscribeChannel(channel: StreamName): Promise<void> {
-  return invoke("unsubscribe_channel", { channel });
+/**
+ * `id` is the id this subscriber registered per-subscriber state under (`setVisualizerConfig`),
+ * which the backend drops along with the subscription.
+ */
+export async function unsubscribeChannel(
+  channel: StreamName,
+  intervalMs?: number,
+  id?: string,
+): Promise<void> {
+  return invoke("unsubscribe_channel", {
+    channel,
+    intervalMs: intervalMs ?? null,
+    id: id ?? null,
+  });
+}
+
+/**
+ * Acknowledges a frame from an `ACKED_EVENTS` event so the backend sends the next one. Called
+ * from an animation frame, so a hidden or sleeping webview stops acking and the backend holds
+ * only the newest frame instead of queueing a backlog. `id` is the tag on `visualizer::*`
+ * payloads.
+ */
+export function ackFrame(event: keyof BackendEvents, id?: string): void {
+  requestAnimationFrame(() => {
+    invoke("ack_frame", { event, id: id ?? null }).catch(() => {});
+  });
 }
 
 export const ipc = {
@@ -51,6 +92,11 @@ export const ipc = {
     invoke<void>("set_preferences", { prefs }),
   previewPreferences: (prefs: Preferences) =>
     invoke<void>("preview_preferences", { prefs }),
+
+  // Replaces `Config::alerts`; rejects duplicate rule ids.
+  setAlertRules: (rules: AlertRule[]) => invoke<void>("set_alert_rules", { rules }),
+  getActiveAlerts: () => invoke<AlertEvent[]>("get_active_alerts"),
+
   generateTheme: (seedHex: string) =>
     invoke<void>("generate_theme", { seedHex }),
 
@@ -87,6 +133,52 @@ export const ipc = {
   togglePlayback: () => invoke<void>("toggle_playback"),
   nextTrack: () => invoke<void>("next_track"),
   prevTrack: () => invoke<void>("prev_track"),
+  seekMedia: (positionMs: number) => invoke<void>("seek_media", { positionMs }),
+  // `0..1`; rejects on Windows, where sessions don't expose a volume.
+  setVolume: (volume: number) => invoke<void>("set_volume", { volume }),
+  setShuffle: (shuffle: boolean) => invoke<void>("set_shuffle", { shuffle }),
+  setRepeat: (mode: RepeatMode) => invoke<void>("set_repeat", { mode }),
+  listMediaSessions: () => invoke<MediaSession[]>("list_media_sessions"),
+  // Pins `stream::media` and the controls to one session; `null` goes back to following
+  // whichever is playing.
+  selectMediaSession: (id: string | null) => invoke<void>("select_media_session", { id }),
+
+  getStreamStats: () => invoke<StreamStats[]>("get_stream_stats"),
+  // `since` is Unix ms; omit it for everything the backend kept.
+  getStreamHistory: (channel: StreamName, since?: number) =>
+    invoke<MetricSeries[]>("get_stream_history", { channel, since: since ?? null }),
+  // Needs `--record-metrics`; rejects otherwise. Times are Unix ms; `bucketMs` is rounded up to
+  // the resolution of the tier covering `from`. Omit `metrics` for all of the channel's.
+  queryMetrics: (
+    channel: StreamName,
+    from: number,
+    to: number,
+    bucketMs?: number,
+    metrics?: string[],
+  ) =>
+    invoke<MetricBuckets[]>("query_metrics", {
+      channel,
+      from,
+      to,
+      bucketMs: bucketMs ?? null,
+      metrics: metrics ?? null,
+    }),
+  // Dev mode only; both reject otherwise.
+  dumpChannels: () => invoke<Record<string, unknown>>("dump_channels"),
+  resetChannel: (channel: StreamName) => invoke<void>("reset_channel", { channel }),
+
+  // Per-widget visualizer analysis; results arrive on `visualizer::spectrum` tagged with `id`.
+  // Subscribe to `visualizer` with the same `id` so the config goes when the widget does.
+  setVisualizerConfig: (id: string, config: Partial<VisualizerConfig>) =>
+    invoke<void>("set_visualizer_config", { id, config }),
+  clearVisualizerConfig: (id: string) =>
+    invoke<void>("clear_visualizer_config", { id }),
+
+  // Per-widget process query; every widget reads the shared `stream::processes` lists.
+  // Subscribe to `processes` with the same `id` so the query goes when the widget does.
+  setProcessQuery: (id: string, query: Partial<ProcessQuery>) =>
+    invoke<void>("set_process_query", { id, query }),
+  clearProcessQuery: (id: string) => invoke<void>("clear_process_query", { id }),
 
   exitProgram: () => invoke<void>("exit_program"),
 
@@ -20,6 +20,25 @@ function wave(t: number, periodMs: number, phase = 0): number {
 const GiB = 1024 ** 3;
 const CORE_COUNT = 8;
 
+const MOCK_PROCESSES = [
+  { pid: 2231, name: "firefox", cpu: 38, rssMiB: 1850 },
+  { pid: 4410, name: "code", cpu: 22, rssMiB: 1320 },
+  { pid: 1187, name: "Xorg", cpu: 9, rssMiB: 240 },
+  { pid: 3302, name: "pipewire", cpu: 4, rssMiB: 36 },
+  { pid: 5120, name: "desk-disp", cpu: 6, rssMiB: 180 },
+  { pid: 2875, name: "discord", cpu: 12, rssMiB: 620 },
+];
+
+function mockDiskIo(t: number, scale: number, phase: number) {
+  const busy = wave(t, 13_000, phase) * wave(t, 3100, phase + 1);
+  return {
+    read_bytes_per_sec: scale * 180e6 * busy,
+    write_bytes_per_sec: scale * 40e6 * wave(t, 21_000, phase + 2),
+    reads_per_sec: scale * 1400 * busy,
+    writes_per_sec: scale * 300 * wave(t, 21_000, phase + 2),
+  };
+}
+
 const TRACKS = [
   {
     title: "Weightless Horizon",
@@ -41,6 +60,18 @@ const TRACKS = [
   },
 ];
 
+/** The track playing at `t` and how far into it. Piecewise track rotation:
+ *  pure functions can fabricate "state". */
+function mockTrack(t: number) {
+  const cycle = TRACKS.reduce((s, x) => s + x.duration, 0);
+  let position = t % cycle;
+  for (const track of TRACKS) {
+    if (position < track.duration) return { track, position };
+    position -= track.duration;
+  }
+  return { track: TRACKS[TRACKS.length - 1], position };
+}
+
 const VISUALIZER_BINS = 4096;
 
 export const MOCK_STREAMS: MockStreams = {
@@ -105,20 +136,35 @@ export const MOCK_STREAMS: MockStreams = {
     ],
   },
 
+  disk_io: {
+    cadence: 1000,
+    generate: (t) => [
+      { device: "nvme0n1", ...mockDiskIo(t, 1, 0) },
+      { device: "sda", ...mockDiskIo(t, 0.2, 2) },
+    ],
+  },
+
   networks: {
     cadence: 1000,
     generate: (t) => [
       {
         name: "Mock Ethernet",
-        received: BigInt(
-          Math.round(6e6 * (0.2 + 0.6 * wave(t, 8000) * wave(t, 2300, 1))),
-        ),
-        transmitted: BigInt(
-          Math.round(8e5 * (0.15 + 0.5 * wave(t, 11_000, 2))),
-        ),
+        received_per_sec: 6e6 * (0.2 + 0.6 * wave(t, 8000) * wave(t, 2300, 1)),
+        transmitted_per_sec: 8e5 * (0.15 + 0.5 * wave(t, 11_000, 2)),
         total_received: BigInt(Math.floor(t * 700)),
         total_transmitted: BigInt(Math.floor(t * 90)),
+        total_packets_received: BigInt(Math.floor(t * 0.6)),
+        total_packets_transmitted: BigInt(Math.floor(t * 0.2)),
+        total_errors_received: BigInt(0),
+        total_errors_transmitted: BigInt(0),
         mac_address: "00:11:22:33:44:55",
+        ipv4: ["192.168.1.20/24"],
+        ipv6: ["fe80::211:22ff:fe33:4455/64"],
+        mtu: BigInt(1500),
+        link: "up",
+        loopback: false,
+        virtual_device: false,
+        wireless: null,
       },
     ],
   },
@@ -126,17 +172,7 @@ export const MOCK_STREAMS: MockStreams = {
   media: {
     cadence: 500,
     generate: (t) => {
-      // Piecewise track rotation: pure functions can fabricate "state".
-      const cycle = TRACKS.reduce((s, x) => s + x.duration, 0);
-      let tt = t % cycle;
-      let track = TRACKS[TRACKS.length - 1];
-      for (const candidate of TRACKS) {
-        if (tt < candidate.duration) {
-          track = candidate;
-          break;
-        }
-        tt -= candidate.duration;
-      }
+      const { track, position } = mockTrack(t);
       return {
         active: true,
         playing: true,
@@ -144,8 +180,38 @@ export const MOCK_STREAMS: MockStreams = {
         artist: track.artist,
         album: track.album,
         album_art_b64: null,
-        position_ms: BigInt(Math.floor(tt)),
+        palette: null,
+        position_ms: BigInt(Math.floor(position)),
+        position_updated_at: BigInt(Math.floor(t)),
+        playback_rate: 1,
+        duration_ms: BigInt(track.duration),
+        url: null,
+        volume: 0.7,
+        shuffle: false,
+        repeat_mode: "off",
+        capabilities: {
+          can_play: true,
+          can_pause: true,
+          can_go_next: true,
+          can_go_previous: true,
+          can_seek: true,
+          can_set_volume: true,
+          can_shuffle: true,
+          can_repeat: true,
+        },
+        source_app: { id: "spotify", name: "Spotify", client: "spotify", icon: "spotify" },
+      };
+    },
+  },
+
+  media_tick: {
+    cadence: 1000,
+    generate: (t) => {
+      const { track, position } = mockTrack(t);
+      return {
+        position_ms: BigInt(Math.floor(position)),
         duration_ms: BigInt(track.duration),
+        playing: true,
       };
     },
   },
@@ -170,4 +236,147 @@ export const MOCK_STREAMS: MockStreams = {
         };
       }),
   },
+
+  sensors: {
+    cadence: 2000,
+    generate: (t) => [
+      {
+        id: "hwmon0/temp1",
+        device: "k10temp",
+        label: "Tctl",
+        kind: "temperature",
+        value: 48 + 22 * wave(t, 25_000) * wave(t, 7000, 1),
+        max: null,
+        critical: 95,
+      },
+      {
+        id: "hwmon1/temp1",
+        device: "nvme",
+        label: "Composite",
+        kind: "temperature",
+        value: 39 + 6 * wave(t, 60_000, 2),
+        max: 81.85,
+        critical: 84.85,
+      },
+      {
+        id: "hwmon2/fan2",
+        device: "nct6798",
+        label: "CPU Fan",
+        kind: "fan",
+        value: Math.round(900 + 700 * wave(t, 25_000) * wave(t, 7000, 1)),
+        max: null,
+        critical: null,
+      },
+      {
+        id: "hwmon2/in0",
+        device: "nct6798",
+        label: "Vcore",
+        kind: "voltage",
+        value: 1.05 + 0.2 * wave(t, 4000, 3),
+        max: 1.5,
+        critical: null,
+      },
+    ],
+  },
+
+  power: {
+    cadence: 5000,
+    generate: (t) => {
+      // Slow discharge over a ten-minute loop; full pack is 57 Wh.
+      const full = 57;
+      const now = full * (0.95 - 0.75 * ((t % 600_000) / 600_000));
+      const rate = 7 + 5 * wave(t, 40_000);
+      const timeToEmpty = BigInt(Math.round((now / rate) * 3600));
+      return {
+        ac_online: false,
+        batteries: [
+          {
+            name: "BAT0",
+            model: "5B10W13930",
+            peripheral: false,
+            state: "discharging",
+            capacity_percent: (now / full) * 100,
+            energy_now_wh: now,
+            energy_full_wh: full,
+            energy_rate_w: rate,
+            time_to_empty_secs: timeToEmpty,
+            time_to_full_secs: null,
+          },
+        ],
+        capacity_percent: (now / full) * 100,
+        time_to_empty_secs: timeToEmpty,
+        time_to_full_secs: null,
+      };
+    },
+  },
+
+  processes: {
+    cadence: 2000,
+    generate: (t) => {
+      const procs = MOCK_PROCESSES.map((p, i) => ({
+        pid: p.pid,
+        name: p.name,
+        cpu_percent: p.cpu * (0.4 + 0.6 * wave(t, 9000 + 1300 * i, i)),
+        rss: BigInt(Math.round(p.rssMiB * 1024 ** 2 * (0.9 + 0.1 * wave(t, 30_000, i)))),
+        start_time: BigInt(1_760_000_000 + 97 * p.pid),
+        user: null,
+      }));
+      return {
+        by_cpu: [...procs].sort((a, b) => b.cpu_percent - a.cpu_percent),
+        by_memory: [...procs].sort((a, b) => Number(b.rss - a.rss)),
+      };
+    },
+  },
+  gpu: {
+    cadence: 1000,
+    generate: (t) => {
+      const busy = 15 + 70 * wave(t, 20_000) ** 2;
+      return {
+        gpus: [
+          {
+            id: "card0",
+            vendor: "amd",
+            driver: "amdgpu",
+            name: null,
+            busy_percent: busy,
+            vram_used: BigInt(Math.round((1.5 + 3 * wave(t, 45_000)) * 1024 ** 3)),
+            vram_total: BigInt(8 * 1024 ** 3),
+            temperature_c: 45 + 0.35 * busy,
+            power_w: 20 + 1.8 * busy,
+            frequency_mhz: 500 + 20 * busy,
+            max_frequency_mhz: 2575,
+          },
+        ],
+        sources: [
+          { backend: "drm", devices: 1, unavailable: null },
+          { backend: "nvml", devices: 0, unavailable: "NVML not loaded" },
+        ],
+      };
+    },
+  },
+  system_info: {
+    cadence: 5000,
+    generate: (t) => {
+      const load = 0.6 + 1.4 * wave(t, 60_000);
+      return {
+        hostname: "workstation",
+        os_name: "Fedora Linux",
+        os_version: "41",
+        long_os_version: "Linux (Fedora Linux 41)",
+        kernel_version: "6.11.4-301.fc41.x86_64",
+        arch: "x86_64",
+        boot_time: BigInt(1_760_000_000),
+        uptime_secs: BigInt(3 * 86_400 + Math.floor(t / 1000)),
+        load_average: {
+          one: Math.round(load * 100) / 100,
+          five: Math.round((0.8 + 0.6 * wave(t, 300_000)) * 100) / 100,
+          fifteen: 0.9,
+        },
+        process_count: 312 + Math.round(8 * wave(t, 40_000)),
+        thread_count: 1480 + Math.round(40 * wave(t, 40_000)),
+        user: "alex",
+        logged_in_users: ["alex"],
+      };
+    },
+  },
 };
@@ -13,15 +13,17 @@ export function MediaControls() {
   const { data } = useSubscription("media");
   const active = data?.active ?? false;
   const playing = data?.playing ?? null;
+  const can = data?.capabilities;
+  const canToggle = playing ? can?.can_pause : can?.can_play;
   return (
     <div className={styles.container}>
-      <button disabled={!active} className={styles.control} onClick={(_) => ipc.prevTrack()}>
+      <button disabled={!active || !can?.can_go_previous} className={styles.control} onClick={(_) => ipc.prevTrack()}>
         <BackwardIcon />
       </button>
-      <button disabled={!active} className={styles.control} onClick={(_) => ipc.togglePlayback()}>
+      <button disabled={!active || !canToggle} className={styles.control} onClick={(_) => ipc.togglePlayback()}>
         {playing ? <PauseIcon /> : <PlayIcon />}
       </button>
-      <button disabled={!active} className={styles.control} onClick={(_) => ipc.nextTrack()}>
+      <button disabled={!active || !can?.can_go_next} className={styles.control} onClick={(_) => ipc.nextTrack()}>
         <ForwardIcon />
       </button>
     </div>
@@ -1,13 +1,35 @@
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import { registerWidget } from "../../registry/defRegistry";
 import { useSubscription } from "../../hooks";
+import type { MediaState } from "../../ffi_types";
 import styles from "./styles/MediaProgressWidget.module.css";
 import { formatMs } from "../../utils/format";
 
+/** The position at `now` (Unix ms): the backend only re-sends the state when
+ *  it changes, so while playing it has to be carried on from the last one. */
+export function positionAt(state: MediaState, now: number): number {
+  const position = Number(state.position_ms);
+  if (!state.playing) return position;
+  const elapsed = Math.max(0, now - Number(state.position_updated_at));
+  const extrapolated = position + elapsed * state.playback_rate;
+  const duration = Number(state.duration_ms);
+  return duration > 0 ? Math.min(extrapolated, duration) : extrapolated;
+}
+
 export function MediaProgress() {
   const { data } = useSubscription("media");
 
-  const { position_ms, duration_ms, playing, title } = data || {};
+  const { position_ms, position_updated_at, playback_rate, duration_ms, playing, title } =
+    data || {};
+
+  // Only the elapsed text needs re-rendering as time passes; the bar animates itself.
+  const [now, setNow] = useState(() => Date.now());
+  useEffect(() => {
+    if (!playing) return;
+    setNow(Date.now());
+    const id = setInterval(() => setNow(Date.now()), 1000);
+    return () => clearInterval(id);
+  }, [playing]);
 
   const barRef = useRef<HTMLDivElement>(null);
   const animRef = useRef<Animation | null>(null);
@@ -21,7 +43,8 @@ export function MediaProgress() {
       [{ width: "0%" }, { width: "100%" }],
       { duration, fill: "forwards" },
     );
-    animRef.current.currentTime = Number(position_ms);
+    if (data) animRef.current.currentTime = positionAt(data, Date.now());
+    animRef.current.playbackRate = playback_rate ?? 1;
     playing ? animRef.current.play() : animRef.current.pause();
     return () => {
       animRef.current?.cancel();
@@ -30,18 +53,20 @@ export function MediaProgress() {
   }, [duration, title]);
 
   function resync() {
-    if (!animRef.current) return;
+    if (!animRef.current || !data) return;
+    animRef.current.playbackRate = data.playback_rate;
+    const expected = positionAt(data, Date.now());
     const t = animRef.current.currentTime;
-    if (typeof t === "number" && Math.abs(t - Number(position_ms)) > 250) {
-      animRef.current.currentTime = Number(position_ms);
+    if (typeof t === "number" && Math.abs(t - expected) > 250) {
+      animRef.current.currentTime = expected;
     }
   }
 
-  // Resync only if drifted beyond threshold
+  // Resync on a new reading, only if drifted beyond threshold
   useEffect(() => {
     if (!animRef.current) return;
     resync();
-  }, [position_ms]);
+  }, [position_ms, position_updated_at, playback_rate]);
 
   // Sync play/pause state
   useEffect(() => {
@@ -52,7 +77,7 @@ export function MediaProgress() {
   return (
     <div className={styles.progressContainer}>
       <div className={styles.timeContainer}>
-        <span className={styles.elapsed}>{formatMs(Number(position_ms))}</span>
+        <span className={styles.elapsed}>{formatMs(data ? positionAt(data, now) : 0)}</span>
         <span className={styles.duration}>{formatMs(duration)}</span>
       </div>
       <div className={styles.barContainer}>
@@ -1,13 +1,14 @@
 import { registerWidget } from "../../registry/defRegistry";
 import { Download } from "./DownloadWidget";
+import { INTERFACE_FILTER_SETTINGS, InterfaceFilter } from "./interfaces";
 import { Upload } from "./UploadWidget";
 import styles from "./styles/BandwidthWidget.module.css";
 
-export function Bandwidth() {
+export function Bandwidth(filter: InterfaceFilter) {
   return (
     <div className={styles.container}>
-      <Download />
-      <Upload />
+      <Download {...filter} />
+      <Upload {...filter} />
     </div>
   );
 }
@@ -17,8 +18,8 @@ const BandwidthWidget = registerWidget(Bandwidth, {
   name: "Bandwidth",
   description: "Shows network upload and download speeds",
   category: "system",
-  tags: [],
-  settingsDef: {},
+  tags: ["customizable"],
+  settingsDef: INTERFACE_FILTER_SETTINGS,
   minSize: [null, null],
   maxSize: [null, null],
 });
@@ -1,13 +1,19 @@
-import { useHistory, useSubscription } from "../../hooks";
+import { useStreamHistory, useSubscription } from "../../hooks";
 import { LineGraph, Readout } from "../../primitives";
 import { registerWidget } from "../../registry/defRegistry";
 import { formatBps } from "../../utils/format";
+import {
+  INTERFACE_FILTER_SETTINGS,
+  InterfaceFilter,
+  sumInterfaces,
+} from "./interfaces";
 
-export function Download() {
+export function Download(filter: InterfaceFilter) {
   const { data } = useSubscription("networks");
-  const rx =
-    data?.reduce((sum, iface) => sum + Number(iface.received), 0) ?? 0;
-  const rxHistory = useHistory(rx);
+  const rx = sumInterfaces(data, filter, "received_per_sec");
+  const rxHistory = useStreamHistory("networks", data ? rx : null, (tick) =>
+    sumInterfaces(data, filter, "received_per_sec", tick),
+  );
 
   const peak = Math.max(...rxHistory, 1);
   return (
@@ -27,11 +33,11 @@ const DownloadWidget = registerWidget(Download, {
   id: "download",
   name: "Download Speed",
   description: "Shows current network download speed",
-  tags: [],
+  tags: ["customizable"],
   category: "system",
   maxSize: [null, null],
   minSize: [null, null],
-  settingsDef: {},
+  settingsDef: INTERFACE_FILTER_SETTINGS,
 });
 
 export default DownloadWidget;
@@ -1,14 +1,19 @@
-import { useHistory, useSubscription } from "../../hooks";
+import { useStreamHistory, useSubscription } from "../../hooks";
 import { LineGraph, Readout } from "../../primitives";
 import { registerWidget } from "../../registry/defRegistry";
 import { formatBps } from "../../utils/format";
+import {
+  INTERFACE_FILTER_SETTINGS,
+  InterfaceFilter,
+  sumInterfaces,
+} from "./interfaces";
 
-export function Upload() {
+export function Upload(filter: InterfaceFilter) {
   const { data } = useSubscription("networks");
-  const tx =
-    data?.reduce((sum, iface) => sum + Number(iface.transmitted), 0) ??
-    0;
-  const txHistory = useHistory(tx);
+  const tx = sumInterfaces(data, filter, "transmitted_per_sec");
+  const txHistory = useStreamHistory("networks", data ? tx : null, (tick) =>
+    sumInterfaces(data, filter, "transmitted_per_sec", tick),
+  );
 
   const peak = Math.max(...txHistory, 1);
   return (
@@ -29,10 +34,10 @@ const UploadWidget = registerWidget(Upload, {
   name: "Upload Speed",
   description: "Shows current network upload speed",
   category: "system",
-  tags: [],
+  tags: ["customizable"],
   maxSize: [null, null],
   minSize: [null, null],
-  settingsDef: {},
+  settingsDef: INTERFACE_FILTER_SETTINGS,
 });
 
 export default UploadWidget;
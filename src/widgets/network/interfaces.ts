@@ -0,0 +1,53 @@
+import type { NetworkInterfaceInfo } from "../../ffi_types";
+import type { HistoryTick } from "../../hooks";
+import type {
+  WidgetSettingsDefinition,
+  WidgetSettingsProps,
+} from "../../registry/defRegistry";
+
+/** Shared by the bandwidth widgets. Both default off: loopback traffic never
+ *  leaves the machine, and a VPN tunnel's traffic is counted again on the
+ *  physical interface carrying it. */
+export const INTERFACE_FILTER_SETTINGS = {
+  includeLoopback: {
+    type: "boolean",
+    label: "Include loopback",
+    default: false,
+  },
+  includeVirtual: {
+    type: "boolean",
+    label: "Include virtual interfaces (VPNs, bridges, containers)",
+    default: false,
+  },
+} satisfies WidgetSettingsDefinition;
+
+export type InterfaceFilter = WidgetSettingsProps<
+  typeof INTERFACE_FILTER_SETTINGS
+>;
+
+export function visibleInterfaces(
+  interfaces: NetworkInterfaceInfo[] | null,
+  filter: InterfaceFilter,
+): NetworkInterfaceInfo[] {
+  // Loopback is also virtual on Linux; its own setting decides it.
+  return (interfaces ?? []).filter((iface) =>
+    iface.loopback
+      ? filter.includeLoopback
+      : filter.includeVirtual || !iface.virtual_device,
+  );
+}
+
+/** Sums `field` over the visible interfaces, from a live payload or from a
+ *  recorded history tick (whose metrics are `<interface>/<field>`). */
+export function sumInterfaces(
+  interfaces: NetworkInterfaceInfo[] | null,
+  filter: InterfaceFilter,
+  field: "received_per_sec" | "transmitted_per_sec",
+  tick?: HistoryTick,
+): number {
+  return visibleInterfaces(interfaces, filter).reduce(
+    (sum, iface) =>
+      sum + (tick ? (tick[`${iface.name}/${field}`] ?? 0) : iface[field]),
+    0,
+  );
+}